@@ -0,0 +1,334 @@
+//! Measures how many Connect 4 positions the CPU search can visit per second,
+//! with the bitboards `Connect4` uses and with the cell array it used before
+//!
+//! Run with `cargo bench` from `client/cli`. Every position is visited the same
+//! way the minimax does it: the board is copied, a piece is dropped, and the
+//! leaves are scored with `calculate_score`. Both boards should print the same
+//! checksum, which shows the bitboards didn't change the scores themselves.
+use std::time::Instant;
+
+// The game modules are shared with the web client and the CLI, which use the
+// parts of them the benchmark doesn't, like undoing moves and setting up positions
+#[allow(dead_code)]
+#[path = "../../src/connect4/connect4.rs"]
+mod connect4;
+use connect4::Connect4;
+
+#[path = "../../src/connect4/connect4_move.rs"]
+mod connect4_move;
+use connect4_move::Connect4Move;
+
+#[path = "../../src/connect4/piece_color.rs"]
+mod piece_color;
+use piece_color::PieceColor;
+
+#[allow(dead_code)]
+#[path = "../../src/connect4/rules.rs"]
+mod rules;
+
+#[path = "../../src/connect4/weights.rs"]
+mod weights;
+
+#[allow(dead_code)]
+#[path = "../../src/engine"]
+mod engine {
+    pub mod game;
+    pub mod weights;
+    pub mod zobrist;
+}
+use engine::game::Game;
+
+#[allow(dead_code)]
+#[path = "../../src/types"]
+mod types {
+    pub mod move_error;
+}
+
+const SEARCH_DEPTH: usize = 7;
+
+/// The board as it was before bitboards, kept to compare against
+///
+/// It scans its cell array for every line, building a `Vec` for each window
+mod array_board {
+    use super::piece_color::{PieceColor, PieceColor::*};
+
+    pub const NUM_COLS: usize = 7;
+    pub const NUM_ROWS: usize = 6;
+
+    type BoardCell = Option<PieceColor>;
+
+    #[derive(Copy, Clone)]
+    pub struct Connect4 {
+        pub board: [[BoardCell; NUM_COLS]; NUM_ROWS],
+        pub active_player: PieceColor,
+        pub moves_played: usize,
+        pub is_terminal: bool,
+        pub winner: Option<PieceColor>,
+        pub column_heights: [usize; NUM_COLS],
+    }
+
+    impl Connect4 {
+        pub fn new() -> Self {
+            Connect4 {
+                board: [[None; NUM_COLS]; NUM_ROWS],
+                moves_played: 0,
+                active_player: RED,
+                is_terminal: false,
+                winner: None,
+                column_heights: [0; NUM_COLS],
+            }
+        }
+
+        pub fn drop(&mut self, col: usize) -> bool {
+            if col >= NUM_COLS || self.column_heights[col] == NUM_ROWS {
+                return false;
+            }
+
+            let row = NUM_ROWS - 1 - self.column_heights[col];
+            self.board[row][col] = Some(self.active_player);
+            self.moves_played += 1;
+            self.winner = match self.check_for_win(self.active_player) {
+                Some(_) => Some(self.active_player),
+                None => None,
+            };
+            self.is_terminal = self.winner.is_some() || self.moves_played == NUM_COLS * NUM_ROWS;
+            self.column_heights[col] += 1;
+            self.active_player = self.active_player.switch();
+
+            true
+        }
+
+        pub fn check_for_win(&self, color: PieceColor) -> Option<Vec<[usize; 2]>> {
+            let is_win = |window: &[BoardCell]| window.iter().all(|&cell| cell == Some(color));
+
+            for row in 0..NUM_ROWS {
+                for start_col in 0..NUM_COLS - 3 {
+                    if is_win(&self.board[row][start_col..start_col + 4]) {
+                        return Some((0..4).map(|i| [row, start_col + i]).collect());
+                    }
+                }
+            }
+
+            for col in 0..NUM_COLS {
+                for start_row in 0..NUM_ROWS - 3 {
+                    let window: Vec<BoardCell> = (start_row..start_row + 4)
+                        .map(|row| self.board[row][col])
+                        .collect();
+                    if is_win(&window) {
+                        return Some((0..4).map(|i| [start_row + i, col]).collect());
+                    }
+                }
+            }
+
+            for row in NUM_ROWS - 3..NUM_ROWS {
+                for col in 0..NUM_COLS - 3 {
+                    let window: Vec<BoardCell> =
+                        (0..4).map(|i| self.board[row - i][col + i]).collect();
+                    if is_win(&window) {
+                        return Some((0..4).map(|i| [row - i, col + i]).collect());
+                    }
+                }
+            }
+
+            for row in 0..NUM_ROWS - 3 {
+                for col in 0..NUM_COLS - 3 {
+                    let window: Vec<BoardCell> =
+                        (0..4).map(|i| self.board[row + i][col + i]).collect();
+                    if is_win(&window) {
+                        return Some((0..4).map(|i| [row + i, col + i]).collect());
+                    }
+                }
+            }
+
+            None
+        }
+
+        pub fn calculate_score(&self, color: PieceColor) -> i32 {
+            let mut score = 0;
+
+            let calculate_window_score = |window: &[(BoardCell, bool)]| -> i32 {
+                let mut own_count = 0;
+                let mut opponent_count = 0;
+                let mut empty_no_below_count = 0;
+                let mut empty_with_below_count = 0;
+
+                for &(cell, has_below) in window.iter() {
+                    match (cell, has_below) {
+                        (None, false) => empty_no_below_count += 1,
+                        (None, true) => empty_with_below_count += 1,
+                        (Some(c), _) if c == color => own_count += 1,
+                        (Some(_), _) => opponent_count += 1,
+                    }
+                }
+
+                if own_count > 0 && opponent_count > 0 {
+                    return 0;
+                }
+
+                match (
+                    own_count,
+                    opponent_count,
+                    empty_with_below_count,
+                    empty_no_below_count,
+                ) {
+                    (4, 0, 0, 0) => 100_000,
+                    (3, 0, _, _) => 10,
+                    (2, 0, _, _) => 1,
+                    (0, 3, 1, 0) => -10_000,
+                    (0, 3, 0, 1) => -20,
+                    (0, 2, _, _) => -3,
+                    _ => 0,
+                }
+            };
+
+            // A cell is "with below" when the column is filled up to it
+            let cell = |row: usize, col: usize| {
+                (
+                    self.board[row][col],
+                    self.column_heights[col] >= NUM_ROWS - row - 1,
+                )
+            };
+
+            for row in 0..NUM_ROWS {
+                for start_col in 0..NUM_COLS - 3 {
+                    let window: Vec<(BoardCell, bool)> = (start_col..start_col + 4)
+                        .map(|col| cell(row, col))
+                        .collect();
+                    score += calculate_window_score(&window);
+                }
+            }
+
+            for col in 0..NUM_COLS {
+                for start_row in 0..NUM_ROWS - 3 {
+                    let window: Vec<(BoardCell, bool)> = (start_row..start_row + 4)
+                        .map(|row| (self.board[row][col], true))
+                        .collect();
+                    score += calculate_window_score(&window);
+                }
+            }
+
+            for row in NUM_ROWS - 3..NUM_ROWS {
+                for col in 0..NUM_COLS - 3 {
+                    let window: Vec<(BoardCell, bool)> =
+                        (0..4).map(|i| cell(row - i, col + i)).collect();
+                    score += calculate_window_score(&window);
+                }
+            }
+
+            for row in 0..NUM_ROWS - 3 {
+                for col in 0..NUM_COLS - 3 {
+                    let window: Vec<(BoardCell, bool)> =
+                        (0..4).map(|i| cell(row + i, col + i)).collect();
+                    score += calculate_window_score(&window);
+                }
+            }
+
+            for row in 0..NUM_ROWS {
+                if self.board[row][3] == Some(color) {
+                    score += 5;
+                }
+            }
+
+            score
+        }
+
+        pub fn get_columns(&self) -> [usize; NUM_COLS] {
+            [3, 2, 4, 1, 5, 0, 6]
+        }
+    }
+}
+
+/// Visits every position `depth` plies ahead, returning how many were visited
+fn count_nodes(board: &Connect4, depth: usize, score: &mut i64) -> u64 {
+    if board.is_terminal() || depth == 0 {
+        *score += board.evaluate(PieceColor::YELLOW) as i64;
+        return 1;
+    }
+
+    let mut nodes = 1;
+    for mv in board.legal_moves() {
+        let mut copy_board = *board;
+        copy_board.apply(mv);
+        nodes += count_nodes(&copy_board, depth - 1, score);
+    }
+
+    nodes
+}
+
+/// Visits every position `depth` plies ahead on the cell array board
+fn count_array_nodes(board: &array_board::Connect4, depth: usize, score: &mut i64) -> u64 {
+    if board.is_terminal || depth == 0 {
+        *score += board.calculate_score(PieceColor::YELLOW) as i64;
+        return 1;
+    }
+
+    let mut nodes = 1;
+    for &col in board.get_columns().iter() {
+        let mut copy_board = *board;
+        if copy_board.drop(col) {
+            nodes += count_array_nodes(&copy_board, depth - 1, score);
+        }
+    }
+
+    nodes
+}
+
+/// Times a count of the nodes, printing and returning how many there were and
+/// how long it took
+fn measure(name: &str, count: impl FnOnce(&mut i64) -> u64) -> (u64, f64) {
+    let mut score = 0;
+    let start = Instant::now();
+    let nodes = count(&mut score);
+    let seconds = start.elapsed().as_secs_f64();
+
+    println!(
+        "  {:<10} {:>9} nodes in {:.3}s ({:.0} nodes/s, checksum {})",
+        name,
+        nodes,
+        seconds,
+        nodes as f64 / seconds,
+        score
+    );
+
+    (nodes, seconds)
+}
+
+fn main() {
+    // An empty board, an early middlegame and a crowded late middlegame
+    let openings: [&[usize]; 3] = [&[], &[3, 3, 2, 4, 2], &[3, 2, 3, 3, 4, 4, 2, 5, 2, 3, 4, 1]];
+
+    // The nodes and seconds of the bitboards, then of the cell array
+    let mut totals = [(0, 0.0); 2];
+
+    for moves in openings.iter() {
+        let mut board = Connect4::new();
+        let mut array_board = array_board::Connect4::new();
+        for &col in moves.iter() {
+            board.apply(Connect4Move::Drop(col));
+            array_board.drop(col);
+        }
+
+        println!("{} moves played:", moves.len());
+        let results = [
+            measure("bitboards", |score| {
+                count_nodes(&board, SEARCH_DEPTH, score)
+            }),
+            measure("cell array", |score| {
+                count_array_nodes(&array_board, SEARCH_DEPTH, score)
+            }),
+        ];
+
+        for (total, (nodes, seconds)) in totals.iter_mut().zip(results.iter()) {
+            total.0 += nodes;
+            total.1 += seconds;
+        }
+    }
+
+    let nodes_per_second = |(nodes, seconds): (u64, f64)| nodes as f64 / seconds;
+    println!(
+        "total: {:.0} nodes/s with bitboards, {:.0} nodes/s with the cell array, {:.1}x faster",
+        nodes_per_second(totals[0]),
+        nodes_per_second(totals[1]),
+        nodes_per_second(totals[0]) / nodes_per_second(totals[1])
+    );
+}
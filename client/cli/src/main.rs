@@ -1,65 +1,161 @@
 use std::io;
-use strum_macros::EnumIter;
-#[path = "../../src/toot_and_otto/player.rs"]
-mod player;
-use player::Player;
-
-#[path = "../../src/connect4/connect4.rs"]
-mod connect4;
-use connect4::Connect4;
+#[path = "../../src/connect4"]
+mod connect4 {
+    pub mod connect4;
+    pub mod connect4_move;
+    pub mod cpu_con4;
+    pub mod piece_color;
+    pub mod position;
+    pub mod record;
+    pub mod rules;
+    pub mod solver;
+    pub mod threats;
+    pub mod weights;
+}
+use connect4::{
+    connect4::Connect4, connect4_move::Connect4Move, cpu_con4, piece_color::PieceColor, rules::Connect4Rules,
+    solver::Solver, threats,
+};
+
+#[path = "../../src/toot_and_otto"]
+mod toot_and_otto {
+    pub mod cpu_toot;
+    pub mod piece_letter;
+    pub mod player;
+    pub mod position;
+    pub mod record;
+    pub mod rules;
+    pub mod solver;
+    pub mod toot_and_otto;
+    pub mod weights;
+}
+use toot_and_otto::{
+    cpu_toot, piece_letter::PieceLetter, player::Player, rules::TootAndOttoRules,
+    solver as toot_solver, toot_and_otto::TootAndOtto,
+};
+
+#[path = "../../src/engine"]
+mod engine {
+    pub mod config;
+    pub mod game;
+    pub mod mcts;
+    pub mod minimax;
+    pub mod position;
+    pub mod record;
+    pub mod transposition;
+    pub mod weights;
+    pub mod zobrist;
+}
 
-#[path = "../../src/connect4/piece_color.rs"]
-mod piece_color;
-use piece_color::PieceColor;
+#[path = "../../src/types"]
+mod types {
+    pub mod move_error;
+    pub mod opponent;
+    pub mod spectator;
+}
+use types::{opponent::Opponent, spectator::Spectator};
+use engine::{
+    config::{EngineConfig, TieBreak},
+    game::{Game, Outcome},
+    mcts::MoveStats,
+    minimax::{format_score, SearchReport},
+    record::{self, Notation, Record},
+    transposition::Bound,
+};
+use std::fmt::Display;
 
-#[path = "../../src/connect4/cpu_con4.rs"]
-mod cpu_con4;
+// use piece_letter::PieceLetter;
 
-#[path = "../../src/toot_and_otto/toot_and_otto.rs"]
-mod toot_and_otto;
-use toot_and_otto::TootAndOtto;
+/// Reads how the CPU picks its moves from the command line, like
+/// `--seed 42 --tie-break center-first --temperature 20`
+///
+/// The same seed makes the CPU answer the same moves the same way, so a game
+/// can be replayed exactly. `--threads 4` splits its search between 4 threads
+fn get_engine_config() -> Result<EngineConfig, String> {
+    let mut config = EngineConfig::DEFAULT;
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let value = args
+            .next()
+            .ok_or_else(|| format!("{} needs a value after it", arg))?;
+
+        match arg.as_str() {
+            "--seed" => {
+                config.seed = Some(
+                    value
+                        .parse()
+                        .map_err(|_| String::from("--seed needs a whole number"))?,
+                )
+            }
+            "--tie-break" => config.tie_break = TieBreak::parse(&value)?,
+            "--temperature" => {
+                config.temperature = value
+                    .parse()
+                    .map_err(|_| String::from("--temperature needs a number"))?
+            }
+            "--threads" => {
+                config.threads = match value.parse() {
+                    Ok(threads) if threads > 0 => threads,
+                    _ => return Err(String::from("--threads needs a whole number above 0")),
+                }
+            }
+            _ => {
+                return Err(format!(
+                    "'{}' isn't an option, use --seed, --tie-break, --temperature or --threads",
+                    arg
+                ))
+            }
+        }
+    }
 
-#[path = "../../src/toot_and_otto/piece_letter.rs"]
-mod piece_letter;
-use piece_letter::PieceLetter;
+    Ok(config)
+}
 
-#[path = "../../src/toot_and_otto/cpu_toot.rs"]
-mod cpu_toot;
+/// Reads a line of input from the user, handles any errors
+fn read_input() -> String {
+    let mut input = String::new();
+    match io::stdin().read_line(&mut input) {
+        // The input was closed, so no more moves are coming
+        Ok(0) => std::process::exit(0),
+        Ok(_) => {}
+        Err(_) => println!("Input failed, try again"),
+    };
 
-// use piece_letter::PieceLetter;
+    input.trim().to_string()
+}
 
-// Gets input from the user and returns a usize and a tuple
-fn get_input_connect4() -> (usize, bool) {
-    // Gets input from the user, handles any errors
-    let mut col = String::new();
-    if let Err(_) = io::stdin().read_line(&mut col) {
-        println!("Input failed, try again");
-        return (0, false);
+// Parses the users input and returns a move and a tuple
+fn get_input_connect4(input: &str) -> (Connect4Move, bool) {
+    // A p in front of the column pops a piece out instead of dropping one
+    let (input, is_pop) = match input.strip_prefix(|c| c == 'p' || c == 'P') {
+        Some(col) => (col.trim(), true),
+        None => (input, false),
     };
 
     // Parses the users input to a usize
-    let col: usize = match col.trim().parse() {
+    let col: usize = match input.parse() {
         Err(_) => {
             println!("Invalid Input, Try Again");
-            return (0, false);
+            return (Connect4Move::Drop(0), false);
         }
         Ok(col) => col,
     };
 
-    (col, true)
+    match is_pop {
+        true => (Connect4Move::Pop(col), true),
+        false => (Connect4Move::Drop(col), true),
+    }
 }
 
-/// Gets input from the user and returns a usize and a tuple
-fn get_input_toot_and_otto() -> (char, usize, bool) {
-    // Gets input from the user, handles any errors
-    let mut input = String::new();
-    if let Err(_) = io::stdin().read_line(&mut input) {
-        println!("Input failed, try again");
-        return ('X', 0, false);
-    };
-
+/// Parses the users input and returns a usize and a tuple
+fn get_input_toot_and_otto(input: &str) -> (char, usize, bool) {
     // Splits the input
-    let parameters: Vec<&str> = input.trim().split_whitespace().collect();
+    let parameters: Vec<&str> = input.split_whitespace().collect();
+    if parameters.len() != 2 {
+        println!("Invalid Input, Try Again");
+        return ('X', 0, false);
+    }
 
     // Parses the first parameter of the users input
     let letter: char = match parameters[0].parse() {
@@ -82,110 +178,251 @@ fn get_input_toot_and_otto() -> (char, usize, bool) {
     (letter, col, true)
 }
 
-fn connect4_cli(lvl : &str) {
-    let mut depth;
-    match lvl {     // difficult depth level mapping
-        "1" => {
-            depth = 0;
-        },
-        "2" => {
-            depth = 1;
-        },
-        "3" => {
-            depth = 2;
-        },
-        "4" => {
-            depth = 3;
-        },
+/// Takes back or replays a turn when the input is "undo" or "redo", returns
+/// false for any other input
+///
+/// Against the CPU its reply is taken back or replayed along with the player's move
+fn history_command<G: Game>(game: &mut G, input: &str, vs: Opponent, player: G::Player) -> bool {
+    let is_done = match (input, vs) {
+        ("undo", Opponent::Human) => game.undo(),
+        ("undo", _) => game.undo_turn(player),
+        ("redo", Opponent::Human) => game.redo(),
+        ("redo", _) => game.redo_turn(player),
+        _ => return false,
+    };
+
+    if !is_done {
+        println!("There are no moves to {}", input);
+    }
+
+    true
+}
+
+/// Writes down the game played on `board` by the human against `vs`, with the
+/// CPU's name first when it moved first
+fn game_record<G: Notation>(board: &G, vs: Opponent, cpu_first: bool) -> Record<G::Move> {
+    let opponent = match vs {
+        Opponent::Human => "Human",
+        _ => "CPU",
+    };
+    let players = match cpu_first {
+        true => [String::from(opponent), String::from("Human")],
+        false => [String::from("Human"), String::from(opponent)],
+    };
+
+    Record::new(
+        board,
+        players,
+        vs.to_string(),
+        record::today(),
+    )
+}
+
+/// Maps the menu level to who the human plays against
+fn get_opponent(lvl: &str) -> Opponent {
+    match lvl {
+        "1" => Opponent::Human,
+        "2" => Opponent::EasyCPU,
+        "3" => Opponent::MediumCPU,
+        "4" => Opponent::HardCPU,
+        "5" => Opponent::PerfectCPU,
+        "6" => Opponent::MctsCPU,
         _ => {
             println!("There is an invalid game setting, thus defaulting to human v human game");
-            depth = 0;
+            Opponent::Human
         }
     }
+}
 
-    // Initializes the game
-    let mut connect4 = Connect4::new();
-    let mut active_player = PieceColor::YELLOW;
+/// Asks whether the CPU should make the first move, never when playing a human
+fn get_cpu_first(vs: Opponent) -> bool {
+    if vs == Opponent::Human {
+        return false;
+    }
 
-    // Displays the empty board before the start of the game
-    println!("{}", connect4);
+    println!("Who moves first?");
+    println!("1. You");
+    println!("2. The CPU");
+
+    read_input() == "2"
+}
+
+/// Asks for the variant or size of the Connect 4 board until a valid one that
+/// every CPU in `engines` can play is entered
+fn get_connect4_rules(engines: &[Opponent]) -> Connect4Rules {
+    println!("Pick a variant, or enter a board size like 9x7 connect 5 or 7x6 connect 4 pop out misere");
+    println!("1. Standard ({})", Connect4Rules::STANDARD);
+    println!("2. Misère, a line loses ({})", Connect4Rules::MISERE);
+    println!("3. Five in a Row ({})", Connect4Rules::FIVE_IN_A_ROW);
+    println!("4. Pop Out ({})", Connect4Rules::POP_OUT);
 
     loop {
-        let winner = match connect4.check_for_win(active_player) {
-            Some(_) => Some(active_player),
-            None => None,
-        };        // Switch Players
+        let input = read_input();
+        let rules = match input.as_str() {
+            "" | "1" => Ok(Connect4Rules::STANDARD),
+            "2" => Ok(Connect4Rules::MISERE),
+            "3" => Ok(Connect4Rules::FIVE_IN_A_ROW),
+            "4" => Ok(Connect4Rules::POP_OUT),
+            _ => Connect4Rules::parse(&input),
+        };
+
+        match rules {
+            Ok(rules) if engines.iter().all(|&engine| cpu_con4::can_play(engine, rules)) => return rules,
+            Ok(_) => println!("The perfect CPU only plays the {} board, try again", Connect4Rules::STANDARD),
+            Err(err) => println!("{}, try again", err),
+        }
+    }
+}
+
+/// Asks for the size of the TOOT and OTTO board and the letters each player
+/// gets until valid ones that every CPU in `engines` can play are entered
+fn get_toot_and_otto_rules(engines: &[Opponent]) -> TootAndOttoRules {
+    println!(
+        "Enter the board size and letters, for example 8x6 with 12 of each letter or 8x6 with unlimited letters, or nothing for {}",
+        TootAndOttoRules::STANDARD
+    );
 
-        if winner.is_some() {
-            break;
+    loop {
+        let input = read_input();
+        let rules = match input.as_str() {
+            "" => Ok(TootAndOttoRules::STANDARD),
+            _ => TootAndOttoRules::parse(&input),
+        };
+
+        match rules {
+            Ok(rules) if engines.iter().all(|&engine| cpu_toot::can_play(engine, rules)) => return rules,
+            Ok(_) => println!("The perfect CPU only plays {}, try again", TootAndOttoRules::STANDARD),
+            Err(err) => println!("{}, try again", err),
         }
+    }
+}
+
+fn connect4_cli(lvl : &str, config: EngineConfig) {
+    let vs = get_opponent(lvl);
+    let cpu_first = get_cpu_first(vs);
+    let user = match cpu_first {
+        true => PieceColor::YELLOW,
+        false => PieceColor::RED,
+    };
 
-        active_player = active_player.switch();
+    // Initializes the game
+    let mut connect4 = Connect4::with_rules(get_connect4_rules(&[vs]));
+
+    // Displays the empty board before the start of the game
+    println!("{}", connect4);
+
+    while !connect4.is_terminal {
+        let active_player = connect4.active_player;
 
         // Display who's turn it is, prompt for a column input
         println!("It is the {} player's turn", active_player);
         println!("==========================");
-        println!("Enter the column you want to drop your piece in (0-6)");
+        println!(
+            "Enter the column you want to drop your piece in (0-{}), or undo or redo a move",
+            connect4.rules.num_cols - 1
+        );
+        if connect4.rules.pop_out {
+            println!("Put a p in front of the column to pop your piece out of the bottom, like p3");
+        }
 
-        if (active_player == PieceColor::RED) || (depth == 0) {
-            let mut column: usize;
+        if (active_player == user) || (vs == Opponent::Human) {
+            let mut mv: Connect4Move;
             let mut is_valid = false;
             // Gets input from the user until their input is valid
             while !is_valid {
-                let result = get_input_connect4();
-                column = result.0;
+                let input = read_input();
+                if history_command(&mut connect4, &input, vs, user) {
+                    break;
+                }
+
+                let result = get_input_connect4(&input);
+                mv = result.0;
                 is_valid = result.1;
                 if !is_valid {
                     continue;
                 }
-                is_valid = connect4.drop(column);
+                is_valid = match connect4.play(mv) {
+                    Ok(()) => true,
+                    Err(err) => {
+                        println!("{}, try again", err);
+                        false
+                    }
+                };
             }
         } else {
-            connect4.drop(cpu_con4::make_move(connect4.clone(), depth));
+            let mv = match vs.mcts_limits() {
+                Some(limits) => {
+                    let moves = cpu_con4::mcts_search(connect4, user.switch(), limits, config)
+                        .expect("The CPU moves on its own turn");
+                    print_mcts_report::<Connect4>(&moves);
+                    moves[0].mv
+                }
+                None => match vs.search_limits() {
+                    Some(limits) => {
+                        let report = cpu_con4::minimax_search(connect4, user.switch(), limits, config)
+                            .expect("The CPU moves on its own turn");
+                        print_search_report::<Connect4>(&report);
+                        report.best_move
+                    }
+                    None => cpu_con4::pick_move(connect4, user.switch(), vs, config)
+                        .expect("The CPU moves on its own turn"),
+                },
+            };
+            println!("The CPU plays {}", mv);
+            connect4.play(mv).expect("The CPU only picks moves that can be played");
         }
 
         // Displays the board after the input
         println!("{}", connect4);
     }
 
-    println!("{} player won!", active_player);
-}
-
-fn toot_and_otto_cli(lvl : &str) {
-    let mut depth;
-    match lvl { // cpu search depth
-        "1" => {
-            depth = 0;
-        },
-        "2" => {
-            depth = 1;
-        },
-        "3" => {
-            depth = 2;
-        },
-        "4" => {
-            depth = 3;
-        },
-        _ => {
-            println!("There is an invalid game setting, thus defaulting to human game");
-            depth = 0;
+    match connect4.winner {
+        Some(player) => {
+            println!("{} player won!", player);
+
+            // In misère it's the loser who finished a line
+            let line_owner = match connect4.rules.misere {
+                true => player.switch(),
+                false => player,
+            };
+            if let Some(line) = connect4.check_for_win(line_owner) {
+                println!("{} in a row at (row, column) {:?}", line.len(), line);
+            }
         }
+        None => println!("Game drawn :)"),
     }
 
-    // Initializes the game
-    let mut toot_and_otto = TootAndOtto::new();
+    // The position can be solved from the main menu, and the record replayed
+    println!("Final position: {}", connect4.position());
+    println!("Game record:\n\n{}", connect4::record::format(&game_record(&connect4, vs, cpu_first)));
+}
 
-    let mut active_player = Player::TOOT;
+fn toot_and_otto_cli(lvl : &str, config: EngineConfig) {
+    let vs = get_opponent(lvl);
+    let cpu_first = get_cpu_first(vs);
+    let user = match cpu_first {
+        true => Player::OTTO,
+        false => Player::TOOT,
+    };
+
+    // Initializes the game
+    let mut toot_and_otto = TootAndOtto::with_rules(get_toot_and_otto_rules(&[vs]));
 
     // Displays the empty board before the start of the game
     println!("{}", toot_and_otto);
 
-    loop {
+    // Unlike connect for, either player could win on any given move
+    // Both players could also win of the piece dropped forms "TOOT"
+    // and "OTTO" simultaneously
+    while !toot_and_otto.is_terminal {
+        let active_player = toot_and_otto.active_player;
+
         // Display who's turn it is, prompt for a column input
         println!("It is {}'s turn", active_player);
         println!("==========================");
         println!(
-            "Enter the letter you want to drop (O, T) and the column you want to drop it in (0-5)"
+            "Enter the letter you want to drop (O, T) and the column you want to drop it in (0-{}), or undo or redo a move",
+            toot_and_otto.rules.num_cols - 1
         );
 
         let mut column: usize;
@@ -193,9 +430,14 @@ fn toot_and_otto_cli(lvl : &str) {
         let mut is_valid = false;
 
         // Gets input from the user until their input is valid
-        if (depth == 0) || (active_player == Player::TOOT) {
+        if (vs == Opponent::Human) || (active_player == user) {
             while !is_valid {
-                let result = get_input_toot_and_otto();
+                let input = read_input();
+                if history_command(&mut toot_and_otto, &input, vs, user) {
+                    break;
+                }
+
+                let result = get_input_toot_and_otto(&input);
                 letter = result.0;
                 column = result.1;
                 is_valid = result.2;
@@ -213,31 +455,38 @@ fn toot_and_otto_cli(lvl : &str) {
                     }
                 };
 
-                is_valid = toot_and_otto.drop(drop_piece, column);
-
-                if !is_valid {
-                    println!("Your input wasn't valid !");
-                }
-
+                is_valid = match toot_and_otto.drop(drop_piece, column) {
+                    Ok(()) => true,
+                    Err(err) => {
+                        println!("{}, try again", err);
+                        false
+                    }
+                };
             }
         } else {
-            let res = cpu_toot::make_move(toot_and_otto.clone(), depth);
-            toot_and_otto.drop(res.1, res.0);
+            let res = match vs.mcts_limits() {
+                Some(limits) => {
+                    let moves = cpu_toot::mcts_search(toot_and_otto, user.switch(), limits, config)
+                        .expect("The CPU moves on its own turn");
+                    print_mcts_report::<TootAndOtto>(&moves);
+                    moves[0].mv
+                }
+                None => match vs.search_limits() {
+                    Some(limits) => {
+                        let report = cpu_toot::minimax_search(toot_and_otto, user.switch(), limits, config)
+                            .expect("The CPU moves on its own turn");
+                        print_search_report::<TootAndOtto>(&report);
+                        report.best_move
+                    }
+                    None => cpu_toot::pick_move(toot_and_otto, user.switch(), vs, config)
+                        .expect("The CPU moves on its own turn"),
+                },
+            };
+            toot_and_otto.drop(res.1, res.0).expect("The CPU only picks moves that can be played");
         }
 
         // Displays the board after the input
         println!("{}", toot_and_otto);
-
-        // Switch Players
-        active_player = active_player.switch();
-
-        // Checks if either player won
-        // Unlike connect for, either player could win on any given move
-        // Both players could also win of the piece dropped forms "TOOT"
-        // and "OTTO" simultaneously
-        if toot_and_otto.is_terminal {
-            break;
-        }
     }
 
     match toot_and_otto.winner {
@@ -248,13 +497,396 @@ fn toot_and_otto_cli(lvl : &str) {
             println!("Game drawn :)");
         }
     }
+
+    // The position can be solved from the main menu, and the record replayed
+    println!("Final position: {}", toot_and_otto.position());
+    println!("Game record:\n\n{}", toot_and_otto::record::format(&game_record(&toot_and_otto, vs, cpu_first)));
+}
+
+/// Reads the moves of a Connect 4 game, or a position, and reports who wins
+/// from there with perfect play
+fn solve_connect4_cli() {
+    println!("Enter the columns played so far (0-6), for example 3324, or nothing for an empty board");
+    println!("A position like 7/7/7/7/7/3R3 y can be entered instead");
+    println!("Positions early in the game can take minutes to solve");
+
+    let input = read_input();
+
+    let connect4 = if input.contains('/') {
+        match Connect4::from_position(&input) {
+            Ok(connect4) => connect4,
+            Err(err) => {
+                println!("{}", err);
+                return;
+            }
+        }
+    } else {
+        // Replays the moves, stopping at the first one that can't be played
+        let mut connect4 = Connect4::new();
+        for (i, col) in input.chars().enumerate() {
+            let result = match col.to_digit(10) {
+                Some(col) => connect4.drop(col as usize).map_err(|err| err.to_string()),
+                None => Err(String::from("It isn't a column")),
+            };
+
+            if let Err(err) = result {
+                println!("Move {} ({}) can't be played: {}", i + 1, col, err);
+                return;
+            }
+        }
+        connect4
+    };
+
+    println!("{}", connect4);
+    print_threats(&connect4);
+    if connect4.rules != Connect4Rules::STANDARD {
+        println!("Only positions on the {} board can be solved", Connect4Rules::STANDARD);
+        return;
+    }
+
+    let mut solver = Solver::new();
+    let solution = match solver.try_solve(&connect4, cpu_con4::SOLVE_NODES) {
+        Some(solution) => solution,
+        None => {
+            println!(
+                "Gave up after searching {} positions, try a position with more pieces played",
+                solver.nodes
+            );
+            return;
+        }
+    };
+    let player = connect4.active_player;
+
+    match solution.outcome {
+        Outcome::Win => println!("{} wins in {} moves", player, solution.moves_left),
+        Outcome::Loss => println!("{} loses in {} moves", player, solution.moves_left),
+        Outcome::Draw => println!("The game is a draw"),
+    }
+
+    if !connect4.is_terminal {
+        println!("A perfect move for {} is column {}", player, solver.best_move(&connect4));
+    }
+    println!("Searched {} positions", solver.nodes);
+}
+
+/// Prints where each color can finish a line, and which color controls the
+/// zugzwang at the end of the game
+fn print_threats(connect4: &Connect4) {
+    let columns = |columns: &[usize]| {
+        let columns: Vec<String> = columns.iter().map(|col| col.to_string()).collect();
+        columns.join(", ")
+    };
+
+    for &color in [PieceColor::RED, PieceColor::YELLOW].iter() {
+        let found = threats::analyse(connect4, color);
+        if !found.winning_moves.is_empty() {
+            println!("{} can finish a line in column {}", color, columns(&found.winning_moves));
+        } else if color == connect4.active_player && !found.must_block.is_empty() {
+            println!("{} has to block column {}", color, columns(&found.must_block));
+        }
+
+        if !found.unplayable.is_empty() {
+            println!(
+                "{} can finish a line in cells that can't be played yet, {} on odd rows and {} on even rows",
+                color, found.odd_threats, found.even_threats
+            );
+        }
+    }
+
+    if let Some(zugzwang) = threats::zugzwang(connect4) {
+        println!("{}", zugzwang);
+    }
+}
+
+/// Reports who wins TOOT and OTTO from a position, or from the opening, when
+/// both players play perfectly
+fn solve_toot_and_otto_cli() {
+    println!("Enter a position like 6/6/6/2T3 o 5666, or nothing for the opening");
+
+    let input = read_input();
+    let toot_and_otto = match input.as_str() {
+        "" => TootAndOtto::new(),
+        position => match TootAndOtto::from_position(position) {
+            Ok(toot_and_otto) => toot_and_otto,
+            Err(err) => {
+                println!("{}", err);
+                return;
+            }
+        },
+    };
+
+    println!("{}", toot_and_otto);
+    if toot_and_otto.rules != TootAndOttoRules::STANDARD {
+        println!("Only positions on the {} board can be solved", TootAndOttoRules::STANDARD);
+        return;
+    }
+
+    let mut solver = toot_solver::Solver::new();
+    let solution = solver.solve(&toot_and_otto);
+    let player = toot_and_otto.active_player;
+
+    match solution.outcome {
+        Outcome::Win => println!("{} wins in {} moves", player, solution.moves_left),
+        Outcome::Loss => println!("{} loses in {} moves", player, solution.moves_left),
+        Outcome::Draw => println!("With perfect play from both sides the game is a draw"),
+    }
+
+    if !toot_and_otto.is_terminal {
+        let (col, letter) = solver.best_move(&toot_and_otto);
+        println!("A perfect move for {} is {} in column {}", player, letter, col);
+    }
+}
+
+/// Solves the positions early in TOOT and OTTO and writes their scores to a file
+/// the solver can load, the built in table lives in `src/toot_and_otto/solutions.bin`
+fn build_toot_table_cli() {
+    println!("Enter how many pieces the deepest positions in the table have, for example 3 like the built in table");
+    println!("Every extra piece takes many times longer to solve");
+
+    let depth: usize = match read_input().parse() {
+        Err(_) => {
+            println!("Invalid Input, Try Again");
+            return;
+        }
+        Ok(depth) => depth,
+    };
+
+    println!("Enter the file to write the table to");
+    let path = read_input();
+
+    let mut solver = toot_solver::Solver::without_table();
+    let table = solver.write_table(depth, |solved, total| {
+        println!("Solved {} of {} positions", solved, total);
+    });
+
+    match std::fs::write(&path, &table) {
+        Ok(_) => println!("Wrote {} bytes after searching {} positions", table.len(), solver.nodes),
+        Err(err) => println!("Couldn't write the table: {}", err),
+    }
+}
+
+/// Solves the best moves of the positions early in Connect 4 and writes them to
+/// a file the solver can load, the built in book lives in `src/connect4/opening_book.bin`
+fn build_connect4_book_cli() {
+    println!("Enter how many pieces the positions in the book have fewer than, for example 6 like the built in book");
+    println!("Every extra piece takes many times longer to solve, the empty board alone takes half an hour");
+
+    let depth: usize = match read_input().parse() {
+        Err(_) => {
+            println!("Invalid Input, Try Again");
+            return;
+        }
+        Ok(depth) => depth,
+    };
+
+    println!("Enter the file to write the book to");
+    let path = read_input();
+
+    let mut solver = Solver::without_book();
+    let book = solver.write_book(depth, |solved, pieces| {
+        println!("Solved {} positions, the last with {} pieces", solved, pieces);
+    });
+
+    match std::fs::write(&path, &book) {
+        Ok(_) => println!("Wrote {} bytes after searching {} positions", book.len(), solver.nodes),
+        Err(err) => println!("Couldn't write the book: {}", err),
+    }
+}
+
+/// Prints how often the Monte Carlo search played out each move and how many
+/// of the points it won, the move it picked first
+fn print_mcts_report<G: Notation>(moves: &[MoveStats<G::Move>]) {
+    let playouts: u64 = moves.iter().map(|stats| stats.visits).sum();
+    println!("The CPU played out {} games", playouts);
+    for stats in moves {
+        println!(
+            "  {:>4}: {} playouts, {:.1}% of the points",
+            G::format_move(stats.mv),
+            stats.visits,
+            stats.win_rate() * 100.0
+        );
+    }
+}
+
+/// Prints how deep the CPU searched, the score of the move it picked with the
+/// line it expects, and the score of every other move
+fn print_search_report<G: Notation>(report: &SearchReport<G::Move>) {
+    let line: Vec<String> = report.principal_variation.iter().map(|&mv| G::format_move(mv)).collect();
+    println!("The CPU searched {} positions, {} moves deep", report.nodes, report.depth);
+    println!("It expects {} ({})", line.join(" "), format_score(report.score));
+    for scored in &report.moves {
+        let score = match scored.bound {
+            Bound::Upper => format!("at most {}", format_score(scored.score)),
+            _ => format_score(scored.score),
+        };
+        println!("  {:>4}: {}", G::format_move(scored.mv), score);
+    }
+}
+
+/// Asks which CPU plays `side` until one is picked
+fn get_engine(side: &str) -> Opponent {
+    println!("Which CPU plays {}?", side);
+    println!("2. CPU EASY");
+    println!("3. CPU MED");
+    println!("4. CPU HARD");
+    println!("5. CPU PERFECT");
+    println!("6. CPU MCTS");
+
+    loop {
+        let input = read_input();
+        match input.as_str() {
+            "2" | "3" | "4" | "5" | "6" => return get_opponent(&input),
+            _ => println!("Pick a CPU from 2 to 6, try again"),
+        }
+    }
+}
+
+/// Plays out a game on `board` between the two CPUs of `spectator`, with
+/// `pick_move` picking the move a CPU makes for the side to move
+///
+/// Each move waits for the watcher to press enter until they ask for the rest
+/// of the game to be played
+fn watch<G: Notation + Display>(
+    mut board: G,
+    spectator: Spectator,
+    pick_move: impl Fn(&G, Opponent) -> Option<G::Move>,
+) {
+    let first_player = board.active_player();
+
+    println!("{}", board);
+    println!("Press enter to see each move, or enter auto to play out the rest of the game");
+
+    let mut auto_play = false;
+    while !board.is_terminal() {
+        if !auto_play {
+            auto_play = read_input() == "auto";
+        }
+
+        let side = if board.active_player() == first_player { 0 } else { 1 };
+        let engine = spectator.engines[side];
+        let mv = pick_move(&board, engine).expect("The CPU moves on its own turn");
+        println!("{} ({}) plays {}", G::SIDES[side], engine, G::format_move(mv));
+        board.apply(mv);
+        println!("{}", board);
+    }
+
+    let winner = board
+        .winner()
+        .map(|winner| if winner == first_player { 0 } else { 1 });
+    println!("{}", spectator.summary(G::SIDES, winner, board.moves().len()));
+
+    let record = Record::new(
+        &board,
+        spectator.engines.map(|engine| format!("CPU {}", engine)),
+        format!("{} v {}", spectator.engines[0], spectator.engines[1]),
+        record::today(),
+    );
+    println!("Game record:\n\n{}", record::format::<G>(&record));
+}
+
+/// Lets two CPUs play each other at any level while the user watches
+fn watch_cli(config: EngineConfig) {
+    println!("Which game should the CPUs play?");
+    println!("1. Connect 4");
+    println!("2. TOOT and OTTO");
+
+    match read_input().as_str() {
+        "1" => {
+            let spectator = Spectator {
+                engines: Connect4::SIDES.map(get_engine),
+            };
+            let connect4 = Connect4::with_rules(get_connect4_rules(&spectator.engines));
+            watch(connect4, spectator, |board, engine| {
+                cpu_con4::pick_move(*board, board.active_player, engine, config)
+            });
+        }
+        "2" => {
+            let spectator = Spectator {
+                engines: TootAndOtto::SIDES.map(get_engine),
+            };
+            let toot_and_otto = TootAndOtto::with_rules(get_toot_and_otto_rules(&spectator.engines));
+            watch(toot_and_otto, spectator, |board, engine| {
+                cpu_toot::pick_move(*board, board.active_player, engine, config)
+            });
+        }
+        _ => println!("There is no game with that number"),
+    }
+}
+
+/// Reads a game record from a file and steps through the game it holds
+fn replay_cli() {
+    println!("Enter the file holding the game record");
+    let path = read_input();
+
+    let text = match std::fs::read_to_string(&path) {
+        Ok(text) => text,
+        Err(err) => {
+            println!("Couldn't read the game record: {}", err);
+            return;
+        }
+    };
+
+    let game = record::header(&text, "Game").unwrap_or_default();
+    let result = if game == Connect4::NAME {
+        connect4::record::parse(&text).map(|(record, board)| step_through(&record, board))
+    } else if game == TootAndOtto::NAME {
+        toot_and_otto::record::parse(&text).map(|(record, board)| step_through(&record, board))
+    } else {
+        Err(String::from("The record doesn't say which game was played"))
+    };
+
+    if let Err(err) = result {
+        println!("{}", err);
+    }
+}
+
+/// Shows how a recorded game ended, then steps back and forth through its moves
+fn step_through<G: Notation + Display>(record: &Record<G::Move>, mut board: G) {
+    println!(
+        "{} ({}) against {} ({}) on {}, difficulty {}",
+        G::SIDES[0], record.players[0], G::SIDES[1], record.players[1], record.date, record.difficulty
+    );
+    println!("{}", board);
+    println!("Result: {}", record.result);
+    println!("Enter undo or redo to step through the game, or anything else to stop");
+
+    loop {
+        let input = read_input();
+        let has_moved = match input.as_str() {
+            "undo" => board.undo(),
+            "redo" => board.redo(),
+            _ => return,
+        };
+
+        if !has_moved {
+            println!("There are no moves to {}", input);
+        }
+        println!("{}", board);
+    }
 }
 
 fn main() {
+    let config = match get_engine_config() {
+        Ok(config) => config,
+        Err(err) => {
+            println!("{}", err);
+            std::process::exit(1);
+        }
+    };
+    if let Some(seed) = config.seed {
+        println!("The CPU is seeded with {}, the same moves against it replay the same game", seed);
+    }
+
     // Asks the user which game they'd like to play
     println!("Which game would you like to play?");
     println!("1. Connect 4");
     println!("2. TOOT and OTTO");
+    println!("3. Solve a Connect 4 position");
+    println!("4. Solve a TOOT and OTTO position");
+    println!("5. Build the TOOT and OTTO solution table");
+    println!("6. Replay a game record");
+    println!("7. Watch two CPUs play each other");
+    println!("8. Build the Connect 4 opening book");
 
     let mut game = String::new();
     if let Err(_) = io::stdin().read_line(&mut game) {
@@ -262,11 +894,23 @@ fn main() {
         return;
     };
 
+    match game.trim() {
+        "3" => return solve_connect4_cli(),
+        "4" => return solve_toot_and_otto_cli(),
+        "5" => return build_toot_table_cli(),
+        "6" => return replay_cli(),
+        "7" => return watch_cli(config),
+        "8" => return build_connect4_book_cli(),
+        _ => {}
+    }
+
     println!("----- ===== ----- ===== ----- ===== -----");
     println!("1. Human");
     println!("2. CPU EASY");
     println!("3. CPU MED");
     println!("4. CPU HARD");
+    println!("5. CPU PERFECT");
+    println!("6. CPU MCTS");
     let mut lvl = String::new();
     if let Err(_) = io::stdin().read_line(&mut lvl) {
         println!("Input failed, try again");
@@ -275,8 +919,8 @@ fn main() {
 
     let game = game.trim();
     if game == "1" {
-        connect4_cli(lvl.trim());
+        connect4_cli(lvl.trim(), config);
     } else {
-        toot_and_otto_cli(lvl.trim());
+        toot_and_otto_cli(lvl.trim(), config);
     }
 }
@@ -0,0 +1,61 @@
+// The entry point of the web worker the CPU searches in, see `cpu_worker.rs`
+
+// The modules are shared with the web client, whose boards use the parts of
+// them the worker doesn't, like undoing moves and the worker's bridge
+#[allow(dead_code)]
+#[path = "../cpu_worker.rs"]
+mod cpu_worker;
+
+#[allow(dead_code)]
+#[path = "../engine"]
+mod engine {
+    pub mod config;
+    pub mod game;
+    pub mod mcts;
+    pub mod minimax;
+    pub mod position;
+    pub mod transposition;
+    pub mod weights;
+    pub mod zobrist;
+}
+
+#[allow(dead_code)]
+#[path = "../connect4"]
+mod connect4 {
+    pub mod connect4;
+    pub mod connect4_move;
+    pub mod cpu_con4;
+    pub mod piece_color;
+    pub mod position;
+    pub mod rules;
+    pub mod solver;
+    pub mod weights;
+}
+
+#[allow(dead_code)]
+#[path = "../toot_and_otto"]
+mod toot_and_otto {
+    pub mod cpu_toot;
+    pub mod piece_letter;
+    pub mod player;
+    pub mod position;
+    pub mod rules;
+    pub mod solver;
+    pub mod toot_and_otto;
+    pub mod weights;
+}
+
+#[allow(dead_code)]
+#[path = "../types"]
+mod types {
+    pub mod move_error;
+    pub mod opponent;
+}
+
+use yew::agent::Threaded;
+
+fn main() {
+    wasm_logger::init(wasm_logger::Config::default());
+    yew::initialize();
+    cpu_worker::CpuWorker::register();
+}
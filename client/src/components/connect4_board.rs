@@ -1,28 +1,123 @@
 use crate::{
 	connect4::{
-		connect4::{Connect4, NUM_COLS, NUM_ROWS},
+		connect4::Connect4,
+		connect4_move::Connect4Move,
 		cpu_con4,
 		piece_color::{PieceColor, PieceColor::*},
+		rules::Connect4Rules,
+		threats,
 	},
-	types::opponent::Opponent,
+	cpu_worker::{CpuRequest, CpuResponse, CpuWorkerBridge, MoveCode, Position, Report},
+	engine::{
+		game::Game,
+		minimax::{format_score, SearchReport},
+		transposition::Bound,
+	},
+	switch::{position_link, shared_position},
+	types::{move_error::MoveError, opponent::Opponent, spectator::Spectator},
 };
 use serde_json::json;
+use std::time::Duration;
 use strum::IntoEnumIterator;
 use yew::format::Json;
 use yew::services::fetch::{FetchService, FetchTask, Request, Response};
+use yew::services::timeout::{TimeoutService, TimeoutTask};
 use yew::{html, Component, ComponentLink, Html, ShouldRender};
 
 pub struct Connect4Board {
 	board: Connect4,
 	vs: Opponent,
+	/// The color the user plays, the CPU plays the other one
+	user: PieceColor,
 	link: ComponentLink<Self>,
 	fetch_task: Option<FetchTask>,
+	/// Why the last piece the user tried to drop was refused
+	move_error: Option<MoveError>,
+	/// The CPUs playing each other when the user is watching instead of playing
+	spectator: Option<Spectator>,
+	/// Whether the watched CPUs move on their own, `delay_millis` apart
+	auto_play: bool,
+	delay_millis: u64,
+	auto_play_task: Option<TimeoutTask>,
+	/// What the search behind the CPU's last move found, `None` when the last
+	/// CPU didn't search a limited depth
+	thinking: Option<SearchReport<Connect4Move>>,
+	/// Whether each move's score is shown to the player to move
+	hint: bool,
+	/// The scores shown while `hint` is on
+	hints: Option<SearchReport<Connect4Move>>,
+	/// Whether hints were shown during this game, which keeps it out of the
+	/// user's ranked stats
+	hinted: bool,
+	/// Whether a move was taken back during this game, which also keeps it out
+	/// of the user's ranked stats
+	undone: bool,
+	/// Whether the result of this game has been sent, it's only sent once
+	recorded: bool,
+	/// Whether the game is played out from a shared position rather than from
+	/// the start, which keeps its result out of the user's stats
+	shared: bool,
+	/// The worker the CPU searches in, so the page keeps responding meanwhile
+	worker: CpuWorkerBridge,
+	/// Whether the CPU is searching for its next move
+	cpu_thinking: bool,
 }
 
+/// The delays between moves, in milliseconds, that can be picked when watching
+const DELAYS: [u64; 4] = [250, 500, 1000, 2000];
+
+/// The board sizes that can be picked before the first move, each can also be
+/// played with Pop Out or misère
+const BOARD_SIZES: [Connect4Rules; 6] = [
+	Connect4Rules::STANDARD,
+	Connect4Rules::FIVE_IN_A_ROW,
+	Connect4Rules {
+		num_cols: 8,
+		num_rows: 7,
+		line_length: 4,
+		pop_out: false,
+		misere: false,
+	},
+	Connect4Rules {
+		num_cols: 9,
+		num_rows: 7,
+		line_length: 4,
+		pop_out: false,
+		misere: false,
+	},
+	Connect4Rules {
+		num_cols: 9,
+		num_rows: 7,
+		line_length: 5,
+		pop_out: false,
+		misere: false,
+	},
+	Connect4Rules {
+		num_cols: 5,
+		num_rows: 4,
+		line_length: 4,
+		pop_out: false,
+		misere: false,
+	},
+];
+
 pub enum Msg {
 	DropPiece(usize),
+	PopPiece(usize),
 	Reset,
+	Undo,
+	Redo,
 	ChangeOpponent(Opponent),
+	ChangeSide(PieceColor),
+	ChangeRules(Connect4Rules),
+	Spectate,
+	ChangeEngine(PieceColor, Opponent),
+	Step,
+	ToggleAutoPlay,
+	ChangeDelay(u64),
+	AutoPlay,
+	ToggleHint,
+	WorkerResponse(CpuResponse),
 	ReceiveResponse(Result<String, anyhow::Error>),
 }
 
@@ -39,7 +134,11 @@ impl Connect4Board {
 		if username == "" {
 			return;
 		}
-		let body = &json!({"username": &username, "game": 0, "win": win});
+		let side = match self.user {
+			RED => 0,
+			YELLOW => 1,
+		};
+		let body = &json!({"username": &username, "game": 0, "win": win, "side": side, "hinted": self.hinted, "undone": self.undone});
 		let request = Request::post("http://localhost:8000/update_score")
 			.header("Content-Type", "application/json")
 			.body(Json(body))
@@ -55,71 +154,342 @@ impl Connect4Board {
 		// 4. store the task so it isn't canceled immediately
 		self.fetch_task = Some(task);
 	}
+
+	/// Updates the user's score once the game is over
+	fn record_result(&mut self) {
+		if !self.board.is_terminal || self.recorded || self.shared {
+			return;
+		}
+		self.recorded = true;
+
+		match self.board.winner {
+			Some(winner) if winner == self.user => self.update_score(1),
+			Some(_) => self.update_score(0),
+			None => self.update_score(2),
+		}
+	}
+
+	/// Asks for the CPU's drop or pop when it's the CPU's turn
+	fn play_cpu_move(&mut self) {
+		if self.spectator.is_none()
+			&& self.vs != Opponent::Human
+			&& !self.board.is_terminal
+			&& self.board.active_player != self.user
+		{
+			self.request_cpu_move(self.vs);
+		}
+	}
+
+	/// Whether the user can move, which is always when both players are human
+	fn is_users_turn(&self) -> bool {
+		self.vs == Opponent::Human || self.board.active_player == self.user
+	}
+
+	/// Plays the user's drop or pop, then asks for the CPU's reply when playing
+	/// against it
+	fn play_move(&mut self, mv: Connect4Move) -> ShouldRender {
+		self.move_error = self.board.play(mv).err();
+		if self.move_error.is_some() {
+			return true;
+		}
+
+		self.record_result();
+		self.play_cpu_move();
+		self.update_hints();
+
+		true
+	}
+
+	/// Asks for the move of the watched CPU whose turn it is, the next one is
+	/// waited for once it's played when they move on their own
+	fn play_spectator_move(&mut self) {
+		match self.spectator {
+			Some(spectator) if !self.cpu_thinking && !self.board.is_terminal => {
+				self.request_cpu_move(spectator.engines[self.board.active_player.index()])
+			}
+			_ => {}
+		}
+	}
+
+	/// Waits `delay_millis` before the next move when the watched CPUs move on
+	/// their own, and stops waiting otherwise
+	fn schedule_auto_play(&mut self) {
+		self.auto_play_task = match self.auto_play && !self.board.is_terminal {
+			true => Some(TimeoutService::spawn(
+				Duration::from_millis(self.delay_millis),
+				self.link.callback(|_| Msg::AutoPlay),
+			)),
+			false => None,
+		};
+	}
+
+	/// Asks the worker to score the moves of the player to move while hints
+	/// are on, the game is marked as hinted once they are shown
+	fn update_hints(&mut self) {
+		self.hints = None;
+		if self.hint && self.spectator.is_none() && !self.board.is_terminal && !self.cpu_thinking {
+			self.worker.send(CpuRequest::hints(self.worker_position()));
+		}
+	}
+
+	/// The position to send to the worker
+	fn worker_position(&self) -> Position {
+		Position::Connect4(self.board.position())
+	}
+
+	/// Asks the worker for the move `engine` makes in the position
+	fn request_cpu_move(&mut self, engine: Opponent) {
+		let request = CpuRequest::cpu_move(self.worker_position(), engine);
+		self.worker.send(request);
+		self.cpu_thinking = true;
+	}
+
+	/// Gives up on the CPU's search, the position it was searching is gone
+	fn cancel_cpu_move(&mut self) {
+		self.worker.cancel();
+		self.cpu_thinking = false;
+	}
+
+	/// Plays the move the worker picked, or shows the hints it found while
+	/// they're on, as long as the position it searched is still on the board
+	fn receive_response(&mut self, response: CpuResponse) -> ShouldRender {
+		match self.worker.receive(response) {
+			Some(CpuResponse::Move {
+				position,
+				mv,
+				report,
+			}) if position == self.worker_position() => {
+				self.cpu_thinking = false;
+				self.thinking = report.map(Report::unpack);
+				if let Some(mv) = mv {
+					self.board
+						.play(Connect4Move::decode(mv))
+						.expect("The CPU only picks moves that can be played");
+				}
+
+				match self.spectator {
+					Some(_) => self.schedule_auto_play(),
+					None => self.record_result(),
+				}
+				self.update_hints();
+				true
+			}
+			Some(CpuResponse::Hints { position, report })
+				if self.hint && position == self.worker_position() =>
+			{
+				self.hints = report.map(Report::unpack);
+				self.hinted |= self.hints.is_some();
+				true
+			}
+			_ => false,
+		}
+	}
+
+	/// Whether the user has yet to move, the CPU may already have made the
+	/// first move when it goes first
+	///
+	/// The moves that set up a shared position don't count, so the opponent
+	/// and side can still be picked before playing it out
+	fn before_first_move(&self) -> bool {
+		let board = &self.board;
+		match board.moves_played.saturating_sub(board.set_up_moves) {
+			0 => true,
+			1 => {
+				self.spectator.is_none()
+					&& self.vs != Opponent::Human
+					&& self.board.active_player == self.user
+			}
+			_ => false,
+		}
+	}
+
+	/// Starts the game over with the same rules, or from the shared position
+	/// when one was set up, letting the CPU move first when the user plays second
+	fn restart(&mut self) {
+		self.cancel_cpu_move();
+		self.move_error = None;
+		self.thinking = None;
+		self.hinted = false;
+		self.undone = false;
+		self.recorded = false;
+		self.auto_play = false;
+		self.schedule_auto_play();
+		while self.board.moves_played > self.board.set_up_moves && self.board.undo() {}
+		self.board.history_len = self.board.moves_played;
+		self.play_cpu_move();
+		self.update_hints();
+	}
 }
 
 impl Component for Connect4Board {
 	type Message = Msg;
 	type Properties = ();
 	fn create(_: Self::Properties, link: ComponentLink<Self>) -> Self {
-		Self {
-			link,
-			board: Connect4::new(),
-			vs: Opponent::HardCPU,
-			fetch_task: None,
+		let worker = CpuWorkerBridge::new(link.callback(Msg::WorkerResponse));
+
+		// A shared position is set up to be played out from either side
+		match shared_position().and_then(|position| Connect4::from_position(&position).ok()) {
+			Some(board) => Self {
+				link,
+				board,
+				vs: Opponent::Human,
+				user: RED,
+				fetch_task: None,
+				move_error: None,
+				spectator: None,
+				auto_play: false,
+				delay_millis: DELAYS[1],
+				auto_play_task: None,
+				thinking: None,
+				hint: false,
+				hints: None,
+				hinted: false,
+				undone: false,
+				recorded: false,
+				shared: true,
+				worker,
+				cpu_thinking: false,
+			},
+			None => Self {
+				link,
+				board: Connect4::new(),
+				vs: Opponent::HardCPU,
+				user: RED,
+				fetch_task: None,
+				move_error: None,
+				spectator: None,
+				auto_play: false,
+				delay_millis: DELAYS[1],
+				auto_play_task: None,
+				thinking: None,
+				hint: false,
+				hints: None,
+				hinted: false,
+				undone: false,
+				recorded: false,
+				shared: false,
+				worker,
+				cpu_thinking: false,
+			},
 		}
 	}
 
 	fn update(&mut self, msg: Self::Message) -> ShouldRender {
 		match msg {
-			Msg::DropPiece(col) => {
-				if let Some(_) = self.board.winner {
-					return false;
-				} else if self.board.moves_played == 42 {
-					return false;
-				}
-
-				if self.board.drop(col) == false {
+			// Only the CPUs move while the user is watching, and the user waits
+			// for the CPU to move and never moves for it
+			Msg::DropPiece(_) | Msg::PopPiece(_)
+				if self.spectator.is_some() || self.cpu_thinking || !self.is_users_turn() =>
+			{
+				return false
+			}
+			Msg::DropPiece(col) => return self.play_move(Connect4Move::Drop(col)),
+			Msg::PopPiece(col) => return self.play_move(Connect4Move::Pop(col)),
+			Msg::Reset => self.restart(),
+			Msg::Undo => {
+				// The CPU's first move stays when it goes first
+				if self.before_first_move() {
 					return false;
 				}
-				if let Some(winner) = self.board.winner {
-					// Player is red
-					// Updates game score
-					match winner {
-						PieceColor::RED => self.update_score(1),
-						PieceColor::YELLOW => self.update_score(0),
-					}
-					return true;
-				} else if self.board.winner.is_none() && self.board.is_terminal {
-					self.update_score(2);
-				}
 
-				let depth = match self.vs {
-					Opponent::Human => return true,
-					Opponent::EasyCPU => 1,
-					Opponent::MediumCPU => 2,
-					Opponent::HardCPU => 3,
+				self.cancel_cpu_move();
+				self.move_error = None;
+				self.thinking = None;
+				self.auto_play = false;
+				self.schedule_auto_play();
+				let is_undone = match (self.spectator, self.vs) {
+					(Some(_), _) | (None, Opponent::Human) => self.board.undo(),
+					_ => self.board.undo_turn(self.user),
 				};
 
-				self.board.drop(cpu_con4::make_move(self.board, depth));
-
-				if let Some(winner) = self.board.winner {
-					// Player is red
-					// update game score
-					match winner {
-						PieceColor::RED => self.update_score(1),
-						PieceColor::YELLOW => self.update_score(0),
-					}
-					return true;
-				} else if self.board.winner.is_none() && self.board.is_terminal {
-					self.update_score(2);
+				// The CPU moves again when there was no turn of the user's to go back to
+				if self.spectator.is_none() {
+					self.undone |= is_undone;
+					self.record_result();
+					self.play_cpu_move();
 				}
+				self.update_hints();
+				return is_undone;
 			}
-			Msg::Reset => {
-				self.board = Connect4::new();
+			Msg::Redo => {
+				self.cancel_cpu_move();
+				self.move_error = None;
+				self.thinking = None;
+				self.auto_play = false;
+				self.schedule_auto_play();
+				let is_redone = match (self.spectator, self.vs) {
+					(Some(_), _) | (None, Opponent::Human) => self.board.redo(),
+					_ => self.board.redo_turn(self.user),
+				};
+
+				// The redone moves can end the game, or stop on the CPU's turn
+				if self.spectator.is_none() {
+					self.record_result();
+					self.play_cpu_move();
+				}
+				self.update_hints();
+				return is_redone;
 			}
 			Msg::ChangeOpponent(opponent) => {
-				if self.board.moves_played == 0 {
+				if self.before_first_move() {
 					self.vs = opponent;
+					self.spectator = None;
+					self.restart();
+				}
+			}
+			Msg::ChangeSide(side) => {
+				if self.before_first_move() {
+					self.user = side;
+					self.spectator = None;
+					self.restart();
+				}
+			}
+			Msg::Spectate => {
+				if self.before_first_move() && self.spectator.is_none() {
+					self.spectator = Some(Spectator::default());
+					self.restart();
+				}
+			}
+			Msg::ChangeEngine(side, engine) => {
+				if let Some(spectator) = &mut self.spectator {
+					spectator.engines[side.index()] = engine;
+				}
+			}
+			Msg::Step => {
+				if !self.auto_play {
+					self.play_spectator_move();
+				}
+			}
+			Msg::ToggleAutoPlay => {
+				self.auto_play = !self.auto_play;
+				self.schedule_auto_play();
+			}
+			Msg::ChangeDelay(delay_millis) => self.delay_millis = delay_millis,
+			Msg::WorkerResponse(response) => return self.receive_response(response),
+			Msg::ToggleHint => {
+				self.hint = !self.hint;
+				self.update_hints();
+			}
+			Msg::AutoPlay => {
+				if self.auto_play {
+					self.play_spectator_move();
+				}
+			}
+			Msg::ChangeRules(rules) => {
+				if self.before_first_move() {
+					// The perfect CPU can't play every board, the hard CPU takes its place
+					let engine = |engine| match cpu_con4::can_play(engine, rules) {
+						true => engine,
+						false => Opponent::HardCPU,
+					};
+					self.vs = engine(self.vs);
+					if let Some(spectator) = &mut self.spectator {
+						spectator.engines = spectator.engines.map(engine);
+					}
+
+					self.board = Connect4::with_rules(rules);
+					self.shared = false;
+					self.restart();
 				}
 			}
 			Msg::ReceiveResponse(response) => match response.unwrap().as_str() {
@@ -146,15 +516,15 @@ impl Component for Connect4Board {
 		let check_for_piece = move |row: usize, col: usize| -> Html {
 			let mut classes = String::from("piece");
 
-			if let Some(_) = self.board.winner {
-				if self
-					.board
-					.check_for_win(self.board.active_player.switch())
-					.unwrap()
-					.contains(&[row, col])
-				{
-					classes.push_str(" piece--winner");
-				}
+			// Highlights every finished line, the winner's or in misère the
+			// loser's, and a pop can finish one for each color
+			if self.board.winner.is_some()
+				&& [RED, YELLOW]
+					.iter()
+					.filter_map(|&color| self.board.check_for_win(color))
+					.any(|line| line.contains(&[row, col]))
+			{
+				classes.push_str(" piece--winner");
 			}
 
 			classes.push_str(match self.board.board[row][col] {
@@ -188,12 +558,12 @@ impl Component for Connect4Board {
 
 			let arrow_text = match self.board.is_terminal {
 				false => "",
-				true => match self.board.winner {
-					Some(winner) => match winner {
-						RED => "YOU WIN!!!",
-						YELLOW => "CPU WINS :(",
-					},
-					None => "TIE GAME!",
+				true => match (self.spectator, self.board.winner) {
+					(Some(_), Some(RED)) => "RED WINS!",
+					(Some(_), Some(YELLOW)) => "YELLOW WINS!",
+					(None, Some(winner)) if winner == self.user => "YOU WIN!!!",
+					(None, Some(_)) => "CPU WINS :(",
+					(_, None) => "TIE GAME!",
 				},
 			};
 
@@ -208,9 +578,9 @@ impl Component for Connect4Board {
 
 		let opponent_buttons = move || -> Html {
 			html! {
-				<div class=format!("opponent {}", if self.board.moves_played > 0 { "opponent--disabled" } else { "" }) >
+				<div class=format!("opponent {}", if self.before_first_move() { "" } else { "opponent--disabled" }) >
 					{
-						Opponent::iter().map(|opponent| {
+						Opponent::iter().filter(|&opponent| cpu_con4::can_play(opponent, self.board.rules)).map(|opponent| {
 							html! {
 								<button
 									class=format!("opponent__button {}", if self.vs == opponent {"opponent__button--selected"} else {""})
@@ -224,6 +594,290 @@ impl Component for Connect4Board {
 			}
 		};
 
+		let board_size_buttons = move || -> Html {
+			html! {
+				<div class=format!("opponent {}", if self.before_first_move() { "" } else { "opponent--disabled" }) >
+					{
+						BOARD_SIZES.iter().map(|&rules| {
+							let rules = rules
+								.with_pop_out(self.board.rules.pop_out)
+								.with_misere(self.board.rules.misere);
+							html! {
+								<button
+									class=format!("opponent__button {}", if self.board.rules == rules {"opponent__button--selected"} else {""})
+									onclick=self.link.callback(move |_| Msg::ChangeRules(rules))
+								>
+									{rules.with_pop_out(false).with_misere(false).to_string().to_uppercase()}
+								</button>
+						}}).collect::<Html>()
+					}
+					{
+						{
+							let rules = self.board.rules.with_pop_out(!self.board.rules.pop_out);
+							html! {
+								<button
+									class=format!("opponent__button {}", if self.board.rules.pop_out {"opponent__button--selected"} else {""})
+									onclick=self.link.callback(move |_| Msg::ChangeRules(rules))
+								>
+									{"POP OUT"}
+								</button>
+							}
+						}
+					}
+					{
+						{
+							let rules = self.board.rules.with_misere(!self.board.rules.misere);
+							html! {
+								<button
+									class=format!("opponent__button {}", if self.board.rules.misere {"opponent__button--selected"} else {""})
+									onclick=self.link.callback(move |_| Msg::ChangeRules(rules))
+								>
+									{"MISÈRE"}
+								</button>
+							}
+						}
+					}
+				</div>
+			}
+		};
+
+		let side_buttons = move || -> Html {
+			html! {
+				<div class=format!("opponent {}", if self.before_first_move() { "" } else { "opponent--disabled" }) >
+					{
+						[RED, YELLOW].iter().map(|&side| {
+							html! {
+								<button
+									class=format!("opponent__button {}", if self.spectator.is_none() && self.user == side {"opponent__button--selected"} else {""})
+									onclick=self.link.callback(move |_| Msg::ChangeSide(side))
+								>
+									{format!("PLAY AS {}", side.to_string().to_uppercase())}
+								</button>
+						}}).collect::<Html>()
+					}
+					<button
+						class=format!("opponent__button {}", if self.spectator.is_some() {"opponent__button--selected"} else {""})
+						onclick=self.link.callback(move |_| Msg::Spectate)
+					>
+						{"WATCH CPUS"}
+					</button>
+				</div>
+			}
+		};
+
+		// While watching, each side picks its CPU and the moves are stepped
+		// through or played on their own
+		let spectator_buttons = move |spectator: Spectator| -> Html {
+			html! {
+				<>
+					{
+						[RED, YELLOW].iter().map(|&side| {
+							html! {
+								<div class="opponent">
+								{
+									Opponent::iter().filter(|&engine| engine != Opponent::Human && cpu_con4::can_play(engine, self.board.rules)).map(|engine| {
+										html! {
+											<button
+												class=format!("opponent__button {}", if spectator.engines[side.index()] == engine {"opponent__button--selected"} else {""})
+												onclick=self.link.callback(move |_| Msg::ChangeEngine(side, engine))
+											>
+												{format!("{} {}", side.to_string().to_uppercase(), engine.to_string().to_uppercase())}
+											</button>
+										}
+									}).collect::<Html>()
+								}
+								</div>
+							}
+						}).collect::<Html>()
+					}
+					<div class=format!("opponent {}", if self.board.is_terminal { "opponent--disabled" } else { "" }) >
+						<button
+							class="opponent__button"
+							disabled=self.auto_play || self.cpu_thinking || self.board.is_terminal
+							onclick=self.link.callback(move |_| Msg::Step)
+						>
+							{"STEP"}
+						</button>
+						<button
+							class=format!("opponent__button {}", if self.auto_play {"opponent__button--selected"} else {""})
+							onclick=self.link.callback(move |_| Msg::ToggleAutoPlay)
+						>
+							{if self.auto_play { "PAUSE" } else { "AUTO PLAY" }}
+						</button>
+						{
+							DELAYS.iter().map(|&delay_millis| {
+								html! {
+									<button
+										class=format!("opponent__button {}", if self.delay_millis == delay_millis {"opponent__button--selected"} else {""})
+										onclick=self.link.callback(move |_| Msg::ChangeDelay(delay_millis))
+									>
+										{format!("{}S", delay_millis as f64 / 1000.0)}
+									</button>
+								}
+							}).collect::<Html>()
+						}
+					</div>
+				</>
+			}
+		};
+
+		// Sums up the game once the watched CPUs have finished it, and shows when
+		// a CPU is thinking
+		let summary = move || -> String {
+			match self.spectator {
+				Some(spectator) if self.board.is_terminal => spectator.summary(
+					[RED, YELLOW],
+					self.board.winner.map(|winner| winner.index()),
+					self.board.moves_played,
+				),
+				_ if self.cpu_thinking => String::from("CPU thinking…"),
+				_ => String::new(),
+			}
+		};
+
+		// Shows the score, expected line and root move scores behind the CPU's
+		// last move
+		let engine_thinks = move || -> Html {
+			let report = match &self.thinking {
+				Some(report) => report,
+				None => return html! {},
+			};
+			let format_move = |mv: Connect4Move| match mv {
+				Connect4Move::Drop(col) => col.to_string(),
+				Connect4Move::Pop(col) => format!("P{}", col),
+			};
+
+			html! {
+				<div class="thinking">
+					<p class="thinking__title">{"ENGINE THINKS"}</p>
+					<p>{format!("{} positions, {} moves deep", report.nodes, report.depth)}</p>
+					<p>
+						{format!(
+							"Best {} ({}), expecting {}",
+							format_move(report.best_move),
+							format_score(report.score),
+							report.principal_variation.iter().map(|&mv| format_move(mv)).collect::<Vec<_>>().join(" ")
+						)}
+					</p>
+					<div class="thinking__moves">
+					{
+						report.moves.iter().map(|scored| {
+							let score = match scored.bound {
+								Bound::Upper => format!("at most {}", format_score(scored.score)),
+								_ => format_score(scored.score),
+							};
+							html! {
+								<span class="thinking__move">{format!("{}: {}", format_move(scored.mv), score)}</span>
+							}
+						}).collect::<Html>()
+					}
+					</div>
+				</div>
+			}
+		};
+
+		// Scores each column's drop, and pop in Pop Out, for the player to move
+		// with the best ones highlighted
+		let hint_row = move || -> Html {
+			let hints = match &self.hints {
+				Some(hints) => hints,
+				None => return html! {},
+			};
+			let best = hints.moves.iter().map(|scored| scored.score).max();
+			let hint = |mv: Connect4Move, label: &str| -> Html {
+				match hints.moves.iter().find(|scored| scored.mv == mv) {
+					Some(scored) => html! {
+						<span class=format!("hints__move {}", if Some(scored.score) == best {"hints__move--best"} else {""})>
+							{format!("{}{}", label, format_score(scored.score))}
+						</span>
+					},
+					None => html! {},
+				}
+			};
+
+			html! {
+				<div class="hints">
+				{
+					(0..self.board.rules.num_cols).into_iter().map(|col| {
+						html! {
+							<div class="hints__column">
+								{hint(Connect4Move::Drop(col), "")}
+								{hint(Connect4Move::Pop(col), "POP ")}
+							</div>
+						}
+					}).collect::<Html>()
+				}
+				</div>
+			}
+		};
+
+		// Points out the lines each color can finish, and which color controls
+		// the zugzwang at the end of the game, alongside the hints
+		let threat_notes = move || -> Html {
+			if self.hints.is_none() {
+				return html! {};
+			}
+			let columns = |columns: &[usize]| {
+				let columns: Vec<String> = columns.iter().map(|col| col.to_string()).collect();
+				columns.join(", ")
+			};
+
+			let mut notes = vec![];
+			for &color in [RED, YELLOW].iter() {
+				let found = threats::analyse(&self.board, color);
+				if !found.winning_moves.is_empty() {
+					notes.push(format!(
+						"{} can finish a line in column {}",
+						color,
+						columns(&found.winning_moves)
+					));
+				} else if color == self.board.active_player && !found.must_block.is_empty() {
+					notes.push(format!(
+						"{} has to block column {}",
+						color,
+						columns(&found.must_block)
+					));
+				}
+
+				if !found.unplayable.is_empty() {
+					notes.push(format!(
+						"{} has {} threats on odd rows and {} on even rows",
+						color, found.odd_threats, found.even_threats
+					));
+				}
+			}
+			if let Some(zugzwang) = threats::zugzwang(&self.board) {
+				notes.push(zugzwang.to_string());
+			}
+
+			html! {
+				<div class="threats">
+					{notes.into_iter().map(|note| html! {<p>{note}</p>}).collect::<Html>()}
+				</div>
+			}
+		};
+
+		let history_buttons = move || -> Html {
+			html! {
+				<div class="history">
+					<button
+						class="history__button"
+						disabled=self.before_first_move()
+						onclick=self.link.callback(move |_| Msg::Undo)
+					>
+						{"UNDO"}
+					</button>
+					<button
+						class="history__button"
+						disabled=!self.board.can_redo()
+						onclick=self.link.callback(move |_| Msg::Redo)
+					>
+						{"REDO"}
+					</button>
+				</div>
+			}
+		};
+
 		let floating_piece_class = move || -> &str {
 			match self.board.active_player {
 				RED => "piece--red",
@@ -231,18 +885,44 @@ impl Component for Connect4Board {
 			}
 		};
 
+		// In Pop Out each column has a button under it for popping out its bottom piece
+		let pop_out_buttons = move || -> Html {
+			if !self.board.rules.pop_out {
+				return html! {};
+			}
+
+			let bottom_row = self.board.rules.num_rows - 1;
+			html! {
+				<div class="pop-out">
+				{
+					(0..self.board.rules.num_cols).into_iter().map(|col| {
+						html! {
+							<button
+								class="pop-out__button"
+								disabled=self.board.is_terminal || self.board.board[bottom_row][col] != Some(self.board.active_player)
+								onclick=self.link.callback(move |_| Msg::PopPiece(col))
+							>
+								{"POP"}
+							</button>
+						}
+					}).collect::<Html>()
+				}
+				</div>
+			}
+		};
+
 		html! {
 			<div class="container">
 				<div class="board">
 				{
-					(0..NUM_COLS).into_iter().map(|col| {
+					(0..self.board.rules.num_cols).into_iter().map(|col| {
 						return html! {
 							<div class="column" onclick=self.link.callback(move |_| Msg::DropPiece(col))>
 								<div class="cell cell--floating">
 									<div class={format!("piece piece--hidden {}", floating_piece_class())}>{floating_piece_letter()}</div>
 								</div>
 								{
-									(0..NUM_ROWS).into_iter().map(|row| {
+									(0..self.board.rules.num_rows).into_iter().map(|row| {
 										return html! {
 											<div class="cell">
 												{check_for_piece(row, col)}
@@ -255,10 +935,32 @@ impl Component for Connect4Board {
 					}).collect::<Html>()
 				}
 				</div>
+				{pop_out_buttons()}
+				{hint_row()}
+				{threat_notes()}
 				{game_status()}
+				<p class="move-error">{self.move_error.map(|err| err.to_string()).unwrap_or_default()}</p>
+				<p class="summary">{summary()}</p>
+				{engine_thinks()}
 				<div class="dashboard">
 					<button class="dashboard__reset" onclick=self.link.callback(move |_| Msg::Reset)>{"RESET"}</button>
-					{opponent_buttons()}
+					{history_buttons()}
+					<button
+						class=format!("dashboard__hint {}", if self.hint {"dashboard__hint--on"} else {""})
+						disabled=self.spectator.is_some()
+						onclick=self.link.callback(move |_| Msg::ToggleHint)
+					>
+						{"HINT"}
+					</button>
+					<a class="dashboard__share" href=position_link(&self.board.position())>{"SHARE POSITION"}</a>
+					{
+						match self.spectator {
+							Some(spectator) => spectator_buttons(spectator),
+							None => opponent_buttons(),
+						}
+					}
+					{side_buttons()}
+					{board_size_buttons()}
 				</div>
 			</div>
 		}
@@ -35,6 +35,14 @@ pub struct GameInfo {
   pub to_wins: i32, // toot and otto wins
   pub to_loss: i32, // toot and otto losses
   pub to_ties: i32, // toot and otto ties
+  pub xo_first: i32, // connect 4 games played as red
+  pub xo_second: i32, // connect 4 games played as yellow
+  pub to_first: i32, // toot and otto games played as toot
+  pub to_second: i32, // toot and otto games played as otto
+  pub xo_hinted: i32, // connect 4 games played with hints, not ranked
+  pub to_hinted: i32, // toot and otto games played with hints, not ranked
+  pub xo_undone: i32, // connect 4 games with moves taken back, not ranked
+  pub to_undone: i32, // toot and otto games with moves taken back, not ranked
 }
 
 impl Stats {
@@ -146,9 +154,17 @@ impl Component for Stats {
             <h2 class="stats_entry">{format!("Connect 4 wins {}", gi.xo_wins)}</h2>
             <h2 class="stats_entry">{format!("Connect 4 losses {}", gi.xo_loss)}</h2>
             <h2 class="stats_entry">{format!("Connect 4 ties {}", gi.xo_ties)}</h2>
+            <h2 class="stats_entry">{format!("Connect 4 games as red {}", gi.xo_first)}</h2>
+            <h2 class="stats_entry">{format!("Connect 4 games as yellow {}", gi.xo_second)}</h2>
+            <h2 class="stats_entry">{format!("Connect 4 games with hints {}", gi.xo_hinted)}</h2>
+            <h2 class="stats_entry">{format!("Connect 4 games with undos {}", gi.xo_undone)}</h2>
             <h2 class="stats_entry">{format!("Toot and Otto wins {}", gi.to_wins)}</h2>
             <h2 class="stats_entry">{format!("Toot and Otto losses {}", gi.to_loss)}</h2>
             <h2 class="stats_entry">{format!("Toot and Otto ties {}", gi.to_ties)}</h2>
+            <h2 class="stats_entry">{format!("Toot and Otto games as TOOT {}", gi.to_first)}</h2>
+            <h2 class="stats_entry">{format!("Toot and Otto games as OTTO {}", gi.to_second)}</h2>
+            <h2 class="stats_entry">{format!("Toot and Otto games with hints {}", gi.to_hinted)}</h2>
+            <h2 class="stats_entry">{format!("Toot and Otto games with undos {}", gi.to_undone)}</h2>
           </div>
         }
       }
@@ -1,29 +1,106 @@
 use crate::{
+	cpu_worker::{CpuRequest, CpuResponse, CpuWorkerBridge, MoveCode, Position, Report},
+	engine::{
+		game::Game,
+		minimax::{format_score, SearchReport},
+		transposition::Bound,
+	},
+	switch::{position_link, shared_position},
 	toot_and_otto::{
 		cpu_toot,
 		piece_letter::{PieceLetter, PieceLetter::*},
-		player::Player::*,
-		toot_and_otto::{TootAndOtto, NUM_COLS, NUM_ROWS},
+		player::{Player, Player::*},
+		rules::TootAndOttoRules,
+		toot_and_otto::TootAndOtto,
 	},
-	types::opponent::Opponent,
+	types::{move_error::MoveError, opponent::Opponent, spectator::Spectator},
 };
 use serde_json::json;
+use std::time::Duration;
 use strum::IntoEnumIterator;
 use yew::format::Json;
 use yew::services::fetch::{FetchService, FetchTask, Request, Response};
+use yew::services::timeout::{TimeoutService, TimeoutTask};
 use yew::{html, Component, ComponentLink, Html, ShouldRender};
 
 pub struct TootAndOttoBoard {
 	link: ComponentLink<Self>,
 	board: TootAndOtto,
 	vs: Opponent,
+	/// The player the user is, the CPU plays the other one
+	user: Player,
 	fetch_task: Option<FetchTask>,
+	/// Why the last piece the user tried to drop was refused
+	move_error: Option<MoveError>,
+	/// The CPUs playing each other when the user is watching instead of playing
+	spectator: Option<Spectator>,
+	/// Whether the watched CPUs move on their own, `delay_millis` apart
+	auto_play: bool,
+	delay_millis: u64,
+	auto_play_task: Option<TimeoutTask>,
+	/// What the search behind the CPU's last move found, `None` when the last
+	/// CPU didn't search a limited depth
+	thinking: Option<SearchReport<(usize, PieceLetter)>>,
+	/// Whether each move's score is shown to the player to move
+	hint: bool,
+	/// The scores shown while `hint` is on
+	hints: Option<SearchReport<(usize, PieceLetter)>>,
+	/// Whether hints were shown during this game, which keeps it out of the
+	/// user's ranked stats
+	hinted: bool,
+	/// Whether a move was taken back during this game, which also keeps it out
+	/// of the user's ranked stats
+	undone: bool,
+	/// Whether the result of this game has been sent, it's only sent once
+	recorded: bool,
+	/// Whether the game is played out from a shared position rather than from
+	/// the start, which keeps its result out of the user's stats
+	shared: bool,
+	/// The worker the CPU searches in, so the page keeps responding meanwhile
+	worker: CpuWorkerBridge,
+	/// Whether the CPU is searching for its next move
+	cpu_thinking: bool,
 }
 
+/// The delays between moves, in milliseconds, that can be picked when watching
+const DELAYS: [u64; 4] = [250, 500, 1000, 2000];
+
+/// The board sizes and letters that can be picked before the first move
+const BOARD_SIZES: [TootAndOttoRules; 4] = [
+	TootAndOttoRules::STANDARD,
+	TootAndOttoRules {
+		num_cols: 6,
+		num_rows: 4,
+		pieces_per_letter: None,
+	},
+	TootAndOttoRules {
+		num_cols: 7,
+		num_rows: 6,
+		pieces_per_letter: Some(11),
+	},
+	TootAndOttoRules {
+		num_cols: 8,
+		num_rows: 6,
+		pieces_per_letter: Some(12),
+	},
+];
+
 pub enum Msg {
 	DropPiece(PieceLetter, usize),
 	Reset,
+	Undo,
+	Redo,
 	ChangeOpponent(Opponent),
+	ChangeSide(Player),
+	ChangeRules(TootAndOttoRules),
+	Spectate,
+	ChangeEngine(Player, Opponent),
+	Step,
+	ToggleAutoPlay,
+	ChangeDelay(u64),
+	AutoPlay,
+	ToggleHint,
+	WorkerResponse(CpuResponse),
 	ReceiveResponse(Result<String, anyhow::Error>),
 }
 
@@ -40,7 +117,11 @@ impl TootAndOttoBoard {
 		if username == "" {
 			return;
 		}
-		let body = &json!({"username": &username, "game": 1, "win": win});
+		let side = match self.user {
+			TOOT => 0,
+			OTTO => 1,
+		};
+		let body = &json!({"username": &username, "game": 1, "win": win, "side": side, "hinted": self.hinted, "undone": self.undone});
 		let request = Request::post("http://localhost:8000/update_score")
 			.header("Content-Type", "application/json")
 			.body(Json(body))
@@ -56,72 +137,337 @@ impl TootAndOttoBoard {
 		// 4. store the task so it isn't canceled immediately
 		self.fetch_task = Some(task);
 	}
+
+	/// Updates the user's score once the game is over
+	fn record_result(&mut self) {
+		if !self.board.is_terminal || self.recorded || self.shared {
+			return;
+		}
+		self.recorded = true;
+
+		match self.board.winner {
+			Some(winner) if winner == self.user => self.update_score(1),
+			Some(_) => self.update_score(0),
+			None => self.update_score(2),
+		}
+	}
+
+	/// Asks for the CPU's letter when it's the CPU's turn
+	fn play_cpu_move(&mut self) {
+		if self.spectator.is_none()
+			&& self.vs != Opponent::Human
+			&& !self.board.is_terminal
+			&& self.board.active_player != self.user
+		{
+			self.request_cpu_move(self.vs);
+		}
+	}
+
+	/// Whether the user can move, which is always when both players are human
+	fn is_users_turn(&self) -> bool {
+		self.vs == Opponent::Human || self.board.active_player == self.user
+	}
+
+	/// Asks for the letter of the watched CPU whose turn it is, the next one is
+	/// waited for once it's dropped when they move on their own
+	fn play_spectator_move(&mut self) {
+		match self.spectator {
+			Some(spectator) if !self.cpu_thinking && !self.board.is_terminal => {
+				self.request_cpu_move(spectator.engines[self.board.active_player.index()])
+			}
+			_ => {}
+		}
+	}
+
+	/// Waits `delay_millis` before the next move when the watched CPUs move on
+	/// their own, and stops waiting otherwise
+	fn schedule_auto_play(&mut self) {
+		self.auto_play_task = match self.auto_play && !self.board.is_terminal {
+			true => Some(TimeoutService::spawn(
+				Duration::from_millis(self.delay_millis),
+				self.link.callback(|_| Msg::AutoPlay),
+			)),
+			false => None,
+		};
+	}
+
+	/// Asks the worker to score the moves of the player to move while hints
+	/// are on, the game is marked as hinted once they are shown
+	fn update_hints(&mut self) {
+		self.hints = None;
+		if self.hint && self.spectator.is_none() && !self.board.is_terminal && !self.cpu_thinking {
+			self.worker.send(CpuRequest::hints(self.worker_position()));
+		}
+	}
+
+	/// The position to send to the worker
+	fn worker_position(&self) -> Position {
+		Position::TootAndOtto(self.board.position())
+	}
+
+	/// Asks the worker for the move `engine` makes in the position
+	fn request_cpu_move(&mut self, engine: Opponent) {
+		let request = CpuRequest::cpu_move(self.worker_position(), engine);
+		self.worker.send(request);
+		self.cpu_thinking = true;
+	}
+
+	/// Gives up on the CPU's search, the position it was searching is gone
+	fn cancel_cpu_move(&mut self) {
+		self.worker.cancel();
+		self.cpu_thinking = false;
+	}
+
+	/// Plays the move the worker picked, or shows the hints it found while
+	/// they're on, as long as the position it searched is still on the board
+	fn receive_response(&mut self, response: CpuResponse) -> ShouldRender {
+		match self.worker.receive(response) {
+			Some(CpuResponse::Move {
+				position,
+				mv,
+				report,
+			}) if position == self.worker_position() => {
+				self.cpu_thinking = false;
+				self.thinking = report.map(Report::unpack);
+				if let Some(mv) = mv {
+					let (col, letter) = <(usize, PieceLetter)>::decode(mv);
+					self.board
+						.drop(letter, col)
+						.expect("The CPU only picks moves that can be played");
+				}
+
+				match self.spectator {
+					Some(_) => self.schedule_auto_play(),
+					None => self.record_result(),
+				}
+				self.update_hints();
+				true
+			}
+			Some(CpuResponse::Hints { position, report })
+				if self.hint && position == self.worker_position() =>
+			{
+				self.hints = report.map(Report::unpack);
+				self.hinted |= self.hints.is_some();
+				true
+			}
+			_ => false,
+		}
+	}
+
+	/// Whether the user has yet to move, the CPU may already have made the
+	/// first move when it goes first
+	///
+	/// The moves that set up a shared position don't count, so the opponent
+	/// and side can still be picked before playing it out
+	fn before_first_move(&self) -> bool {
+		let board = &self.board;
+		match board.moves_played.saturating_sub(board.set_up_moves) {
+			0 => true,
+			1 => {
+				self.spectator.is_none()
+					&& self.vs != Opponent::Human
+					&& self.board.active_player == self.user
+			}
+			_ => false,
+		}
+	}
+
+	/// Starts the game over with the same rules, or from the shared position
+	/// when one was set up, letting the CPU move first when the user plays second
+	fn restart(&mut self) {
+		self.cancel_cpu_move();
+		self.move_error = None;
+		self.thinking = None;
+		self.hinted = false;
+		self.undone = false;
+		self.recorded = false;
+		self.auto_play = false;
+		self.schedule_auto_play();
+		while self.board.moves_played > self.board.set_up_moves && self.board.undo() {}
+		self.board.history_len = self.board.moves_played;
+		self.play_cpu_move();
+		self.update_hints();
+	}
 }
 
 impl Component for TootAndOttoBoard {
 	type Message = Msg;
 	type Properties = ();
 	fn create(_: Self::Properties, link: ComponentLink<Self>) -> Self {
-		Self {
-			link,
-			board: TootAndOtto::new(),
-			vs: Opponent::Human,
-			fetch_task: None,
+		let worker = CpuWorkerBridge::new(link.callback(Msg::WorkerResponse));
+
+		// A shared position is set up to be played out from either side
+		match shared_position().and_then(|position| TootAndOtto::from_position(&position).ok()) {
+			Some(board) => Self {
+				link,
+				board,
+				vs: Opponent::Human,
+				user: TOOT,
+				fetch_task: None,
+				move_error: None,
+				spectator: None,
+				auto_play: false,
+				delay_millis: DELAYS[1],
+				auto_play_task: None,
+				thinking: None,
+				hint: false,
+				hints: None,
+				hinted: false,
+				undone: false,
+				recorded: false,
+				shared: true,
+				worker,
+				cpu_thinking: false,
+			},
+			None => Self {
+				link,
+				board: TootAndOtto::new(),
+				vs: Opponent::Human,
+				user: TOOT,
+				fetch_task: None,
+				move_error: None,
+				spectator: None,
+				auto_play: false,
+				delay_millis: DELAYS[1],
+				auto_play_task: None,
+				thinking: None,
+				hint: false,
+				hints: None,
+				hinted: false,
+				undone: false,
+				recorded: false,
+				shared: false,
+				worker,
+				cpu_thinking: false,
+			},
 		}
 	}
 
 	fn update(&mut self, msg: Self::Message) -> ShouldRender {
 		match msg {
+			// Only the CPUs move while the user is watching, and the user waits
+			// for the CPU to move and never moves for it
+			Msg::DropPiece(..)
+				if self.spectator.is_some() || self.cpu_thinking || !self.is_users_turn() =>
+			{
+				return false
+			}
 			Msg::DropPiece(letter, col) => {
-				if self.board.is_terminal == true {
-					return false;
-				} else if self.board.drop(letter, col) == false {
-					return false;
+				self.move_error = self.board.drop(letter, col).err();
+				if self.move_error.is_some() {
+					return true;
 				}
 
-				if self.board.is_terminal {
-					match self.board.winner {
-						None => {
-							// TODO: Insert a tie into the db
-							self.update_score(2);
-						}
-						Some(winner) => match winner {
-							OTTO => self.update_score(0),
-							TOOT => self.update_score(1),
-						},
+				self.record_result();
+				self.play_cpu_move();
+				self.update_hints();
+			}
+			Msg::ChangeOpponent(opponent) => {
+				if self.before_first_move() {
+					self.vs = opponent;
+					self.spectator = None;
+					self.restart();
+				}
+			}
+			Msg::ChangeSide(side) => {
+				if self.before_first_move() {
+					self.user = side;
+					self.spectator = None;
+					self.restart();
+				}
+			}
+			Msg::Spectate => {
+				if self.before_first_move() && self.spectator.is_none() {
+					self.spectator = Some(Spectator::default());
+					self.restart();
+				}
+			}
+			Msg::ChangeEngine(side, engine) => {
+				if let Some(spectator) = &mut self.spectator {
+					spectator.engines[side.index()] = engine;
+				}
+			}
+			Msg::Step => {
+				if !self.auto_play {
+					self.play_spectator_move();
+				}
+			}
+			Msg::ToggleAutoPlay => {
+				self.auto_play = !self.auto_play;
+				self.schedule_auto_play();
+			}
+			Msg::ChangeDelay(delay_millis) => self.delay_millis = delay_millis,
+			Msg::WorkerResponse(response) => return self.receive_response(response),
+			Msg::ToggleHint => {
+				self.hint = !self.hint;
+				self.update_hints();
+			}
+			Msg::AutoPlay => {
+				if self.auto_play {
+					self.play_spectator_move();
+				}
+			}
+			Msg::Reset => self.restart(),
+			Msg::ChangeRules(rules) => {
+				if self.before_first_move() {
+					// The perfect CPU can't play every board, the hard CPU takes its place
+					let engine = |engine| match cpu_toot::can_play(engine, rules) {
+						true => engine,
+						false => Opponent::HardCPU,
+					};
+					self.vs = engine(self.vs);
+					if let Some(spectator) = &mut self.spectator {
+						spectator.engines = spectator.engines.map(engine);
 					}
-					return true;
+
+					self.board = TootAndOtto::with_rules(rules);
+					self.shared = false;
+					self.restart();
+				}
+			}
+			Msg::Undo => {
+				// The CPU's first move stays when it goes first
+				if self.before_first_move() {
+					return false;
 				}
 
-				let cpu_depth = match self.vs {
-					Opponent::Human => return true,
-					Opponent::EasyCPU => 1,
-					Opponent::MediumCPU => 2,
-					Opponent::HardCPU => 3,
+				self.cancel_cpu_move();
+				self.move_error = None;
+				self.thinking = None;
+				self.auto_play = false;
+				self.schedule_auto_play();
+				let is_undone = match (self.spectator, self.vs) {
+					(Some(_), _) | (None, Opponent::Human) => self.board.undo(),
+					_ => self.board.undo_turn(self.user),
 				};
-				let (best_col, best_letter) = cpu_toot::make_move(self.board, cpu_depth);
-				self.board.drop(best_letter, best_col);
-				if self.board.is_terminal {
-					match self.board.winner {
-						None => {
-							// TODO: Insert a tie into the db
-							self.update_score(2);
-						}
-						Some(winner) => match winner {
-							OTTO => self.update_score(0),
-							TOOT => self.update_score(1),
-						},
-					}
-					return true;
+
+				// The CPU moves again when there was no turn of the user's to go back to
+				if self.spectator.is_none() {
+					self.undone |= is_undone;
+					self.record_result();
+					self.play_cpu_move();
 				}
+				self.update_hints();
+				return is_undone;
 			}
-			Msg::ChangeOpponent(opponent) => {
-				if self.board.moves_played == 0 {
-					self.vs = opponent;
+			Msg::Redo => {
+				self.cancel_cpu_move();
+				self.move_error = None;
+				self.thinking = None;
+				self.auto_play = false;
+				self.schedule_auto_play();
+				let is_redone = match (self.spectator, self.vs) {
+					(Some(_), _) | (None, Opponent::Human) => self.board.redo(),
+					_ => self.board.redo_turn(self.user),
+				};
+
+				// The redone moves can end the game, or stop on the CPU's turn
+				if self.spectator.is_none() {
+					self.record_result();
+					self.play_cpu_move();
 				}
-			}
-			Msg::Reset => {
-				self.board = TootAndOtto::new();
+				self.update_hints();
+				return is_redone;
 			}
 			Msg::ReceiveResponse(response) => match response.unwrap().as_str() {
 				"Update success" => {}
@@ -186,12 +532,12 @@ impl Component for TootAndOttoBoard {
 
 			let arrow_text = match self.board.is_terminal {
 				false => "",
-				true => match self.board.winner {
-					Some(winner) => match winner {
-						TOOT => "YOU WIN!!!",
-						OTTO => "CPU WINS :(",
-					},
-					None => "TIE GAME!",
+				true => match (self.spectator, self.board.winner) {
+					(Some(_), Some(TOOT)) => "TOOT WINS!",
+					(Some(_), Some(OTTO)) => "OTTO WINS!",
+					(None, Some(winner)) if winner == self.user => "YOU WIN!!!",
+					(None, Some(_)) => "CPU WINS :(",
+					(_, None) => "TIE GAME!",
 				},
 			};
 
@@ -206,7 +552,7 @@ impl Component for TootAndOttoBoard {
 
 		let opponent_buttons = move || -> Html {
 			html! {
-				Opponent::iter().map(|opponent| {
+				Opponent::iter().filter(|&opponent| cpu_toot::can_play(opponent, self.board.rules)).map(|opponent| {
 					html! {
 						<button
 							class=format!("opponent__button {}", if self.vs == opponent {"opponent__button--selected"} else {""})
@@ -219,6 +565,220 @@ impl Component for TootAndOttoBoard {
 			}
 		};
 
+		let side_buttons = move || -> Html {
+			html! {
+				<div class=format!("opponent {}", if self.before_first_move() { "" } else { "opponent--disabled" }) >
+					{
+						[TOOT, OTTO].iter().map(|&side| {
+							html! {
+								<button
+									class=format!("opponent__button {}", if self.spectator.is_none() && self.user == side {"opponent__button--selected"} else {""})
+									onclick=self.link.callback(move |_| Msg::ChangeSide(side))
+								>
+									{format!("PLAY AS {}", side)}
+								</button>
+						}}).collect::<Html>()
+					}
+					<button
+						class=format!("opponent__button {}", if self.spectator.is_some() {"opponent__button--selected"} else {""})
+						onclick=self.link.callback(move |_| Msg::Spectate)
+					>
+						{"WATCH CPUS"}
+					</button>
+				</div>
+			}
+		};
+
+		// While watching, each side picks its CPU and the moves are stepped
+		// through or played on their own
+		let spectator_buttons = move |spectator: Spectator| -> Html {
+			html! {
+				<>
+					{
+						[TOOT, OTTO].iter().map(|&side| {
+							html! {
+								<div class="opponent">
+								{
+									Opponent::iter().filter(|&engine| engine != Opponent::Human && cpu_toot::can_play(engine, self.board.rules)).map(|engine| {
+										html! {
+											<button
+												class=format!("opponent__button {}", if spectator.engines[side.index()] == engine {"opponent__button--selected"} else {""})
+												onclick=self.link.callback(move |_| Msg::ChangeEngine(side, engine))
+											>
+												{format!("{} {}", side, engine.to_string().to_uppercase())}
+											</button>
+										}
+									}).collect::<Html>()
+								}
+								</div>
+							}
+						}).collect::<Html>()
+					}
+					<div class=format!("opponent {}", if self.board.is_terminal { "opponent--disabled" } else { "" }) >
+						<button
+							class="opponent__button"
+							disabled=self.auto_play || self.cpu_thinking || self.board.is_terminal
+							onclick=self.link.callback(move |_| Msg::Step)
+						>
+							{"STEP"}
+						</button>
+						<button
+							class=format!("opponent__button {}", if self.auto_play {"opponent__button--selected"} else {""})
+							onclick=self.link.callback(move |_| Msg::ToggleAutoPlay)
+						>
+							{if self.auto_play { "PAUSE" } else { "AUTO PLAY" }}
+						</button>
+						{
+							DELAYS.iter().map(|&delay_millis| {
+								html! {
+									<button
+										class=format!("opponent__button {}", if self.delay_millis == delay_millis {"opponent__button--selected"} else {""})
+										onclick=self.link.callback(move |_| Msg::ChangeDelay(delay_millis))
+									>
+										{format!("{}S", delay_millis as f64 / 1000.0)}
+									</button>
+								}
+							}).collect::<Html>()
+						}
+					</div>
+				</>
+			}
+		};
+
+		// Sums up the game once the watched CPUs have finished it, and shows when
+		// a CPU is thinking
+		let summary = move || -> String {
+			match self.spectator {
+				Some(spectator) if self.board.is_terminal => spectator.summary(
+					[TOOT, OTTO],
+					self.board.winner.map(|winner| winner.index()),
+					self.board.moves_played,
+				),
+				_ if self.cpu_thinking => String::from("CPU thinking…"),
+				_ => String::new(),
+			}
+		};
+
+		let board_size_buttons = move || -> Html {
+			html! {
+				<div class=format!("opponent {}", if self.before_first_move() { "" } else { "opponent--disabled" }) >
+					{
+						BOARD_SIZES.iter().map(|&rules| {
+							html! {
+								<button
+									class=format!("opponent__button {}", if self.board.rules == rules {"opponent__button--selected"} else {""})
+									onclick=self.link.callback(move |_| Msg::ChangeRules(rules))
+								>
+									{rules.to_string().to_uppercase()}
+								</button>
+						}}).collect::<Html>()
+					}
+				</div>
+			}
+		};
+
+		// With unlimited letters there is no count to show
+		let piece_count = move |player_index: usize, letter_index: usize| -> String {
+			match self.board.rules.pieces_per_letter {
+				None => String::from("\u{221E}"),
+				Some(_) => self.board.piece_counts[player_index][letter_index].to_string(),
+			}
+		};
+
+		// Shows the score, expected line and root move scores behind the CPU's
+		// last letter
+		let engine_thinks = move || -> Html {
+			let report = match &self.thinking {
+				Some(report) => report,
+				None => return html! {},
+			};
+			let format_move = |(col, letter): (usize, PieceLetter)| format!("{}{}", letter, col);
+
+			html! {
+				<div class="thinking">
+					<p class="thinking__title">{"ENGINE THINKS"}</p>
+					<p>{format!("{} positions, {} moves deep", report.nodes, report.depth)}</p>
+					<p>
+						{format!(
+							"Best {} ({}), expecting {}",
+							format_move(report.best_move),
+							format_score(report.score),
+							report.principal_variation.iter().map(|&mv| format_move(mv)).collect::<Vec<_>>().join(" ")
+						)}
+					</p>
+					<div class="thinking__moves">
+					{
+						report.moves.iter().map(|scored| {
+							let score = match scored.bound {
+								Bound::Upper => format!("at most {}", format_score(scored.score)),
+								_ => format_score(scored.score),
+							};
+							html! {
+								<span class="thinking__move">{format!("{}: {}", format_move(scored.mv), score)}</span>
+							}
+						}).collect::<Html>()
+					}
+					</div>
+				</div>
+			}
+		};
+
+		// Scores each column's T and O for the player to move, with the best ones
+		// highlighted
+		let hint_row = move || -> Html {
+			let hints = match &self.hints {
+				Some(hints) => hints,
+				None => return html! {},
+			};
+			let best = hints.moves.iter().map(|scored| scored.score).max();
+			let hint = |col: usize, letter: PieceLetter| -> Html {
+				match hints.moves.iter().find(|scored| scored.mv == (col, letter)) {
+					Some(scored) => html! {
+						<span class=format!("hints__move {}", if Some(scored.score) == best {"hints__move--best"} else {""})>
+							{format!("{}: {}", letter, format_score(scored.score))}
+						</span>
+					},
+					None => html! {},
+				}
+			};
+
+			html! {
+				<div class="hints">
+				{
+					(0..self.board.rules.num_cols).into_iter().map(|col| {
+						html! {
+							<div class="hints__column">
+								{hint(col, PieceLetter::T)}
+								{hint(col, PieceLetter::O)}
+							</div>
+						}
+					}).collect::<Html>()
+				}
+				</div>
+			}
+		};
+
+		let history_buttons = move || -> Html {
+			html! {
+				<div class="history">
+					<button
+						class="history__button"
+						disabled=self.before_first_move()
+						onclick=self.link.callback(move |_| Msg::Undo)
+					>
+						{"UNDO"}
+					</button>
+					<button
+						class="history__button"
+						disabled=!self.board.can_redo()
+						onclick=self.link.callback(move |_| Msg::Redo)
+					>
+						{"REDO"}
+					</button>
+				</div>
+			}
+		};
+
 		let floating_pieces = move |col: usize| -> Html {
 			let show_piece = move |letter| -> &str {
 				let player_index = match self.board.active_player {
@@ -256,12 +816,12 @@ impl Component for TootAndOttoBoard {
 			<div class="container">
 				<div class="board">
 					{
-						(0..NUM_COLS).into_iter().map(|col| {
+						(0..self.board.rules.num_cols).into_iter().map(|col| {
 							return html! {
 								<div class="column">
 									{ floating_pieces(col) }
 									{
-										(0..NUM_ROWS).into_iter().map(|row| {
+										(0..self.board.rules.num_rows).into_iter().map(|row| {
 											return html! {
 												<div class="cell">
 													{check_for_piece(row, col)}
@@ -274,22 +834,44 @@ impl Component for TootAndOttoBoard {
 						}).collect::<Html>()
 					}
 				</div>
+				{hint_row()}
 				<div class="piece-counts__container">
 					<div class="piece-counts__player">
-						<p class="piece-counts__player__piece">{format!("TOOT's T's: {}", self.board.piece_counts[0][0])}</p>
-						<p class="piece-counts__player__piece">{format!("TOOT's O's: {}", self.board.piece_counts[0][1])}</p>
+						<p class="piece-counts__player__piece">{format!("TOOT's T's: {}", piece_count(0, 0))}</p>
+						<p class="piece-counts__player__piece">{format!("TOOT's O's: {}", piece_count(0, 1))}</p>
 					</div>
 					<div class="piece-counts__player">
-						<p class="piece-counts__player__piece">{format!("OTTO's T's: {}", self.board.piece_counts[1][0])}</p>
-						<p class="piece-counts__player__piece">{format!("OTTO's O's: {}", self.board.piece_counts[1][1])}</p>
+						<p class="piece-counts__player__piece">{format!("OTTO's T's: {}", piece_count(1, 0))}</p>
+						<p class="piece-counts__player__piece">{format!("OTTO's O's: {}", piece_count(1, 1))}</p>
 					</div>
 				</div>
 				{game_status()}
+				<p class="move-error">{self.move_error.map(|err| err.to_string()).unwrap_or_default()}</p>
+				<p class="summary">{summary()}</p>
+				{engine_thinks()}
 				<div class="dashboard">
 					<button class="dashboard__reset" onclick=self.link.callback(move |_| Msg::Reset)>{"RESET"}</button>
-					<div class=format!("opponent {}", if self.board.moves_played > 0 { "opponent--disabled" } else { "" })>
-						{opponent_buttons()}
-					</div>
+					{history_buttons()}
+					<button
+						class=format!("dashboard__hint {}", if self.hint {"dashboard__hint--on"} else {""})
+						disabled=self.spectator.is_some()
+						onclick=self.link.callback(move |_| Msg::ToggleHint)
+					>
+						{"HINT"}
+					</button>
+					<a class="dashboard__share" href=position_link(&self.board.position())>{"SHARE POSITION"}</a>
+					{
+						match self.spectator {
+							Some(spectator) => spectator_buttons(spectator),
+							None => html! {
+								<div class=format!("opponent {}", if self.before_first_move() { "" } else { "opponent--disabled" })>
+									{opponent_buttons()}
+								</div>
+							},
+						}
+					}
+					{side_buttons()}
+					{board_size_buttons()}
 				</div>
 			</div>
 		}
@@ -1,267 +1,593 @@
-use super::piece_color::{PieceColor, PieceColor::*};
-use std::fmt::{Display, Formatter, Result};
+use super::{
+	connect4_move::{Connect4Move, Connect4Move::*},
+	piece_color::{PieceColor, PieceColor::*},
+	rules::{Connect4Rules, MAX_COLS, MAX_MOVES, MAX_ROWS},
+	weights::Connect4Weights,
+};
+use crate::{
+	engine::{game::Game, zobrist},
+	types::move_error::MoveError,
+};
+use std::fmt::{self, Display, Formatter};
 
 /// A wrapper around the primary `Board` component
+///
+/// The grid is kept twice: `board` for display, and one bitboard per color for
+/// fast win detection. Each column takes `MAX_ROWS + 1` bits from the bottom up,
+/// the spare bit on top keeps lines from wrapping into the next column
+///
+/// Both have room for the largest board, a smaller board only uses the first
+/// `rules.num_rows` rows and `rules.num_cols` columns of `board`
 #[derive(Copy, Clone)]
 pub struct Connect4 {
+	pub rules: Connect4Rules,
+	/// How `calculate_score` values each kind of window
+	pub weights: Connect4Weights,
 	pub board: Board,
+	pub bitboards: [u128; 2], // [RED's pieces, YELLOW's pieces]
+	pub hash: u64,
+	pub mirrored_hash: u64,
 	pub active_player: PieceColor,
 	pub moves_played: usize,
 	pub is_terminal: bool,
 	pub winner: Option<PieceColor>,
-	pub column_heights: [usize; NUM_COLS],
+	pub column_heights: [usize; MAX_COLS],
+	/// Every move played in order, moves past `moves_played` were undone and can be redone
+	pub history: [Connect4Move; MAX_MOVES],
+	/// The number of moves in `history` that can be replayed
+	pub history_len: usize,
+	/// How many of the first moves in `history` only set up the position the
+	/// board started from, game records start after them
+	pub set_up_moves: usize,
+	/// The columns ordered by distance from the center
+	column_order: [usize; MAX_COLS],
+	/// A bitboard with every cell of the board set
+	board_mask: u128,
+	/// The hash of the position before each move in `history`, so repeated
+	/// positions can be spotted in Pop Out
+	position_hashes: [u64; MAX_MOVES],
 }
 
-/// A 7x6 Connect 4 Board
-type Board = [[BoardCell; NUM_COLS]; NUM_ROWS];
-pub const NUM_COLS: usize = 7;
-pub const NUM_ROWS: usize = 6;
+/// A Connect 4 Board, top row first
+type Board = [[BoardCell; MAX_COLS]; MAX_ROWS];
 
 /// All the possible values for a board cell (None, Some(RED), SOME(YELLOW))
 type BoardCell = Option<PieceColor>;
 
+/// The number of bits each column takes up in a bitboard
+pub const COL_BITS: usize = MAX_ROWS + 1;
+
+/// The bitboard shifts that move along a column, a row and both diagonals
+const LINE_SHIFTS: [usize; 4] = [1, COL_BITS, COL_BITS - 1, COL_BITS + 1];
+
+/// A random key for every color in every cell, indexed the same way as the bitboards
+const ZOBRIST_KEYS: [[u64; MAX_COLS * COL_BITS]; 2] = {
+	let mut keys = [[0; MAX_COLS * COL_BITS]; 2];
+	let mut i = 0;
+	while i < MAX_COLS * COL_BITS {
+		keys[0][i] = zobrist::key(2 * i as u64);
+		keys[1][i] = zobrist::key(2 * i as u64 + 1);
+		i += 1;
+	}
+	keys
+};
+
+/// A random key for whose turn it is, only used in Pop Out where the same
+/// pieces can be on the board with either color to move
+const SIDE_KEY: u64 = zobrist::key(2 * (MAX_COLS * COL_BITS) as u64);
+
 impl Connect4 {
-	/// Initializes an empty Connect 4 board
+	/// Initializes an empty Connect 4 board of the standard size
 	pub fn new() -> Self {
+		Connect4::with_rules(Connect4Rules::STANDARD)
+	}
+
+	/// Initializes an empty board of the size set by `rules`, which should
+	/// come from `Connect4Rules::new`
+	pub fn with_rules(rules: Connect4Rules) -> Self {
+		// Sorts the columns by distance from the center, leftmost first on ties
+		let mut column_order = [0; MAX_COLS];
+		let mut columns: Vec<usize> = (0..rules.num_cols).collect();
+		columns.sort_by_key(|&col| (2 * col as isize - rules.num_cols as isize + 1).abs());
+		column_order[..rules.num_cols].copy_from_slice(&columns);
+
+		let board_mask = (0..rules.num_cols).fold(0, |mask, col| {
+			mask | ((1 << rules.num_rows) - 1) << (col * COL_BITS)
+		});
+
 		Connect4 {
-			board: [[None; NUM_COLS]; NUM_ROWS],
+			rules,
+			weights: Connect4Weights::DEFAULT,
+			board: [[None; MAX_COLS]; MAX_ROWS],
+			bitboards: [0; 2],
+			hash: 0,
+			mirrored_hash: 0,
 			moves_played: 0,
 			active_player: RED,
 			is_terminal: false,
 			winner: None,
-			column_heights: [0; NUM_COLS],
+			column_heights: [0; MAX_COLS],
+			history: [Drop(0); MAX_MOVES],
+			history_len: 0,
+			set_up_moves: 0,
+			column_order,
+			board_mask,
+			position_hashes: [0; MAX_MOVES],
 		}
 	}
 
-	/// Drops a piece of some `color` into a `col` on the the board
-	pub fn drop(&mut self, col: usize) -> bool {
+	/// Sets up a board of `rules` with the pieces in `grid`, top row first,
+	/// already in place and `active_player` to move
+	///
+	/// There are no moves to undo, and the pieces aren't checked against any
+	/// order they could have been played in, `Connect4::from_position` does
+	/// that for everything but Pop Out
+	pub fn with_pieces(
+		rules: Connect4Rules,
+		grid: &[Vec<Option<PieceColor>>],
+		active_player: PieceColor,
+	) -> Self {
+		let mut board = Connect4::with_rules(rules);
+		for (row, cells) in grid.iter().enumerate() {
+			for (col, &cell) in cells.iter().enumerate() {
+				if let Some(color) = cell {
+					let col_height = rules.num_rows - 1 - row;
+					let player = color.index();
+					board.board[row][col] = Some(color);
+					board.bitboards[player] |= 1 << (col * COL_BITS + col_height);
+					board.hash ^= ZOBRIST_KEYS[player][col * COL_BITS + col_height];
+					board.mirrored_hash ^=
+						ZOBRIST_KEYS[player][(rules.num_cols - 1 - col) * COL_BITS + col_height];
+					board.column_heights[col] += 1;
+				}
+			}
+		}
+
+		if active_player != board.active_player {
+			board.switch_player();
+		}
+
+		board.winner = board.winner_after_move(active_player.switch());
+		board.is_terminal = board.winner.is_some() || board.is_drawn();
+
+		board
+	}
+
+	/// Drops a piece of the active player's color into a `col` on the the board
+	pub fn drop(&mut self, col: usize) -> Result<(), MoveError> {
 		// Checks for some simple input errors
-		if col >= NUM_COLS {
-			return false;
+		if self.is_terminal {
+			return Err(MoveError::GameOver);
+		} else if col >= self.rules.num_cols {
+			return Err(MoveError::ColumnOutOfRange(col));
 		}
 
 		// Gets the number of pieces in the column and checks if it is full
 		let col_height = self.column_heights[col];
 
 		// Checks if the column can be dropped into
-		if col_height == NUM_ROWS {
-			return false;
+		if col_height == self.rules.num_rows {
+			return Err(MoveError::ColumnFull(col));
 		}
 
 		// Calculates which row the the piece should be inserted into
-		let row = NUM_ROWS - 1 - col_height;
+		let row = self.rules.num_rows - 1 - col_height;
 
 		// Inserts the piece into the board
+		let player = self.active_player.index();
+		self.position_hashes[self.moves_played] = self.hash;
 		self.board[row][col] = Some(self.active_player);
+		self.bitboards[player] |= 1 << (col * COL_BITS + col_height);
+		self.hash ^= ZOBRIST_KEYS[player][col * COL_BITS + col_height];
+		self.mirrored_hash ^=
+			ZOBRIST_KEYS[player][(self.rules.num_cols - 1 - col) * COL_BITS + col_height];
+
+		// A new move replaces any moves that were undone
+		self.history[self.moves_played] = Drop(col);
+		self.history_len = self.moves_played + 1;
+
 		self.moves_played += 1;
-		self.winner = match self.check_for_win(self.active_player) {
-			Some(_) => Some(self.active_player),
-			None => None,
+		self.winner = match self.has_won(self.active_player) {
+			true => Some(self.line_winner(self.active_player)),
+			false => None,
 		};
-		self.is_terminal = self.winner != None || self.moves_played == NUM_COLS * NUM_ROWS;
 		self.column_heights[col] += 1;
-		self.active_player = self.active_player.switch();
+		self.switch_player();
+		self.is_terminal = self.winner.is_some() || self.is_drawn();
 
-		true
+		Ok(())
 	}
 
-	/// Checks to see if a color has one the game
-	pub fn check_for_win(&self, color: PieceColor) -> Option<Vec<[usize; 2]>> {
-		let check_for_win_in_window = |window: &[BoardCell]| -> bool {
-			for cell in window.iter() {
-				match cell {
-					None => return false,
-					Some(c) => {
-						if *c != color {
-							return false;
-						}
-					}
-				}
-			}
+	/// Pops the active player's piece out of the bottom of a `col`, only
+	/// allowed in Pop Out
+	pub fn pop(&mut self, col: usize) -> Result<(), MoveError> {
+		if self.is_terminal {
+			return Err(MoveError::GameOver);
+		} else if !self.rules.pop_out {
+			return Err(MoveError::PopOutNotAllowed);
+		} else if col >= self.rules.num_cols {
+			return Err(MoveError::ColumnOutOfRange(col));
+		} else if self.bitboards[self.active_player.index()] & 1 << (col * COL_BITS) == 0 {
+			return Err(MoveError::CannotPop(col));
+		}
+
+		self.position_hashes[self.moves_played] = self.hash;
+		self.remove_bottom_piece(col);
+
+		// A new move replaces any moves that were undone
+		self.history[self.moves_played] = Pop(col);
+		self.history_len = self.moves_played + 1;
+		self.moves_played += 1;
+
+		// Moving a column down can finish lines for both colors at once
+		self.winner = self.winner_after_move(self.active_player);
+		self.switch_player();
+		self.is_terminal = self.winner.is_some() || self.is_drawn();
+
+		Ok(())
+	}
 
-			true
+	/// Works out who won after `color` moved from who has a line, a pop can
+	/// finish lines for both colors and then the line of `color` counts
+	fn winner_after_move(&self, color: PieceColor) -> Option<PieceColor> {
+		let line = match (self.has_won(color), self.has_won(color.switch())) {
+			(true, _) => Some(color),
+			(false, true) => Some(color.switch()),
+			(false, false) => None,
 		};
 
-		// Checks all the rows
-		for row in 0..NUM_ROWS {
-			for start_col in 0..NUM_COLS - 3 {
-				let window = &self.board[row][start_col..start_col + 4];
-				if check_for_win_in_window(window) {
-					return Some((0..4).into_iter().map(|i| [row, start_col + i]).collect());
-				}
-			}
+		line.map(|color| self.line_winner(color))
+	}
+
+	/// The winner once `color` has a line, which is the other color in misère
+	fn line_winner(&self, color: PieceColor) -> PieceColor {
+		match self.rules.misere {
+			true => color.switch(),
+			false => color,
 		}
+	}
 
-		// Performs a check across all columns
-		for col in 0..NUM_COLS {
-			for start_row in 0..NUM_ROWS - 3 {
-				let mut window = vec![];
-				(start_row..start_row + 4)
-					.into_iter()
-					.for_each(|row| window.push(self.board[row][col]));
+	/// Plays a drop or a pop for the active player
+	pub fn play(&mut self, mv: Connect4Move) -> Result<(), MoveError> {
+		match mv {
+			Drop(col) => self.drop(col),
+			Pop(col) => self.pop(col),
+		}
+	}
 
-				if check_for_win_in_window(&window) {
-					return Some((0..4).into_iter().map(|i| [start_row + i, col]).collect());
-				}
-			}
+	/// Hands the turn to the other color, in Pop Out the hash says whose turn it is
+	fn switch_player(&mut self) {
+		self.active_player = self.active_player.switch();
+		if self.rules.pop_out {
+			self.hash ^= SIDE_KEY;
+			self.mirrored_hash ^= SIDE_KEY;
+		}
+	}
+
+	/// Checks if the game is drawn, on a full board normally
+	///
+	/// In Pop Out a full board plays on, and the game is drawn when the player
+	/// to move can't pop either, when the same position comes up a third time
+	/// with the same color to move, or when it reaches `Connect4Rules::max_moves`
+	fn is_drawn(&self) -> bool {
+		let is_full = self.bitboards[0] | self.bitboards[1] == self.board_mask;
+		if !self.rules.pop_out {
+			return is_full;
+		}
+
+		let bottom_row = self.board_mask & !(self.board_mask << 1);
+		let can_pop = self.bitboards[self.active_player.index()] & bottom_row != 0;
+		let repeats = (0..self.moves_played)
+			.rev()
+			.skip(1)
+			.step_by(2)
+			.filter(|&i| self.position_hashes[i] == self.hash)
+			.count();
+
+		(is_full && !can_pop) || repeats >= 2 || self.moves_played == self.rules.max_moves()
+	}
+
+	/// Takes the bottom piece out of a column and moves the pieces above it down
+	fn remove_bottom_piece(&mut self, col: usize) {
+		self.toggle_column_hash(col);
+		let col_mask = ((1 << self.rules.num_rows) - 1) << (col * COL_BITS);
+		for pieces in self.bitboards.iter_mut() {
+			*pieces = (*pieces & !col_mask) | (*pieces & col_mask) >> 1 & col_mask;
+		}
+		self.toggle_column_hash(col);
+
+		for row in (1..self.rules.num_rows).rev() {
+			self.board[row][col] = self.board[row - 1][col];
 		}
+		self.board[0][col] = None;
+		self.column_heights[col] -= 1;
+	}
 
-		// Perform a check across positively sloped diagonals
-		for row in NUM_ROWS - 3..NUM_ROWS {
-			for col in 0..NUM_COLS - 3 {
-				let mut window = vec![];
-				(0..4)
-					.into_iter()
-					.for_each(|i| window.push(self.board[row - i][col + i]));
-				if check_for_win_in_window(&window) {
-					return Some((0..4).into_iter().map(|i| [row - i, col + i]).collect());
+	/// Moves the pieces in a column up and puts a piece of `color` under them,
+	/// the opposite of `remove_bottom_piece`
+	fn insert_bottom_piece(&mut self, col: usize, color: PieceColor) {
+		self.toggle_column_hash(col);
+		let col_mask = ((1 << self.rules.num_rows) - 1) << (col * COL_BITS);
+		for pieces in self.bitboards.iter_mut() {
+			*pieces = (*pieces & !col_mask) | (*pieces & col_mask) << 1 & col_mask;
+		}
+		self.bitboards[color.index()] |= 1 << (col * COL_BITS);
+		self.column_heights[col] += 1;
+		self.toggle_column_hash(col);
+
+		let bottom = self.rules.num_rows - 1;
+		for row in 0..bottom {
+			self.board[row][col] = self.board[row + 1][col];
+		}
+		self.board[bottom][col] = Some(color);
+	}
+
+	/// Adds or removes the keys of every piece in a column from both hashes
+	fn toggle_column_hash(&mut self, col: usize) {
+		let mirrored_col = self.rules.num_cols - 1 - col;
+		for (keys, &pieces) in ZOBRIST_KEYS.iter().zip(self.bitboards.iter()) {
+			for col_height in 0..self.column_heights[col] {
+				if pieces & 1 << (col * COL_BITS + col_height) != 0 {
+					self.hash ^= keys[col * COL_BITS + col_height];
+					self.mirrored_hash ^= keys[mirrored_col * COL_BITS + col_height];
 				}
 			}
 		}
+	}
 
-		// Perform a check across positively sloped diagonals
-		for row in 0..NUM_ROWS - 3 {
-			for col in 0..NUM_COLS - 3 {
-				let mut window = vec![];
-				(0..4)
-					.into_iter()
-					.for_each(|i| window.push(self.board[row + i][col + i]));
+	/// Takes back the last move played, returns false if there is none
+	pub fn undo(&mut self) -> bool {
+		if self.moves_played == 0 {
+			return false;
+		}
 
-				if check_for_win_in_window(&window) {
-					return Some((0..4).into_iter().map(|i| [row + i, col + i]).collect());
-				}
+		self.switch_player();
+		match self.history[self.moves_played - 1] {
+			Drop(col) => {
+				self.column_heights[col] -= 1;
+
+				// Removes the top piece of the column
+				let col_height = self.column_heights[col];
+				let row = self.rules.num_rows - 1 - col_height;
+				let player = self.active_player.index();
+				self.board[row][col] = None;
+				self.bitboards[player] ^= 1 << (col * COL_BITS + col_height);
+				self.hash ^= ZOBRIST_KEYS[player][col * COL_BITS + col_height];
+				self.mirrored_hash ^=
+					ZOBRIST_KEYS[player][(self.rules.num_cols - 1 - col) * COL_BITS + col_height];
 			}
+			// Puts the popped piece back under the column
+			Pop(col) => self.insert_bottom_piece(col, self.active_player),
 		}
+		self.moves_played -= 1;
+
+		// No moves are played once the game is over, so it wasn't over before this one
+		self.winner = None;
+		self.is_terminal = false;
 
-		None
+		true
 	}
 
-	/// Calculates a heuristic score for the current player and board position
-	pub fn calculate_score(&self, color: PieceColor) -> i32 {
-		const _CENTER_COL: i32 = 5; // Playing the center column
-		const _LINE_OF_TWO: i32 = 1; // Two pieces in a line
-		const _LINE_OF_THREE: i32 = 10; // 3 pieces in a line
-		const _LINE_OF_FOUR: i32 = 100_000; // Self Won
+	/// Plays the last move that was undone again, returns false if there is none
+	pub fn redo(&mut self) -> bool {
+		if !self.can_redo() {
+			return false;
+		}
 
-		const _OPPONENT_LINE_OF_THREE: i32 = -20; // Opponent can win
-		const _OPPONENT_LINE_OF_THREE_WITH_BELOW: i32 = -10_000; // Opponent can win
-		const _OPPONENT_LINE_OF_TWO: i32 = -3; // Opponent can setup for win
+		// Replaying the same move keeps the rest of the undone moves
+		let history_len = self.history_len;
+		let is_redone = self.play(self.history[self.moves_played]).is_ok();
+		self.history_len = history_len;
 
-		let mut score = 0;
+		is_redone
+	}
 
-		let calculate_window_score = |window: &[(BoardCell, bool)]| -> i32 {
-			let mut own_count = 0;
-			let mut opponent_count = 0;
-			let mut empty_no_below_count = 0;
-			let mut empty_with_below_count = 0;
-
-			for cell in window.iter() {
-				match cell.0 {
-					None => match cell.1 {
-						false => empty_no_below_count += 1,
-						true => empty_with_below_count += 1,
-					},
-					Some(c) => {
-						if c == color {
-							own_count += 1;
-						} else {
-							opponent_count += 1;
-						}
-					}
-				}
+	/// Checks if there are any undone moves left to play again
+	pub fn can_redo(&self) -> bool {
+		self.history_len > self.moves_played
+	}
+
+	/// Checks if a color has a full line of pieces anywhere on the board, which
+	/// loses instead in misère
+	pub fn has_won(&self, color: PieceColor) -> bool {
+		let pieces = self.bitboards[color.index()];
+
+		LINE_SHIFTS
+			.iter()
+			.any(|&shift| self.line_starts(pieces, shift) != 0)
+	}
+
+	/// Checks to see if a color has finished a line, returning the line, which
+	/// wins the game unless playing misère
+	pub fn check_for_win(&self, color: PieceColor) -> Option<Vec<[usize; 2]>> {
+		let pieces = self.bitboards[color.index()];
+
+		// Finds which pieces made the win
+		LINE_SHIFTS.iter().find_map(|&shift| {
+			let starts = self.line_starts(pieces, shift);
+			if starts == 0 {
+				return None;
 			}
 
-			if own_count > 0 && opponent_count > 0 {
+			let start = starts.trailing_zeros() as usize;
+			let line = (0..self.rules.line_length)
+				.map(|i| {
+					let bit = start + i * shift;
+					[self.rules.num_rows - 1 - bit % COL_BITS, bit / COL_BITS]
+				})
+				.collect();
+
+			Some(line)
+		})
+	}
+
+	/// Gets the cells of a bitboard that start a line of `rules.line_length` of
+	/// its cells, the line going up the bitboard in steps of `shift`
+	fn line_starts(&self, cells: u128, shift: usize) -> u128 {
+		// Doubles the length of the lines found each step, until the last step
+		// which only needs to make up the difference
+		let mut starts = cells;
+		let mut length = 1;
+		while length < self.rules.line_length {
+			let step = length.min(self.rules.line_length - length);
+			starts &= starts >> (step * shift);
+			length += step;
+		}
+
+		starts
+	}
+
+	/// Calculates a heuristic score for the current player and board position,
+	/// weighing each kind of window by `weights`
+	pub fn calculate_score(&self, color: PieceColor) -> i32 {
+		let weights = self.weights;
+		let mut score = 0;
+		let line_length = self.rules.line_length;
+
+		let own = self.bitboards[color.index()];
+		let opponent = self.bitboards[color.switch().index()];
+
+		// The next cell a piece would land in, for every column that isn't full
+		let bottom_mask = self.board_mask & !(self.board_mask << 1);
+		let playable = ((own | opponent) + bottom_mask) & self.board_mask;
+
+		let calculate_window_score = |window: u128| -> i32 {
+			// Windows with both colors in them, or with nothing in them, score nothing
+			if (own & window != 0) == (opponent & window != 0) {
 				return 0;
 			}
 
+			let own_count = (own & window).count_ones() as usize;
+			let opponent_count = (opponent & window).count_ones() as usize;
+			let empty_with_below_count = (playable & window).count_ones() as usize;
+			let empty_no_below_count =
+				line_length - own_count - opponent_count - empty_with_below_count;
+
 			match (
 				own_count,
 				opponent_count,
 				empty_with_below_count,
 				empty_no_below_count,
 			) {
-				(4, 0, 0, 0) => _LINE_OF_FOUR,
-				(3, 0, _, _) => _LINE_OF_THREE,
-				(2, 0, _, _) => _LINE_OF_TWO,
-				(0, 3, 1, 0) => _OPPONENT_LINE_OF_THREE_WITH_BELOW,
-				(0, 3, 0, 1) => _OPPONENT_LINE_OF_THREE,
-				(0, 2, _, _) => _OPPONENT_LINE_OF_TWO,
+				(own, 0, 0, 0) if own == line_length => weights.line_of_four,
+				(own, 0, _, _) if own == line_length - 1 => weights.line_of_three,
+				(own, 0, _, _) if own == line_length - 2 => weights.line_of_two,
+				(0, opponent, 1, 0) if opponent == line_length - 1 => {
+					weights.opponent_line_of_three_with_below
+				}
+				(0, opponent, 0, 1) if opponent == line_length - 1 => {
+					weights.opponent_line_of_three
+				}
+				(0, opponent, _, _) if opponent == line_length - 2 => weights.opponent_line_of_two,
 				_ => 0,
 			}
 		};
 
-		// Performs a check across all rows
-		for row in 0..NUM_ROWS {
-			for start_col in 0..NUM_COLS - 3 {
-				let mut window: Vec<(BoardCell, bool)> = vec![];
-				(start_col..start_col + 4).into_iter().for_each(|col| {
-					window.push((
-						self.board[row][col],
-						self.column_heights[col] >= NUM_ROWS - row - 1,
-					))
-				});
-				score += calculate_window_score(&window);
-			}
-		}
+		// Scores every row, column and diagonal window, an empty cell is "with
+		// below" when it is the next cell a piece would land in
+		for &shift in LINE_SHIFTS.iter() {
+			let line = (0..line_length).fold(0, |line, i| line | 1 << (i * shift));
 
-		// Performs a check across all columns
-		for col in 0..NUM_COLS {
-			for start_row in 0..NUM_ROWS - 3 {
-				let mut window: Vec<(BoardCell, bool)> = vec![];
-				(start_row..start_row + 4)
-					.into_iter()
-					.for_each(|row| window.push((self.board[row][col], true)));
-				score += calculate_window_score(&window);
+			let mut starts = self.line_starts(self.board_mask, shift);
+			while starts != 0 {
+				score += calculate_window_score(line << starts.trailing_zeros());
+				starts &= starts - 1;
 			}
 		}
 
-		// Perform a check across positively sloped diagonals
-		for row in NUM_ROWS - 3..NUM_ROWS {
-			for col in 0..NUM_COLS - 3 {
-				let mut window: Vec<(BoardCell, bool)> = vec![];
-				(0..4).into_iter().for_each(|i| {
-					window.push((
-						self.board[row - i][col + i],
-						self.column_heights[col + i] >= NUM_ROWS - (row - i) - 1,
-					))
-				});
-				score += calculate_window_score(&window);
-			}
-		}
+		// Gives `weights.center_col` points, 5 by default, for every piece in the center column
+		let center_col = ((1 << self.rules.num_rows) - 1) << (self.rules.num_cols / 2 * COL_BITS);
+		score += weights.center_col * (own & center_col).count_ones() as i32;
 
-		// Perform a check across positively sloped diagonals
-		for row in 0..NUM_ROWS - 3 {
-			for col in 0..NUM_COLS - 3 {
-				let mut window = vec![];
-				(0..4).into_iter().for_each(|i| {
-					window.push((
-						self.board[row + i][col + i],
-						self.column_heights[col + i] >= NUM_ROWS - (row + i) - 1,
-					))
-				});
-				score += calculate_window_score(&window);
-			}
+		// In misère a line loses, so everything that builds towards one counts
+		// against the color building it
+		match self.rules.misere {
+			true => -score,
+			false => score,
 		}
+	}
 
-		// Gives +2 points for every block in the center column
-		for row in 0..NUM_ROWS {
-			match self.board[row][3] {
-				None => {}
-				Some(c) => {
-					if c == color {
-						score += _CENTER_COL
-					}
-				}
-			}
+	/// Gets the columns ordered by distance from the center
+	pub fn get_columns(&self) -> &[usize] {
+		&self.column_order[..self.rules.num_cols]
+	}
+}
+
+impl Game for Connect4 {
+	type Move = Connect4Move;
+	type Player = PieceColor;
+
+	/// Gets the columns that aren't full, ordered by distance from the center,
+	/// then in Pop Out the columns with the active player's piece at the bottom
+	fn legal_moves(&self) -> Vec<Connect4Move> {
+		let columns = self.get_columns().iter().copied();
+		let drops = columns
+			.clone()
+			.filter(|&col| self.column_heights[col] < self.rules.num_rows)
+			.map(Drop);
+		let pops = columns
+			.filter(|_| self.rules.pop_out)
+			.filter(|&col| self.bitboards[self.active_player.index()] & 1 << (col * COL_BITS) != 0)
+			.map(Pop);
+
+		drops.chain(pops).collect()
+	}
+
+	fn apply(&mut self, mv: Connect4Move) -> bool {
+		self.play(mv).is_ok()
+	}
+
+	fn undo(&mut self) -> bool {
+		Connect4::undo(self)
+	}
+
+	fn redo(&mut self) -> bool {
+		Connect4::redo(self)
+	}
+
+	fn active_player(&self) -> PieceColor {
+		self.active_player
+	}
+
+	fn is_terminal(&self) -> bool {
+		self.is_terminal
+	}
+
+	fn winner(&self) -> Option<PieceColor> {
+		self.winner
+	}
+
+	fn evaluate(&self, color: PieceColor) -> i32 {
+		self.calculate_score(color)
+	}
+
+	fn max_moves(&self) -> usize {
+		self.rules.max_moves()
+	}
+
+	fn hash(&self) -> u64 {
+		self.hash
+	}
+
+	fn mirrored_hash(&self) -> u64 {
+		self.mirrored_hash
+	}
+
+	fn mirror_move(&self, mv: Connect4Move) -> Connect4Move {
+		match mv {
+			Drop(col) => Drop(self.rules.num_cols - 1 - col),
+			Pop(col) => Pop(self.rules.num_cols - 1 - col),
 		}
+	}
 
-		score
+	fn column(&self, mv: Connect4Move) -> usize {
+		match mv {
+			Drop(col) | Pop(col) => col,
+		}
 	}
 
-	/// Gets the columns ordered by distance from the center
-	pub fn get_columns(&self) -> [usize; NUM_COLS] {
-		return [3, 2, 4, 1, 5, 0, 6];
+	fn num_cols(&self) -> usize {
+		self.rules.num_cols
 	}
 }
 
@@ -269,12 +595,12 @@ impl Display for Connect4 {
 	/**
 	 * Defines the print functionality for the board
 	 */
-	fn fmt(&self, f: &mut Formatter) -> Result {
+	fn fmt(&self, f: &mut Formatter) -> fmt::Result {
 		// Initializes an empty string that will be built on
 		let mut print_string = String::new();
 
-		for row in 0..NUM_ROWS {
-			for col in 0..NUM_COLS {
+		for row in 0..self.rules.num_rows {
+			for col in 0..self.rules.num_cols {
 				// Places a -, R, Y depending on what is in the cell
 				match self.board[row][col] {
 					None => print_string.push('-'),
@@ -290,8 +616,318 @@ impl Display for Connect4 {
 			print_string.push('\n');
 		}
 
-		print_string.push_str("0 1 2 3 4 5 6");
+		let col_numbers: Vec<String> = (0..self.rules.num_cols)
+			.map(|col| col.to_string())
+			.collect();
+		print_string.push_str(&col_numbers.join(" "));
 
 		write!(f, "\nCurrent Board:\n{}\n", print_string)
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn pop_out() -> Connect4Rules {
+		Connect4Rules::STANDARD.with_pop_out(true)
+	}
+
+	/// Sets up a board from rows of `R`, `Y` and `.`, top row first
+	fn with_rows(rules: Connect4Rules, rows: &[&str], active_player: PieceColor) -> Connect4 {
+		let grid: Vec<Vec<Option<PieceColor>>> = rows
+			.iter()
+			.map(|row| {
+				row.chars()
+					.map(|c| match c {
+						'R' => Some(RED),
+						'Y' => Some(YELLOW),
+						_ => None,
+					})
+					.collect()
+			})
+			.collect();
+		Connect4::with_pieces(rules, &grid, active_player)
+	}
+
+	/// Everything about a board that undoing and redoing has to restore
+	type Snapshot = (
+		Board,
+		[u128; 2],
+		[usize; MAX_COLS],
+		PieceColor,
+		usize,
+		Option<PieceColor>,
+		bool,
+		u64,
+		u64,
+	);
+
+	fn snapshot(board: &Connect4) -> Snapshot {
+		(
+			board.board,
+			board.bitboards,
+			board.column_heights,
+			board.active_player,
+			board.moves_played,
+			board.winner,
+			board.is_terminal,
+			board.hash,
+			board.mirrored_hash,
+		)
+	}
+
+	#[test]
+	fn lines_win_in_every_direction_up_to_the_edges() {
+		// Each line as `[row, col]` cells, top row first, from its lowest bit
+		let wins: [([&str; 6], [[usize; 2]; 4]); 4] = [
+			(
+				[
+					".......", ".......", ".......", ".......", ".......", "...RRRR",
+				],
+				[[5, 3], [5, 4], [5, 5], [5, 6]],
+			),
+			(
+				[
+					"......R", "......R", "......R", "......R", ".......", ".......",
+				],
+				[[3, 6], [2, 6], [1, 6], [0, 6]],
+			),
+			(
+				[
+					"......R", ".....R.", "....R..", "...R...", ".......", ".......",
+				],
+				[[3, 3], [2, 4], [1, 5], [0, 6]],
+			),
+			(
+				[
+					"R......", ".R.....", "..R....", "...R...", ".......", ".......",
+				],
+				[[0, 0], [1, 1], [2, 2], [3, 3]],
+			),
+		];
+
+		for (rows, line) in wins.iter() {
+			let board = with_rows(Connect4Rules::STANDARD, rows, YELLOW);
+			assert_eq!(board.check_for_win(RED), Some(line.to_vec()));
+			assert_eq!(board.check_for_win(YELLOW), None);
+			assert_eq!(board.winner, Some(RED));
+			assert!(board.is_terminal);
+		}
+
+		// The top of one column and the bottom of the next aren't a line
+		let rows = [
+			"R......", "R......", "R......", ".......", ".......", ".R.....",
+		];
+		let board = with_rows(Connect4Rules::STANDARD, &rows, YELLOW);
+		assert!(!board.has_won(RED));
+		assert_eq!(board.winner, None);
+	}
+
+	#[test]
+	fn five_in_a_row_wins_on_a_9x7_board() {
+		let rules = Connect4Rules::new(9, 7, 5).unwrap();
+		let mut board = Connect4::with_rules(rules);
+		for &col in &[4, 4, 5, 5, 6, 6, 7, 7] {
+			board.drop(col).unwrap();
+		}
+		assert!(!board.is_terminal);
+
+		board.drop(8).unwrap();
+		assert_eq!(board.winner, Some(RED));
+		assert_eq!(
+			board.check_for_win(RED),
+			Some(vec![[6, 4], [6, 5], [6, 6], [6, 7], [6, 8]])
+		);
+
+		// Four in a row up to the top of the last column isn't enough
+		let rows = [
+			"........R",
+			"........R",
+			"........R",
+			"........R",
+			".........",
+			".........",
+			".........",
+		];
+		assert!(!with_rows(rules, &rows, YELLOW).has_won(RED));
+	}
+
+	#[test]
+	fn the_largest_board_fits_in_a_bitboard() {
+		let rules = Connect4Rules::new(MAX_COLS, MAX_ROWS, 4).unwrap();
+		let mut rows = vec![".........."; MAX_ROWS];
+		rows[..4].copy_from_slice(&[".........R"; 4]);
+
+		// The top right cell is the highest bit any board uses
+		let board = with_rows(rules, &rows, YELLOW);
+		assert_eq!(board.check_for_win(RED).unwrap()[3], [0, MAX_COLS - 1]);
+		assert_eq!(board.board_mask.count_ones() as usize, rules.num_cells());
+	}
+
+	#[test]
+	fn boards_past_the_largest_are_turned_down() {
+		assert!(Connect4Rules::new(MAX_COLS + 1, MAX_ROWS, 4).is_err());
+		assert!(Connect4Rules::new(MAX_COLS, MAX_ROWS + 1, 4).is_err());
+		assert!(Connect4Rules::new(3, 6, 3).is_err());
+
+		// Lines have to fit on the board too
+		assert!(Connect4Rules::new(9, 7, 9).is_ok());
+		assert!(Connect4Rules::new(9, 7, 10).is_err());
+		assert!(Connect4Rules::new(7, 6, 2).is_err());
+	}
+
+	#[test]
+	fn finishing_a_line_loses_in_misere() {
+		let mut board = Connect4::with_rules(Connect4Rules::MISERE);
+		for &col in &[0, 1, 0, 1, 0, 1, 0] {
+			board.drop(col).unwrap();
+		}
+		assert!(board.has_won(RED));
+		assert_eq!(board.winner, Some(YELLOW));
+		assert!(board.is_terminal);
+	}
+
+	#[test]
+	fn pop_moves_the_column_down() {
+		let mut board = Connect4::with_rules(pop_out());
+		board.drop(0).unwrap();
+		board.drop(0).unwrap();
+
+		assert_eq!(board.pop(1), Err(MoveError::CannotPop(1)));
+		board.pop(0).unwrap();
+
+		let rows = [
+			".......", ".......", ".......", ".......", ".......", "Y......",
+		];
+		let popped = with_rows(pop_out(), &rows, YELLOW);
+		assert_eq!(board.board, popped.board);
+		assert_eq!(board.bitboards, popped.bitboards);
+		assert_eq!(board.hash, popped.hash);
+		assert_eq!(board.column_heights[0], 1);
+		assert_eq!(Connect4::new().pop(0), Err(MoveError::PopOutNotAllowed));
+	}
+
+	#[test]
+	fn pop_finishing_both_lines_wins_for_the_popper() {
+		// Popping column 0 finishes Yellow's bottom row and Red's row above it
+		let rows = [
+			".......", ".......", ".......", "R......", "YRRR...", "RYYY...",
+		];
+		let mut board = with_rows(pop_out(), &rows, RED);
+		board.pop(0).unwrap();
+		assert!(board.has_won(RED) && board.has_won(YELLOW));
+		assert_eq!(board.winner, Some(RED));
+
+		// In misère Red's line loses it the game
+		let mut board = with_rows(pop_out().with_misere(true), &rows, RED);
+		board.pop(0).unwrap();
+		assert_eq!(board.winner, Some(YELLOW));
+	}
+
+	#[test]
+	fn undo_and_redo_retrace_drops_and_pops() {
+		let mut board = Connect4::with_rules(pop_out());
+		let moves = [Drop(0), Drop(1), Drop(0), Pop(1), Drop(0), Drop(1), Drop(0)];
+		let mut states = vec![snapshot(&board)];
+		for &mv in &moves {
+			board.play(mv).unwrap();
+			states.push(snapshot(&board));
+		}
+		assert_eq!(board.winner, Some(RED));
+
+		for state in states.iter().rev().skip(1) {
+			assert!(board.undo());
+			assert_eq!(&snapshot(&board), state);
+		}
+		assert!(!board.undo());
+
+		for state in states.iter().skip(1) {
+			assert!(board.redo());
+			assert_eq!(&snapshot(&board), state);
+		}
+		assert!(!board.redo());
+
+		// A new move replaces the moves that were undone
+		board.undo();
+		board.undo();
+		board.drop(6).unwrap();
+		assert!(!board.can_redo());
+		assert!(!board.redo());
+		assert_eq!(board.history_len, board.moves_played);
+	}
+
+	#[test]
+	fn hashes_match_boards_set_up_from_scratch() {
+		// With an even number of columns no column mirrors onto itself
+		let even = Connect4Rules::new(8, 6, 4).unwrap().with_pop_out(true);
+		for &rules in &[pop_out(), even] {
+			let mut board = Connect4::with_rules(rules);
+			let check = |board: &Connect4| {
+				let grid: Vec<Vec<Option<PieceColor>>> = board.board[..rules.num_rows]
+					.iter()
+					.map(|row| row[..rules.num_cols].to_vec())
+					.collect();
+				let mirrored: Vec<Vec<Option<PieceColor>>> = grid
+					.iter()
+					.map(|row| row.iter().rev().copied().collect())
+					.collect();
+
+				let from_scratch = Connect4::with_pieces(rules, &grid, board.active_player);
+				let reflection = Connect4::with_pieces(rules, &mirrored, board.active_player);
+				assert_eq!(
+					board.hash, from_scratch.hash,
+					"after {} moves",
+					board.moves_played
+				);
+				assert_eq!(
+					board.mirrored_hash, reflection.hash,
+					"after {} moves",
+					board.moves_played
+				);
+				assert_eq!(reflection.mirrored_hash, board.hash);
+			};
+
+			let moves = [Drop(0), Drop(1), Drop(3), Drop(0), Pop(0), Drop(2), Pop(3)];
+			for &mv in &moves {
+				board.play(mv).unwrap();
+				check(&board);
+			}
+			for _ in 0..3 {
+				board.undo();
+				check(&board);
+			}
+			board.pop(0).unwrap();
+			check(&board);
+			board.drop(rules.num_cols - 1).unwrap();
+			check(&board);
+		}
+	}
+
+	#[test]
+	fn third_repetition_is_a_draw() {
+		let mut board = Connect4::with_rules(pop_out());
+		for round in 0..2 {
+			board.drop(0).unwrap();
+			board.drop(1).unwrap();
+			board.pop(0).unwrap();
+			assert!(!board.is_terminal, "drawn after {} rounds", round);
+			board.pop(1).unwrap();
+		}
+
+		assert!(board.is_terminal);
+		assert_eq!(board.winner, None);
+	}
+
+	#[test]
+	fn max_moves_is_a_draw() {
+		let mut board = Connect4::with_rules(pop_out());
+		board.moves_played = board.rules.max_moves() - 2;
+		board.drop(3).unwrap();
+		assert!(!board.is_terminal);
+
+		board.drop(3).unwrap();
+		assert!(board.is_terminal);
+		assert_eq!(board.winner, None);
+	}
+}
@@ -0,0 +1,21 @@
+use std::fmt::{Display, Formatter, Result};
+
+/// A single Connect 4 move, popping pieces out is only allowed in Pop Out
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Connect4Move {
+	/// Drops a piece into the top of a column
+	Drop(usize),
+	/// Takes the player's own piece out of the bottom of a column, moving the
+	/// pieces above it down
+	Pop(usize),
+}
+
+impl Display for Connect4Move {
+	/// Prints out the move, like `drop in column 3`
+	fn fmt(&self, f: &mut Formatter) -> Result {
+		match self {
+			Connect4Move::Drop(col) => write!(f, "drop in column {}", col),
+			Connect4Move::Pop(col) => write!(f, "pop out of column {}", col),
+		}
+	}
+}
@@ -1,61 +1,145 @@
-use super::{connect4::Connect4, piece_color::PieceColor::*};
-use rand::seq::SliceRandom;
+use super::{
+	connect4::Connect4, connect4_move::Connect4Move, piece_color::PieceColor, rules::Connect4Rules,
+	solver::Solver,
+};
+use crate::{
+	engine::{
+		config::EngineConfig,
+		mcts::{self, MctsLimits, MoveStats},
+		minimax::{self, SearchLimits, SearchReport},
+	},
+	types::opponent::Opponent,
+};
+use std::cell::RefCell;
 
-pub fn make_move(board: Connect4, depth: usize) -> usize {
-	let (col, _) = minmax(board, depth, true);
-	col
+/// How many positions the perfect CPU solves before giving up on a move, a
+/// little more than the slowest positions past the opening book take
+pub const SOLVE_NODES: u64 = 500_000_000;
+
+thread_local! {
+	/// The perfect CPU's solver, kept between moves so it remembers the
+	/// positions it has already solved
+	static SOLVER: RefCell<Solver> = RefCell::new(Solver::new());
 }
 
-fn minmax(board: Connect4, depth: usize, is_cpu_turn: bool) -> (usize, i32) {
-	if board.is_terminal {
-		return match board.winner {
-			None => (3, 0), // Draw
-			Some(color) => match color {
-				YELLOW => (3, (i32::MAX - 43 + depth as i32)), // Computer won, good
-				RED => (3, i32::MIN + 43 - depth as i32),      // Human won, bad
-			},
-		};
-	} else if depth == 0 {
-		return (3, board.calculate_score(YELLOW));
+/// Whether `engine` can play a game with `rules`, the perfect CPU only plays
+/// the boards the solver can solve
+pub fn can_play(engine: Opponent, rules: Connect4Rules) -> bool {
+	engine != Opponent::PerfectCPU || rules == Connect4Rules::STANDARD
+}
+
+/// Picks the move `engine` makes playing `side`, choosing between equally good
+/// moves as `config` says, `None` when `engine` is a human or can't play the
+/// board, or when it isn't `side`'s turn or the game is over
+pub fn pick_move(
+	board: Connect4,
+	side: PieceColor,
+	engine: Opponent,
+	config: EngineConfig,
+) -> Option<Connect4Move> {
+	match (engine, engine.search_limits(), engine.mcts_limits()) {
+		(Opponent::Human, _, _) => None,
+		(_, Some(limits), _) => make_move(board, side, limits, config),
+		(_, _, Some(limits)) => make_mcts_move(board, side, limits, config),
+		(_, None, None) => make_perfect_move(board, side, config),
 	}
+}
 
-	if is_cpu_turn {
-		let mut best_options = vec![(0, i32::MIN)];
+/// Picks the column the CPU playing `side` should drop its piece into, or pop
+/// its piece out of, `None` when it isn't `side`'s turn or the game is over
+pub fn make_move(
+	board: Connect4,
+	side: PieceColor,
+	limits: SearchLimits,
+	config: EngineConfig,
+) -> Option<Connect4Move> {
+	minimax_search(board, side, limits, config).map(|report| report.best_move)
+}
 
-		for col in board.get_columns().iter() {
-			let mut copy_board = board.clone();
+/// Searches the position for `side`, returning the column the CPU picks along
+/// with its score, the line it expects and the score of every other move, or
+/// `None` when it isn't `side`'s turn or the game is over
+pub fn minimax_search(
+	board: Connect4,
+	side: PieceColor,
+	limits: SearchLimits,
+	config: EngineConfig,
+) -> Option<SearchReport<Connect4Move>> {
+	if board.is_terminal || board.active_player != side {
+		return None;
+	}
 
-			if copy_board.drop(*col) == false {
-				continue;
-			}
-			let new_value = minmax(copy_board, depth - 1, false).1;
+	Some(minimax::search(&board, limits, config))
+}
 
-			if new_value == best_options[0].1 {
-				best_options.push((*col, new_value));
-			} else if new_value > best_options[0].1 {
-				best_options = vec![(*col, new_value)];
-			}
-		}
+/// Scores every column for the player to move as deep as the hard CPU
+/// searches, the hints shown to the user, or `None` when the game is over
+#[allow(dead_code)] // Only the web boards show hints
+pub fn hints(board: Connect4) -> Option<SearchReport<Connect4Move>> {
+	if board.is_terminal {
+		return None;
+	}
 
-		*best_options.choose(&mut rand::thread_rng()).unwrap()
-	} else {
-		let mut best_options = vec![(0, i32::MAX)];
+	let limits = Opponent::HardCPU
+		.search_limits()
+		.expect("The hard CPU searches a limited depth");
+	Some(minimax::analyse(&board, limits))
+}
 
-		for col in board.get_columns().iter() {
-			let mut copy_board = board.clone();
-			if copy_board.drop(*col) == false {
-				continue;
-			}
+/// Picks the column the Monte Carlo search playing `side` played out the most,
+/// `None` when it isn't `side`'s turn or the game is over
+pub fn make_mcts_move(
+	board: Connect4,
+	side: PieceColor,
+	limits: MctsLimits,
+	config: EngineConfig,
+) -> Option<Connect4Move> {
+	mcts_search(board, side, limits, config).map(|moves| moves[0].mv)
+}
 
-			let new_value = minmax(copy_board, depth - 1, true).1;
+/// Plays out games from the position for `side`, returning how often each move
+/// was played out and how well it did, the most played out first, or `None`
+/// when it isn't `side`'s turn or the game is over
+pub fn mcts_search(
+	board: Connect4,
+	side: PieceColor,
+	limits: MctsLimits,
+	config: EngineConfig,
+) -> Option<Vec<MoveStats<Connect4Move>>> {
+	if board.is_terminal || board.active_player != side {
+		return None;
+	}
 
-			if new_value == best_options[0].1 {
-				best_options.push((*col, new_value));
-			} else if new_value < best_options[0].1 {
-				best_options = vec![(*col, new_value)];
-			}
-		}
+	Some(mcts::search(&board, limits, config))
+}
+
+/// Picks a column with perfect play for `side`, winning as fast as possible when
+/// it can, `None` when it isn't `side`'s turn, the game is over or the board
+/// isn't one the solver can solve, see `can_play`
+///
+/// The first moves come from the solver's opening book, and the positions after
+/// those are solved, which takes up to a minute. Positions off the book early
+/// in the game, like a shared one or a game the CPU takes over, can take far
+/// longer, so should the solver take more than `SOLVE_NODES` positions the CPU
+/// plays the hard CPU's move rather than keep the user waiting
+pub fn make_perfect_move(
+	board: Connect4,
+	side: PieceColor,
+	config: EngineConfig,
+) -> Option<Connect4Move> {
+	if board.is_terminal
+		|| board.active_player != side
+		|| !can_play(Opponent::PerfectCPU, board.rules)
+	{
+		return None;
+	}
 
-		*best_options.choose(&mut rand::thread_rng()).unwrap()
+	let solved = SOLVER.with(|solver| solver.borrow_mut().try_best_moves(&board, SOLVE_NODES));
+	match solved {
+		Some(cols) => {
+			let moves: Vec<Connect4Move> = cols.into_iter().map(Connect4Move::Drop).collect();
+			Some(config.break_tie(&board, &moves, &mut config.rng(board.hash)))
+		}
+		None => pick_move(board, side, Opponent::HardCPU, config),
 	}
 }
@@ -15,14 +15,22 @@ impl PieceColor {
 			PieceColor::YELLOW => PieceColor::RED,
 		}
 	}
+
+	/// Gets the position of the color in per-color arrays
+	pub fn index(&self) -> usize {
+		match self {
+			PieceColor::RED => 0,
+			PieceColor::YELLOW => 1,
+		}
+	}
 }
 
 impl Display for PieceColor {
 	/// Prints out the piece color
 	fn fmt(&self, f: &mut Formatter) -> Result {
 		match self {
-			PieceColor::RED => write!(f, "{}", "Red"),
-			PieceColor::YELLOW => write!(f, "{}", "Yellow"),
+			PieceColor::RED => f.write_str("Red"),
+			PieceColor::YELLOW => f.write_str("Yellow"),
 		}
 	}
 }
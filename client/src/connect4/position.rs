@@ -0,0 +1,275 @@
+use super::{
+	connect4::{Connect4, COL_BITS},
+	piece_color::PieceColor::*,
+	rules::{Connect4Rules, MAX_COLS},
+};
+use crate::engine::position::{format_grid, grid_size, parse_grid};
+use std::collections::HashSet;
+
+/// Connect 4 positions written as a single line of text
+///
+/// A position is the board, top row first, followed by the color to move:
+///
+/// ```text
+/// 7/7/7/7/3Y3/2RR3 y
+/// ```
+///
+/// Pieces are `R` and `Y`, and the color to move is `r` or `y`. The size of
+/// the board comes from the grid, and when a line of other than four wins its
+/// length follows the color to move, like `9/9/9/9/9/9/9 r 5`. Pop Out
+/// positions end in `popout`, like `7/7/7/7/7/3R3 y popout`, and misère
+/// positions in `misere`
+impl Connect4 {
+	/// Sets up the board from a position string, checking that the position can
+	/// be reached in a real game
+	///
+	/// The pieces are played in an order that leads to the position, so the
+	/// position's moves can be undone like any other
+	pub fn from_position(position: &str) -> Result<Self, String> {
+		let fields: Vec<&str> = position.split_whitespace().collect();
+		if fields.len() < 2 || fields.len() > 5 {
+			return Err(String::from(
+				"A position is the board and the color to move, like \"7/7/7/7/7/3R3 y\"",
+			));
+		}
+
+		let (num_rows, num_cols) = grid_size(fields[0]);
+		let mut line_length = Connect4Rules::STANDARD.line_length;
+		let mut pop_out = false;
+		let mut misere = false;
+		for &field in fields[2..].iter() {
+			match field {
+				"popout" => pop_out = true,
+				"misere" => misere = true,
+				line => {
+					line_length = line
+						.parse()
+						.map_err(|_| format!("'{}' isn't a line length, popout or misere", line))?
+				}
+			}
+		}
+		let rules = Connect4Rules::new(num_cols, num_rows, line_length)?
+			.with_pop_out(pop_out)
+			.with_misere(misere);
+
+		let grid = parse_grid(fields[0], num_rows, num_cols, |c| match c {
+			'R' => Some(RED),
+			'Y' => Some(YELLOW),
+			_ => None,
+		})?;
+
+		let active_player = match fields[1] {
+			"r" => RED,
+			"y" => YELLOW,
+			side => return Err(format!("'{}' isn't a color to move, use r or y", side)),
+		};
+
+		// Pieces come and go in Pop Out, so either color can have any number of
+		// pieces and there's no order of moves to work out
+		if rules.pop_out {
+			return Ok(Connect4::with_pieces(rules, &grid, active_player));
+		}
+
+		// Places the pieces without playing them to work out an order they could
+		// have been played in
+		let mut setup = Connect4::with_rules(rules);
+		for (row, cells) in grid.iter().enumerate() {
+			for (col, &cell) in cells.iter().enumerate() {
+				if let Some(color) = cell {
+					let col_height = num_rows - 1 - row;
+					setup.board[row][col] = Some(color);
+					setup.bitboards[color.index()] |= 1 << (col * COL_BITS + col_height);
+					setup.column_heights[col] += 1;
+					setup.moves_played += 1;
+				}
+			}
+		}
+
+		// Red moves first, so Red always has as many pieces as Yellow or one more
+		let reds = setup.bitboards[RED.index()].count_ones();
+		let yellows = setup.bitboards[YELLOW.index()].count_ones();
+		let to_move = match reds.checked_sub(yellows) {
+			Some(0) => RED,
+			Some(1) => YELLOW,
+			_ => {
+				return Err(format!(
+					"Red has {} pieces and Yellow has {}, Red must have as many as Yellow or one more",
+					reds, yellows
+				))
+			}
+		};
+		if to_move != active_player {
+			return Err(format!("It has to be {}'s turn", to_move));
+		}
+
+		let mut moves = Vec::with_capacity(setup.moves_played);
+		if !setup.find_move_order(&mut moves, &mut HashSet::new()) {
+			return Err(String::from("The position can't be reached in a game"));
+		}
+
+		let mut board = Connect4::with_rules(rules);
+		for &col in moves.iter() {
+			board.drop(col).map_err(|err| err.to_string())?;
+		}
+		board.set_up_moves = board.moves_played;
+
+		Ok(board)
+	}
+
+	/// Writes the board and the color to move as a position string
+	pub fn position(&self) -> String {
+		let rows = self.board[..self.rules.num_rows]
+			.iter()
+			.map(|row| &row[..self.rules.num_cols]);
+		let grid = format_grid(rows, |color| match color {
+			RED => 'R',
+			YELLOW => 'Y',
+		});
+
+		let active_player = match self.active_player {
+			RED => 'r',
+			YELLOW => 'y',
+		};
+
+		// The line length is left out when it is the usual four
+		let mut position = format!("{} {}", grid, active_player);
+		if self.rules.line_length != Connect4Rules::STANDARD.line_length {
+			position.push_str(&format!(" {}", self.rules.line_length));
+		}
+		if self.rules.pop_out {
+			position.push_str(" popout");
+		}
+		if self.rules.misere {
+			position.push_str(" misere");
+		}
+
+		position
+	}
+
+	/// Takes pieces back off the top of the columns, last move first, until the
+	/// board is empty, pushing the columns in the order they were played
+	///
+	/// Nobody can have won before the last move, so every piece taken back has
+	/// to leave a board without four in a row. Positions that turn out to be
+	/// dead ends are kept in `dead_ends` by their column heights
+	fn find_move_order(
+		&mut self,
+		moves: &mut Vec<usize>,
+		dead_ends: &mut HashSet<[usize; MAX_COLS]>,
+	) -> bool {
+		if self.moves_played == 0 {
+			return true;
+		} else if dead_ends.contains(&self.column_heights) {
+			return false;
+		}
+
+		// The player who made the last move
+		let color = match self.moves_played % 2 {
+			1 => RED,
+			_ => YELLOW,
+		};
+
+		for col in 0..self.rules.num_cols {
+			let col_height = self.column_heights[col];
+			let row = self.rules.num_rows - col_height;
+			if col_height == 0 || self.board[row][col] != Some(color) {
+				continue;
+			}
+
+			let bit = 1 << (col * COL_BITS + col_height - 1);
+			self.board[row][col] = None;
+			self.bitboards[color.index()] ^= bit;
+			self.column_heights[col] -= 1;
+			self.moves_played -= 1;
+
+			if !self.has_won(RED) && !self.has_won(YELLOW) && self.find_move_order(moves, dead_ends)
+			{
+				moves.push(col);
+				return true;
+			}
+
+			self.board[row][col] = Some(color);
+			self.bitboards[color.index()] ^= bit;
+			self.column_heights[col] += 1;
+			self.moves_played += 1;
+		}
+
+		dead_ends.insert(self.column_heights);
+		false
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	/// Gets the reason a position is turned down
+	fn error(position: &str) -> String {
+		match Connect4::from_position(position) {
+			Ok(_) => panic!("{} was accepted", position),
+			Err(err) => err,
+		}
+	}
+
+	#[test]
+	fn positions_round_trip() {
+		for &position in [
+			"7/7/7/7/7/7 r",
+			"7/7/7/7/3Y3/2RR3 y",
+			"9/9/9/9/9/9/9 r 5",
+			"7/7/7/7/R6/YR5 y popout",
+			"7/7/7/7/7/3R3 y misere",
+		]
+		.iter()
+		{
+			let board = Connect4::from_position(position).unwrap();
+
+			assert_eq!(board.position(), position);
+			assert_eq!(board.set_up_moves, board.moves_played);
+		}
+	}
+
+	#[test]
+	fn floating_pieces_are_turned_down() {
+		let err = error("7/7/7/7/R6/7 y");
+
+		assert!(err.contains("floating"), "{}", err);
+	}
+
+	#[test]
+	fn wrong_piece_counts_are_turned_down() {
+		for &position in ["7/7/7/7/7/RR5 y", "7/7/7/7/7/Y6 r"].iter() {
+			let err = error(position);
+
+			assert!(err.contains("Red must have as many as Yellow"), "{}", err);
+		}
+	}
+
+	#[test]
+	fn wrong_side_to_move_is_turned_down() {
+		let err = error("7/7/7/7/7/3R3 r");
+
+		assert_eq!(err, "It has to be Yellow's turn");
+	}
+
+	#[test]
+	fn unreachable_positions_are_turned_down() {
+		// Yellow's last piece has to have been played after Red already won
+		let err = error("7/7/7/7/YYYY3/RRRR3 r");
+
+		assert_eq!(err, "The position can't be reached in a game");
+	}
+
+	#[test]
+	fn oversized_runs_are_turned_down() {
+		for &position in [
+			"7/7/7/7/7/99999999999999999999999R r",
+			"7/7/7/7/7/99999999999999999999999 r",
+			"7/7/7/7/7/8 r",
+		]
+		.iter()
+		{
+			assert!(Connect4::from_position(position).is_err());
+		}
+	}
+}
@@ -0,0 +1,77 @@
+use super::{
+	connect4::Connect4,
+	connect4_move::Connect4Move,
+	rules::{Connect4Rules, MAX_COLS},
+};
+use crate::engine::record::{self, Notation, Record};
+
+/// Connect 4 moves are written as the column played, `0` to `6` on the
+/// standard board, with a `P` in front for a piece popped out, like `P3`. The
+/// variant is the board size, like `9x7 connect 5` or `7x6 connect 4 pop out`
+impl Notation for Connect4 {
+	const NAME: &'static str = "Connect 4";
+	const SIDES: [&'static str; 2] = ["Red", "Yellow"];
+
+	fn new_game() -> Self {
+		Connect4::new()
+	}
+
+	fn variant(&self) -> Option<String> {
+		match self.rules {
+			Connect4Rules::STANDARD => None,
+			rules => Some(rules.to_string()),
+		}
+	}
+
+	fn new_variant(variant: &str) -> Result<Self, String> {
+		Connect4Rules::parse(variant).map(Connect4::with_rules)
+	}
+
+	fn start_position(&self) -> Option<String> {
+		let mut start = *self;
+		while start.moves_played > start.set_up_moves && start.undo() {}
+
+		match start.position() == Connect4::with_rules(self.rules).position() {
+			true => None,
+			false => Some(start.position()),
+		}
+	}
+
+	fn from_start_position(position: &str) -> Result<Self, String> {
+		Connect4::from_position(position)
+	}
+
+	fn moves(&self) -> Vec<Connect4Move> {
+		self.history[self.set_up_moves.min(self.moves_played)..self.moves_played].to_vec()
+	}
+
+	fn format_move(mv: Connect4Move) -> String {
+		match mv {
+			Connect4Move::Drop(col) => col.to_string(),
+			Connect4Move::Pop(col) => format!("P{}", col),
+		}
+	}
+
+	fn parse_move(text: &str) -> Option<Connect4Move> {
+		let (col, is_pop) = match text.strip_prefix(|c| c == 'P' || c == 'p') {
+			Some(col) => (col, true),
+			None => (text, false),
+		};
+
+		match (col.parse(), is_pop) {
+			(Ok(col), false) if col < MAX_COLS => Some(Connect4Move::Drop(col)),
+			(Ok(col), true) if col < MAX_COLS => Some(Connect4Move::Pop(col)),
+			_ => None,
+		}
+	}
+}
+
+/// Reads a Connect 4 game record, see `Record` for the format
+pub fn parse(text: &str) -> Result<(Record<Connect4Move>, Connect4), String> {
+	record::parse(text)
+}
+
+/// Writes a Connect 4 game record
+pub fn format(record: &Record<Connect4Move>) -> String {
+	record::format::<Connect4>(record)
+}
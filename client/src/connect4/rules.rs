@@ -0,0 +1,181 @@
+use std::fmt::{Display, Formatter, Result};
+
+/// The most columns a board can have
+pub const MAX_COLS: usize = 10;
+
+/// The most rows a board can have
+pub const MAX_ROWS: usize = 10;
+
+/// The most moves a game on the largest board can last, see `max_moves`
+pub const MAX_MOVES: usize = 4 * MAX_COLS * MAX_ROWS;
+
+/// The size of a Connect 4 board, how many pieces in a row win the game, and
+/// the variants being played
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Connect4Rules {
+	pub num_cols: usize,
+	pub num_rows: usize,
+	/// How many pieces in a row win the game
+	pub line_length: usize,
+	/// Whether a player can take one of their own pieces out of the bottom of a
+	/// column instead of dropping one, the Pop Out variant
+	pub pop_out: bool,
+	/// Whether finishing a line loses the game instead of winning it
+	pub misere: bool,
+}
+
+impl Connect4Rules {
+	/// The 7x6 board where four in a row wins
+	pub const STANDARD: Self = Connect4Rules {
+		num_cols: 7,
+		num_rows: 6,
+		line_length: 4,
+		pop_out: false,
+		misere: false,
+	};
+
+	/// The standard board where four in a row loses
+	#[allow(dead_code)] // Only offered as a preset by the CLI
+	pub const MISERE: Self = Connect4Rules {
+		misere: true,
+		..Connect4Rules::STANDARD
+	};
+
+	/// A 9x6 board where five in a row wins
+	pub const FIVE_IN_A_ROW: Self = Connect4Rules {
+		num_cols: 9,
+		num_rows: 6,
+		line_length: 5,
+		..Connect4Rules::STANDARD
+	};
+
+	/// The standard board where pieces can be popped out
+	#[allow(dead_code)] // Only offered as a preset by the CLI
+	pub const POP_OUT: Self = Connect4Rules {
+		pop_out: true,
+		..Connect4Rules::STANDARD
+	};
+
+	/// Checks that a board of `num_cols` by `num_rows` can be played on, and
+	/// that a line of `line_length` fits on it. Pieces can't be popped out and
+	/// lines win, see `with_pop_out` and `with_misere`
+	pub fn new(
+		num_cols: usize,
+		num_rows: usize,
+		line_length: usize,
+	) -> std::result::Result<Self, String> {
+		if !(4..=MAX_COLS).contains(&num_cols) {
+			return Err(format!("A board has from 4 to {} columns", MAX_COLS));
+		} else if !(4..=MAX_ROWS).contains(&num_rows) {
+			return Err(format!("A board has from 4 to {} rows", MAX_ROWS));
+		} else if line_length < 3 || line_length > num_cols.max(num_rows) {
+			return Err(format!(
+				"A line has to be at least 3 pieces long and fit on the board, so at most {}",
+				num_cols.max(num_rows)
+			));
+		}
+
+		Ok(Connect4Rules {
+			num_cols,
+			num_rows,
+			line_length,
+			pop_out: false,
+			misere: false,
+		})
+	}
+
+	/// The same rules, with or without Pop Out
+	pub fn with_pop_out(self, pop_out: bool) -> Self {
+		Connect4Rules { pop_out, ..self }
+	}
+
+	/// The same rules, with lines losing or winning
+	pub fn with_misere(self, misere: bool) -> Self {
+		Connect4Rules { misere, ..self }
+	}
+
+	/// Reads rules written by `Display`, like `9x7 connect 5` or
+	/// `7x6 connect 4 pop out misere`
+	#[allow(dead_code)] // Rules are only read from text by the CLI
+	pub fn parse(text: &str) -> std::result::Result<Self, String> {
+		let invalid = || {
+			format!(
+				"'{}' isn't a board size, use something like 9x7 connect 5",
+				text
+			)
+		};
+
+		let words: Vec<&str> = text.split_whitespace().collect();
+		let (size, line_length, mut variants) = match words.as_slice() {
+			[size, "connect", line_length, variants @ ..] => (size, line_length, variants),
+			_ => return Err(invalid()),
+		};
+
+		let mut pop_out = false;
+		let mut misere = false;
+		loop {
+			variants = match variants {
+				[] => break,
+				["pop", "out", rest @ ..] if !pop_out => {
+					pop_out = true;
+					rest
+				}
+				["misere", rest @ ..] if !misere => {
+					misere = true;
+					rest
+				}
+				_ => return Err(invalid()),
+			};
+		}
+		let (num_cols, num_rows) = size.split_once('x').ok_or_else(invalid)?;
+
+		match (num_cols.parse(), num_rows.parse(), line_length.parse()) {
+			(Ok(num_cols), Ok(num_rows), Ok(line_length)) => {
+				Connect4Rules::new(num_cols, num_rows, line_length)
+					.map(|rules| rules.with_pop_out(pop_out).with_misere(misere))
+			}
+			_ => Err(invalid()),
+		}
+	}
+
+	/// The number of cells on the board, and so the longest a game can last
+	pub fn num_cells(&self) -> usize {
+		self.num_cols * self.num_rows
+	}
+
+	/// The most moves a game can last, a Pop Out game that goes on for four
+	/// moves per cell is called a draw
+	pub fn max_moves(&self) -> usize {
+		match self.pop_out {
+			false => self.num_cells(),
+			true => 4 * self.num_cells(),
+		}
+	}
+}
+
+impl Default for Connect4Rules {
+	fn default() -> Self {
+		Connect4Rules::STANDARD
+	}
+}
+
+impl Display for Connect4Rules {
+	/// Prints out the board size and line length, like `7x6 connect 4`, then
+	/// `pop out` when pieces can be popped out and `misere` when lines lose
+	fn fmt(&self, f: &mut Formatter) -> Result {
+		write!(
+			f,
+			"{}x{} connect {}",
+			self.num_cols, self.num_rows, self.line_length
+		)?;
+
+		if self.pop_out {
+			write!(f, " pop out")?;
+		}
+
+		match self.misere {
+			true => write!(f, " misere"),
+			false => Ok(()),
+		}
+	}
+}
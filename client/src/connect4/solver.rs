@@ -0,0 +1,701 @@
+use super::{
+	connect4::{self, Connect4},
+	rules::Connect4Rules,
+};
+use crate::engine::game::Outcome;
+use rand::seq::SliceRandom;
+
+/// The solver only plays the standard board, where everything fits in a `u64`
+const NUM_COLS: usize = Connect4Rules::STANDARD.num_cols;
+const NUM_ROWS: usize = Connect4Rules::STANDARD.num_rows;
+
+/// The number of bits each column takes up in the solver's bitboards
+const COL_BITS: usize = NUM_ROWS + 1;
+
+/// The number of cells on the board
+const NUM_CELLS: usize = NUM_COLS * NUM_ROWS;
+
+/// No position scores lower than this, losing to the opponent's first move
+const MIN_SCORE: i32 = -(NUM_CELLS as i32) / 2;
+
+/// How many positions the solver remembers, a prime so positions spread evenly
+const TABLE_SIZE: usize = 4_194_301;
+
+/// A bitboard with the bottom cell of every column set
+const BOTTOM_MASK: u64 = {
+	let mut mask = 0;
+	let mut col = 0;
+	while col < NUM_COLS {
+		mask |= 1 << (col * COL_BITS);
+		col += 1;
+	}
+	mask
+};
+
+/// A bitboard with every cell of the board set, leaving out the spare bit on top of each column
+const BOARD_MASK: u64 = BOTTOM_MASK * ((1 << NUM_ROWS) - 1);
+
+/// The order columns are tried in, center columns take part in the most lines
+const COLUMN_ORDER: [usize; NUM_COLS] = [3, 2, 4, 1, 5, 0, 6];
+
+/// The best moves of positions early in the game, built by `Solver::write_book`
+const OPENING_BOOK: &[u8] = include_bytes!("opening_book.bin");
+
+/// The bytes every opening book starts with
+const BOOK_MAGIC: &[u8; 4] = b"C4OB";
+
+/// The size of the header, the magic bytes then the depth and the number of entries
+const BOOK_HEADER_SIZE: usize = 9;
+
+/// The size of a single entry, a position's key and its best columns packed together
+const BOOK_ENTRY_SIZE: usize = 7;
+
+/// The exact game theoretic value of a position
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Solution {
+	/// Positive when the player to move wins and negative when they lose,
+	/// the further from zero the sooner the game is decided
+	pub score: i32,
+	pub outcome: Outcome,
+	/// How many more moves the game lasts when both sides play perfectly
+	pub moves_left: usize,
+}
+
+/// A position in the form the solver works with
+///
+/// `current` holds the pieces of the player to move and `mask` holds every
+/// piece, both laid out like the bitboards in `Connect4` but with only
+/// `COL_BITS` bits per column
+#[derive(Copy, Clone)]
+struct Position {
+	current: u64,
+	mask: u64,
+	moves: usize,
+}
+
+impl Position {
+	fn from_board(board: &Connect4) -> Self {
+		// Packs each column of the board's bitboards into the solver's narrower columns
+		let pack = |pieces: u128| {
+			(0..NUM_COLS).fold(0, |packed, col| {
+				let column = (pieces >> (col * connect4::COL_BITS)) as u64 & ((1 << NUM_ROWS) - 1);
+				packed | column << (col * COL_BITS)
+			})
+		};
+
+		Position {
+			current: pack(board.bitboards[board.active_player.index()]),
+			mask: pack(board.bitboards[0] | board.bitboards[1]),
+			moves: board.moves_played,
+		}
+	}
+
+	/// The position reflected left to right
+	fn mirrored(&self) -> Self {
+		let mirror = |pieces: u64| {
+			(0..NUM_COLS).fold(0, |mirrored, col| {
+				let column = (pieces >> (col * COL_BITS)) & column_mask(0);
+				mirrored | column << ((NUM_COLS - 1 - col) * COL_BITS)
+			})
+		};
+
+		Position {
+			current: mirror(self.current),
+			mask: mirror(self.mask),
+			moves: self.moves,
+		}
+	}
+
+	/// Plays a move given as a bitboard with the single cell being filled
+	fn play(&mut self, mv: u64) {
+		self.current ^= self.mask;
+		self.mask |= mv;
+		self.moves += 1;
+	}
+
+	/// A key that is unique for every position
+	fn key(&self) -> u64 {
+		self.current + self.mask
+	}
+
+	/// Gets the lowest empty cell of every column that isn't full
+	fn possible(&self) -> u64 {
+		(self.mask + BOTTOM_MASK) & BOARD_MASK
+	}
+
+	/// Checks if the player to move can win with their next piece
+	fn can_win_next(&self) -> bool {
+		winning_cells(self.current, self.mask) & self.possible() != 0
+	}
+
+	/// Gets the moves that don't hand the opponent a win on their next move
+	///
+	/// When the opponent threatens to win the threat has to be blocked, and when
+	/// they threaten twice every move loses
+	fn non_losing_moves(&self) -> u64 {
+		let mut possible = self.possible();
+		let opponent_wins = winning_cells(self.current ^ self.mask, self.mask);
+		let forced_moves = possible & opponent_wins;
+
+		if forced_moves != 0 {
+			if forced_moves & (forced_moves - 1) != 0 {
+				return 0;
+			}
+			possible = forced_moves;
+		}
+
+		// Never play directly underneath a cell the opponent wins with
+		possible & !(opponent_wins >> 1)
+	}
+
+	/// Rates a move by how many cells the player could win with after playing it
+	fn move_score(&self, mv: u64) -> u32 {
+		winning_cells(self.current | mv, self.mask).count_ones()
+	}
+}
+
+/// Gets the empty cells that would complete a line of four for `pieces`
+fn winning_cells(pieces: u64, mask: u64) -> u64 {
+	// Vertical lines can only be completed from the top
+	let mut cells = (pieces << 1) & (pieces << 2) & (pieces << 3);
+
+	// Horizontal and both diagonal lines can be completed at any of their four cells
+	for &shift in [COL_BITS, COL_BITS - 1, COL_BITS + 1].iter() {
+		let pairs = (pieces << shift) & (pieces << (2 * shift));
+		cells |= pairs & (pieces << (3 * shift));
+		cells |= pairs & (pieces >> shift);
+
+		let pairs = (pieces >> shift) & (pieces >> (2 * shift));
+		cells |= pairs & (pieces << shift);
+		cells |= pairs & (pieces >> (3 * shift));
+	}
+
+	cells & (BOARD_MASK ^ mask)
+}
+
+/// Gets the cells of a single column
+fn column_mask(col: usize) -> u64 {
+	((1 << NUM_ROWS) - 1) << (col * COL_BITS)
+}
+
+/// Reflects a set of columns, one bit per column, left to right
+fn mirror_columns(columns: u8) -> u8 {
+	(0..NUM_COLS).fold(0, |mirrored, col| match columns & 1 << col {
+		0 => mirrored,
+		_ => mirrored | 1 << (NUM_COLS - 1 - col),
+	})
+}
+
+/// Solves Connect 4 positions exactly with a full depth alpha-beta search
+///
+/// The solver remembers positions between calls, so analysing several moves
+/// from the same game is much faster with a single `Solver`. Solving the first
+/// few moves of a game takes from minutes up to half an hour, so the best moves
+/// of those positions are read from an opening book instead. The book built into the game covers
+/// every position with fewer than 6 pieces that either side can reach by
+/// playing the book's moves, whatever the other side replies. The positions
+/// after those mostly take a second or two to solve, and a few take up to a
+/// minute. The book has no scores, so `solve` and positions off the book, like
+/// a shared position, can still take as long as the first moves do, which
+/// `try_solve` and `try_best_moves` put a limit on. Only boards with
+/// `Connect4Rules::STANDARD` can be solved
+pub struct Solver {
+	/// The low 32 bits of each stored key, the table size being a prime larger
+	/// than 2^17 makes them enough to tell positions apart
+	keys: Vec<u32>,
+	/// An upper bound on the score of each stored position, offset to be positive
+	values: Vec<u8>,
+	/// The best columns of early positions sorted by key, one bit per column,
+	/// from the opening book
+	book: Vec<(u64, u8)>,
+	/// Every position in `book` has fewer than this many pieces
+	book_depth: usize,
+	/// The number of positions searched so far
+	pub nodes: u64,
+	/// The value of `nodes` the search gives up at, see `try_solve`
+	budget: Option<u64>,
+	/// Whether the search ran past `budget`
+	aborted: bool,
+}
+
+impl Solver {
+	/// Creates a solver that reads the opening book built into the game
+	pub fn new() -> Self {
+		let mut solver = Solver::without_book();
+		solver
+			.load_book(OPENING_BOOK)
+			.expect("The built in Connect 4 opening book is corrupt");
+		solver
+	}
+
+	/// Creates a solver that solves every position itself
+	pub fn without_book() -> Self {
+		Solver {
+			keys: vec![0; TABLE_SIZE],
+			values: vec![0; TABLE_SIZE],
+			book: vec![],
+			book_depth: 0,
+			nodes: 0,
+			budget: None,
+			aborted: false,
+		}
+	}
+
+	/// Reads an opening book written by `write_book`, returning an error when
+	/// the bytes aren't one
+	///
+	/// A book starts with the bytes `C4OB`, then the depth it was built to as a
+	/// byte and the number of entries as a little endian `u32`. Each entry is a
+	/// little endian 56 bit number, the key of the position shifted above its
+	/// best columns with one bit per column, and the entries are sorted by key
+	pub fn load_book(&mut self, bytes: &[u8]) -> Result<(), String> {
+		if bytes.len() < BOOK_HEADER_SIZE || &bytes[..4] != BOOK_MAGIC {
+			return Err(String::from("Not a Connect 4 opening book"));
+		}
+
+		let depth = bytes[4] as usize;
+		let mut count = [0; 4];
+		count.copy_from_slice(&bytes[5..BOOK_HEADER_SIZE]);
+		let count = u32::from_le_bytes(count) as usize;
+
+		if bytes.len() != BOOK_HEADER_SIZE + count * BOOK_ENTRY_SIZE {
+			return Err(format!("Expected {} entries in the opening book", count));
+		}
+
+		let mut book = Vec::with_capacity(count);
+		for entry in bytes[BOOK_HEADER_SIZE..].chunks(BOOK_ENTRY_SIZE) {
+			let mut packed = [0; 8];
+			packed[..BOOK_ENTRY_SIZE].copy_from_slice(entry);
+			let packed = u64::from_le_bytes(packed);
+
+			let columns = (packed & ((1 << NUM_COLS) - 1)) as u8;
+			book.push((packed >> NUM_COLS, columns));
+		}
+
+		if book.windows(2).any(|pair| pair[0].0 >= pair[1].0) {
+			return Err(String::from("The opening book isn't sorted"));
+		}
+
+		self.book = book;
+		self.book_depth = depth;
+		Ok(())
+	}
+
+	/// Finds the best moves of every position with fewer than `depth` pieces
+	/// that either side reaches by playing those moves, against every move the
+	/// other side makes, and writes them out in the format `load_book` reads
+	///
+	/// The number of positions solved so far and how many pieces the last one
+	/// had are reported to `progress` after each position
+	#[allow(dead_code)] // Books are only built from the CLI
+	pub fn write_book(&mut self, depth: usize, mut progress: impl FnMut(usize, usize)) -> Vec<u8> {
+		// A position and its reflection share the same best moves, reflected
+		let canonical = |board: &Connect4| {
+			let position = Position::from_board(board);
+			position.key().min(position.mirrored().key())
+		};
+
+		let mut book = vec![];
+		for &book_side in [0, 1].iter() {
+			let mut frontier = vec![Connect4::new()];
+			for moves in 0..depth {
+				let mut next = vec![];
+				for board in frontier {
+					let columns = match moves % 2 == book_side {
+						true => {
+							let columns = self.best_moves(&board);
+							let packed = columns.iter().fold(0, |packed, col| packed | 1 << col);
+							book.push((Position::from_board(&board).key(), packed));
+							progress(book.len(), moves);
+							columns
+						}
+						false => (0..NUM_COLS).collect(),
+					};
+
+					for col in columns {
+						let mut child = board;
+						if child.drop(col).is_ok() && !child.is_terminal {
+							next.push(child);
+						}
+					}
+				}
+
+				next.sort_by_key(canonical);
+				next.dedup_by_key(|board| canonical(board));
+				frontier = next;
+			}
+		}
+		book.sort_unstable();
+
+		let mut bytes = BOOK_MAGIC.to_vec();
+		bytes.push(depth as u8);
+		bytes.extend_from_slice(&(book.len() as u32).to_le_bytes());
+		for (key, columns) in book {
+			let packed = key << NUM_COLS | columns as u64;
+			bytes.extend_from_slice(&packed.to_le_bytes()[..BOOK_ENTRY_SIZE]);
+		}
+
+		bytes
+	}
+
+	/// Finds the exact value of a position for the player to move
+	pub fn solve(&mut self, board: &Connect4) -> Solution {
+		let score = if board.is_terminal {
+			// The previous move either won or filled the board
+			match board.winner {
+				Some(_) => -((NUM_CELLS + 2 - board.moves_played) as i32) / 2,
+				None => 0,
+			}
+		} else {
+			self.solve_position(&Position::from_board(board))
+		};
+
+		solution(score, board.moves_played)
+	}
+
+	/// Picks one of the moves that wins fastest, or failing that loses slowest,
+	/// at random
+	#[allow(dead_code)] // Only the CLI asks for a single perfect move
+	pub fn best_move(&mut self, board: &Connect4) -> usize {
+		*self
+			.best_moves(board)
+			.choose(&mut rand::thread_rng())
+			.expect("best_move called on a finished game")
+	}
+
+	/// Finds every move that wins fastest, or failing that loses slowest
+	///
+	/// Positions in the opening book are looked up, any other position is
+	/// solved exactly and each move is then checked against that score with a
+	/// single null window search
+	pub fn best_moves(&mut self, board: &Connect4) -> Vec<usize> {
+		if let Some(columns) = self.book_moves(&Position::from_board(board)) {
+			return (0..NUM_COLS)
+				.filter(|col| columns & 1 << col != 0)
+				.collect();
+		}
+
+		let score = self.solve(board).score;
+		let mut best_moves = vec![];
+
+		for col in 0..NUM_COLS {
+			let mut copy_board = *board;
+			if copy_board.drop(col).is_err() {
+				continue;
+			}
+
+			// A move keeps the score when the opponent can do no better than its negation
+			let keeps_score = if copy_board.is_terminal {
+				copy_board.winner.is_some() || score <= 0
+			} else {
+				let position = Position::from_board(&copy_board);
+				let opponent_score = match position.can_win_next() {
+					true => (NUM_CELLS as i32 + 1 - position.moves as i32) / 2,
+					false => self.negamax(&position, -score, -score + 1),
+				};
+				opponent_score <= -score
+			};
+
+			if keeps_score {
+				best_moves.push(col);
+			}
+		}
+
+		best_moves
+	}
+
+	/// Finds the value `solve` does, unless that takes more than `max_nodes`
+	/// positions, when it gives up and returns `None`
+	///
+	/// The book only has the best moves, so early positions can take far longer
+	/// to solve than to find a perfect move in. The positions solved before
+	/// giving up stay in the table, so asking again picks up where this left off
+	#[allow(dead_code)] // Only the CLI solves positions on their own
+	pub fn try_solve(&mut self, board: &Connect4, max_nodes: u64) -> Option<Solution> {
+		self.with_budget(max_nodes, |solver| solver.solve(board))
+	}
+
+	/// Finds the moves `best_moves` does, unless that takes more than
+	/// `max_nodes` positions, when it gives up and returns `None`
+	pub fn try_best_moves(&mut self, board: &Connect4, max_nodes: u64) -> Option<Vec<usize>> {
+		self.with_budget(max_nodes, |solver| solver.best_moves(board))
+	}
+
+	/// Runs `search`, giving up once it has searched `max_nodes` more positions
+	fn with_budget<T>(&mut self, max_nodes: u64, search: impl FnOnce(&mut Self) -> T) -> Option<T> {
+		self.budget = Some(self.nodes.saturating_add(max_nodes));
+		self.aborted = false;
+
+		let result = search(self);
+		self.budget = None;
+
+		match self.aborted {
+			true => None,
+			false => Some(result),
+		}
+	}
+
+	/// Looks up the best columns of a position, or of its reflection, in the
+	/// opening book
+	fn book_moves(&self, position: &Position) -> Option<u8> {
+		if position.moves >= self.book_depth {
+			return None;
+		}
+
+		let find = |key: u64| {
+			self.book
+				.binary_search_by_key(&key, |&(key, _)| key)
+				.ok()
+				.map(|i| self.book[i].1)
+		};
+		find(position.key()).or_else(|| find(position.mirrored().key()).map(mirror_columns))
+	}
+
+	/// Narrows in on the exact score with null window searches
+	fn solve_position(&mut self, position: &Position) -> i32 {
+		if position.can_win_next() {
+			return (NUM_CELLS as i32 + 1 - position.moves as i32) / 2;
+		}
+
+		let mut min = -(NUM_CELLS as i32 - position.moves as i32) / 2;
+		let mut max = (NUM_CELLS as i32 + 1 - position.moves as i32) / 2;
+
+		while min < max {
+			// Searching closer to zero first finds the result of most positions faster
+			let mut med = min + (max - min) / 2;
+			if med <= 0 && min / 2 < med {
+				med = min / 2;
+			} else if med >= 0 && max / 2 > med {
+				med = max / 2;
+			}
+
+			// Checks whether the score is above or below `med`
+			let score = self.negamax(position, med, med + 1);
+			if self.aborted {
+				// The score is thrown away, any that makes a valid solution will do
+				return 0;
+			} else if score <= med {
+				max = score;
+			} else {
+				min = score;
+			}
+		}
+
+		min
+	}
+
+	/// Scores a position the player to move can't win immediately
+	///
+	/// The result is exact inside `alpha..beta`, at most `alpha` when the true
+	/// score is lower and at least `beta` when it is higher
+	fn negamax(&mut self, position: &Position, mut alpha: i32, mut beta: i32) -> i32 {
+		self.nodes += 1;
+		if matches!(self.budget, Some(budget) if self.nodes > budget) {
+			self.aborted = true;
+			return 0;
+		}
+
+		let moves = position.non_losing_moves();
+		if moves == 0 {
+			// The opponent wins with their next move whatever is played
+			return -(NUM_CELLS as i32 - position.moves as i32) / 2;
+		}
+
+		// The board fills up before anyone can make four in a row
+		if position.moves >= NUM_CELLS - 2 {
+			return 0;
+		}
+
+		// The opponent can't win with their next move
+		let min = -(NUM_CELLS as i32 - 2 - position.moves as i32) / 2;
+		if alpha < min {
+			alpha = min;
+			if alpha >= beta {
+				return alpha;
+			}
+		}
+
+		// The player to move can't win with this move
+		let mut max = (NUM_CELLS as i32 - 1 - position.moves as i32) / 2;
+		let key = position.key();
+		let slot = (key % TABLE_SIZE as u64) as usize;
+		if self.keys[slot] == key as u32 && self.values[slot] != 0 {
+			max = self.values[slot] as i32 + MIN_SCORE - 1;
+		}
+
+		if beta > max {
+			beta = max;
+			if alpha >= beta {
+				return beta;
+			}
+		}
+
+		// Tries moves that set up the most threats first, center columns breaking ties
+		let mut ordered = [(0, 0); NUM_COLS];
+		let mut count = 0;
+		for &col in COLUMN_ORDER.iter() {
+			let mv = moves & column_mask(col);
+			if mv == 0 {
+				continue;
+			}
+
+			let score = position.move_score(mv);
+			let mut i = count;
+			while i > 0 && ordered[i - 1].1 < score {
+				ordered[i] = ordered[i - 1];
+				i -= 1;
+			}
+			ordered[i] = (mv, score);
+			count += 1;
+		}
+
+		for &(mv, _) in ordered[..count].iter() {
+			let mut next = *position;
+			next.play(mv);
+
+			// Nothing is stored for a position that wasn't finished
+			let score = -self.negamax(&next, -beta, -alpha);
+			if self.aborted {
+				return 0;
+			} else if score >= beta {
+				return score;
+			}
+			if score > alpha {
+				alpha = score;
+			}
+		}
+
+		// Every move scored at most `alpha`, so it is an upper bound on the score
+		self.keys[slot] = key as u32;
+		self.values[slot] = (alpha - MIN_SCORE + 1) as u8;
+
+		alpha
+	}
+}
+
+/// Works out who wins and when from a score and the number of moves played
+fn solution(score: i32, moves_played: usize) -> Solution {
+	// A score of `s` means the winning move comes after `NUM_CELLS + 1 - 2s`
+	// moves or one fewer, whichever leaves the winner to play it
+	let winning_move = |score: i32, by_player_to_move: bool| -> usize {
+		let winning_move = (NUM_CELLS as i32 + 1 - 2 * score) as usize;
+		if (winning_move % 2 == moves_played % 2) == by_player_to_move {
+			winning_move
+		} else {
+			winning_move - 1
+		}
+	};
+
+	if score > 0 {
+		Solution {
+			score,
+			outcome: Outcome::Win,
+			moves_left: winning_move(score, true) + 1 - moves_played,
+		}
+	} else if score < 0 {
+		Solution {
+			score,
+			outcome: Outcome::Loss,
+			moves_left: winning_move(-score, false) + 1 - moves_played,
+		}
+	} else {
+		Solution {
+			score,
+			outcome: Outcome::Draw,
+			moves_left: NUM_CELLS - moves_played,
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn opening_book_plays_the_center_and_mirrors() {
+		let mut solver = Solver::new();
+		assert!(!solver.book.is_empty());
+		assert_eq!(solver.best_moves(&Connect4::new()), vec![3]);
+
+		// Only one of a position and its reflection is kept in the book
+		let mut left = Connect4::new();
+		left.drop(0).unwrap();
+		let mut right = Connect4::new();
+		right.drop(NUM_COLS - 1).unwrap();
+		let mirrored: Vec<usize> = solver
+			.best_moves(&right)
+			.iter()
+			.map(|col| NUM_COLS - 1 - col)
+			.rev()
+			.collect();
+		assert_eq!(solver.best_moves(&left), mirrored);
+		assert_eq!(solver.nodes, 0);
+	}
+
+	#[test]
+	fn corrupt_books_are_turned_down() {
+		let mut solver = Solver::without_book();
+
+		let truncated = &OPENING_BOOK[..OPENING_BOOK.len() - 1];
+		assert!(solver.load_book(b"C4OB").is_err());
+		assert!(solver.load_book(truncated).is_err());
+		assert!(solver.load_book(OPENING_BOOK).is_ok());
+	}
+
+	fn play(moves: &[usize]) -> Connect4 {
+		let mut board = Connect4::new();
+		for &col in moves {
+			board.drop(col).unwrap();
+		}
+		board
+	}
+
+	#[test]
+	fn solves_an_immediate_win() {
+		let mut solver = Solver::without_book();
+		let board = play(&[0, 1, 0, 1, 0, 1]);
+
+		let solution = solver.solve(&board);
+		assert_eq!(solution.score, (NUM_CELLS as i32 + 1 - 6) / 2);
+		assert_eq!(solution.outcome, Outcome::Win);
+		assert_eq!(solution.moves_left, 1);
+		assert_eq!(solver.best_moves(&board), vec![0]);
+	}
+
+	#[test]
+	fn solves_a_loss_to_a_double_threat() {
+		let mut solver = Solver::without_book();
+		// Red threatens to finish the bottom row in both column 1 and column 5
+		let board = play(&[2, 2, 3, 3, 4]);
+
+		let solution = solver.solve(&board);
+		assert_eq!(solution.score, -(NUM_CELLS as i32 - 5) / 2);
+		assert_eq!(solution.outcome, Outcome::Loss);
+		assert_eq!(solution.moves_left, 2);
+		assert_eq!(solver.best_moves(&board).len(), NUM_COLS);
+	}
+
+	#[test]
+	fn finished_games_are_lost_by_the_player_to_move() {
+		let mut solver = Solver::without_book();
+		let board = play(&[0, 1, 0, 1, 0, 1, 0]);
+
+		let solution = solver.solve(&board);
+		assert_eq!(solution.outcome, Outcome::Loss);
+		assert_eq!(solution.moves_left, 0);
+		assert_eq!(solver.nodes, 0);
+	}
+
+	#[test]
+	fn gives_up_past_the_budget() {
+		let mut solver = Solver::new();
+		assert_eq!(solver.try_solve(&Connect4::new(), 1), None);
+
+		// The book still answers the first moves without any searching
+		assert_eq!(solver.try_best_moves(&Connect4::new(), 1), Some(vec![3]));
+
+		let board = play(&[0, 1, 0, 1, 0, 1]);
+		let solution = solver.try_solve(&board, u64::MAX).unwrap();
+		assert_eq!(solution.moves_left, 1);
+	}
+}
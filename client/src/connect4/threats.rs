@@ -0,0 +1,370 @@
+use super::{
+	connect4::{Connect4, COL_BITS},
+	piece_color::{PieceColor, PieceColor::*},
+};
+use std::{
+	collections::HashMap,
+	fmt::{Display, Formatter, Result},
+};
+
+/// The directions a line can run in, as a step in columns and in rows going up
+const DIRECTIONS: [(isize, isize); 4] = [(1, 0), (0, 1), (1, 1), (1, -1)];
+
+/// Where one color can finish a line, and what that means for the moves of
+/// the color it's worked out for
+///
+/// Only drops are looked at, in Pop Out a pop can finish a line too. In misère
+/// finishing a line loses, so winning moves are the moves to stay away from
+#[derive(Clone, Debug, PartialEq)]
+pub struct Threats {
+	/// The columns the color can drop into to finish a line right away
+	pub winning_moves: Vec<usize>,
+	/// The columns the other color can finish a line in with its next drop,
+	/// which the color has to drop into first to stop it
+	pub must_block: Vec<usize>,
+	/// The empty cells that would finish a line for the color but can't be
+	/// played until the cell under them is, as `[row, col]` top row first like
+	/// `Connect4::check_for_win`
+	pub unplayable: Vec<[usize; 2]>,
+	/// How many of `unplayable` are on odd rows, counting the bottom row as 1
+	pub odd_threats: usize,
+	/// How many of `unplayable` are on even rows, counting the bottom row as 1
+	pub even_threats: usize,
+}
+
+/// Finds the threats of `color` and the ones it has to answer
+pub fn analyse(board: &Connect4, color: PieceColor) -> Threats {
+	let own = winning_cells(board, color);
+	let playable = playable_cells(board);
+
+	let unplayable: Vec<usize> = cells(own & !playable).collect();
+	let odd_threats = unplayable.iter().filter(|&&cell| is_odd_row(cell)).count();
+
+	Threats {
+		winning_moves: cells(own & playable).map(|cell| cell / COL_BITS).collect(),
+		must_block: cells(winning_cells(board, color.switch()) & playable)
+			.map(|cell| cell / COL_BITS)
+			.collect(),
+		odd_threats,
+		even_threats: unplayable.len() - odd_threats,
+		unplayable: unplayable
+			.iter()
+			.map(|&cell| [board.rules.num_rows - 1 - cell % COL_BITS, cell / COL_BITS])
+			.collect(),
+	}
+}
+
+/// Who the end of the game favours once every cell that can safely be played
+/// has been, see `zugzwang`
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Zugzwang {
+	/// The color in control of the zugzwang, Yellow unless Red's threats win
+	/// the ending
+	pub control: PieceColor,
+	/// The color whose threats win the ending when neither color makes new
+	/// ones, `None` when it's a draw
+	pub winner: Option<PieceColor>,
+}
+
+impl Display for Zugzwang {
+	fn fmt(&self, f: &mut Formatter) -> Result {
+		match self.winner {
+			Some(winner) => write!(
+				f,
+				"{} controls the zugzwang and wins the end of the game unless new threats are made",
+				winner
+			),
+			None => write!(
+				f,
+				"{} controls the zugzwang and the end of the game is a draw unless new threats are made",
+				self.control
+			),
+		}
+	}
+}
+
+/// A threat nobody can play yet, as its row counting the bottom row as 1 and
+/// whether it's a threat for Red and for Yellow
+type Threat = (usize, [bool; 2]);
+
+/// Works out who controls the zugzwang at the end of the game, following
+/// Allis, or `None` when the game is over or the rules don't allow it
+///
+/// Every cell under a threat can be played until only the cells right under
+/// the threats are left, and whoever has to play one of those first lets the
+/// other color play the threat. Playing under an own threat only loses it,
+/// the other color blocks it and the column carries on above. Red, who moves
+/// first, needs a threat on an odd row to take control, Yellow is in control
+/// otherwise and wins with a threat on an even row. A threat lower in the
+/// same column comes first, and the other color's threats can change which
+/// color ends up having to give way, so rather than count threats the end of
+/// the game is played out over the threats each color has now. Threats that
+/// can be played right away are won or blocked first, and new threats made
+/// while the board fills up aren't looked at. It needs an even number of rows,
+/// and doesn't hold in Pop Out or misère
+pub fn zugzwang(board: &Connect4) -> Option<Zugzwang> {
+	let rules = board.rules;
+	if board.is_terminal || rules.num_rows % 2 == 1 || rules.pop_out || rules.misere {
+		return None;
+	}
+
+	let winner = ending_winner(board);
+	Some(Zugzwang {
+		control: winner.unwrap_or(YELLOW),
+		winner,
+	})
+}
+
+/// Plays out the end of the game for `zugzwang`, returning the color its
+/// threats win it for
+fn ending_winner(board: &Connect4) -> Option<PieceColor> {
+	if board.is_terminal {
+		return board.winner;
+	}
+
+	let color = board.active_player;
+	let playable = playable_cells(board);
+	let threats = [winning_cells(board, RED), winning_cells(board, YELLOW)];
+
+	// A threat that can be played now is won, or has to be blocked first
+	let to_block: Vec<usize> = cells(threats[color.switch().index()] & playable).collect();
+	if threats[color.index()] & playable != 0 {
+		return Some(color);
+	} else if to_block.len() > 1 {
+		return Some(color.switch());
+	} else if let Some(&cell) = to_block.first() {
+		let mut blocked = *board;
+		blocked
+			.drop(cell / COL_BITS)
+			.expect("A threat that can be played is in a column that isn't full");
+		return ending_winner(&blocked);
+	}
+
+	// The threats in each column from the bottom up, and the cells that can be
+	// played before the lowest of them
+	let num_rows = board.rules.num_rows;
+	let mut columns: Vec<Vec<Threat>> = vec![];
+	let mut free = 0;
+	for col in 0..board.rules.num_cols {
+		let height = board.column_heights[col];
+		let column: Vec<Threat> = (height + 1..=num_rows)
+			.map(|row| {
+				let cell = 1 << (col * COL_BITS + row - 1);
+				(row, [threats[0] & cell != 0, threats[1] & cell != 0])
+			})
+			.filter(|&(_, owners)| owners[0] || owners[1])
+			.collect();
+
+		free += match column.first() {
+			Some(&(row, _)) => row - 2 - height,
+			None => num_rows - height,
+		};
+		if !column.is_empty() {
+			columns.push(column);
+		}
+	}
+
+	let mut lowest = vec![0; columns.len()];
+	match play_ending(
+		&columns,
+		&mut lowest,
+		free % 2,
+		color,
+		num_rows,
+		&mut HashMap::new(),
+	) {
+		1 => Some(color),
+		-1 => Some(color.switch()),
+		_ => None,
+	}
+}
+
+/// Scores the end of the game for `color`, 1 when it wins, 0 for a draw and
+/// -1 when it loses
+///
+/// `lowest` holds the index of the lowest threat left in each column, and
+/// `free` whether an odd number of cells can be played without giving way.
+/// Two of those cells cancel out, when one color plays one the other can
+/// answer with another, so only how many there are modulo 2 counts
+fn play_ending(
+	columns: &[Vec<Threat>],
+	lowest: &mut Vec<usize>,
+	free: usize,
+	color: PieceColor,
+	num_rows: usize,
+	scores: &mut HashMap<(Vec<usize>, usize, usize), i32>,
+) -> i32 {
+	let key = (lowest.clone(), free, color.index());
+	if let Some(&score) = scores.get(&key) {
+		return score;
+	}
+
+	// With nothing left to play the board is full
+	let mut can_move = free > 0;
+	let mut best = match can_move {
+		true => -play_ending(columns, lowest, 0, color.switch(), num_rows, scores),
+		false => -1,
+	};
+
+	for (col, column) in columns.iter().enumerate() {
+		let (row, owners) = match column.get(lowest[col]) {
+			Some(&threat) => threat,
+			None => continue,
+		};
+		can_move = true;
+
+		// Playing under the other color's threat hands it the game
+		if owners[color.switch().index()] {
+			continue;
+		}
+
+		// Playing under an own threat has the other color block it, and then
+		// each color has to block any threat right above until the column is
+		// back to cells that can be played freely
+		let mut next = lowest[col] + 1;
+		let mut cell = row + 1;
+		let mut to_move = color;
+		let score = loop {
+			match column.get(next) {
+				Some(&(row, owners)) if row == cell => {
+					if owners[to_move.index()] {
+						break if to_move == color { 1 } else { -1 };
+					}
+					next += 1;
+					cell += 1;
+					to_move = to_move.switch();
+				}
+				threat => {
+					let freed = match threat {
+						Some(&(row, _)) => row - 1 - cell,
+						None => num_rows + 1 - cell,
+					};
+
+					let previous = lowest[col];
+					lowest[col] = next;
+					let free = (free + freed) % 2;
+					let score = play_ending(columns, lowest, free, to_move, num_rows, scores);
+					lowest[col] = previous;
+					break if to_move == color { score } else { -score };
+				}
+			}
+		};
+		best = best.max(score);
+	}
+
+	let score = match can_move {
+		true => best,
+		false => 0,
+	};
+	scores.insert(key, score);
+	score
+}
+
+/// Gets the empty cells that would finish a line for `color`, whether or not a
+/// piece can be dropped into them yet
+fn winning_cells(board: &Connect4, color: PieceColor) -> u128 {
+	let rules = board.rules;
+	let own = board.bitboards[color.index()];
+	let opponent = board.bitboards[color.switch().index()];
+	let line_length = rules.line_length as isize;
+
+	let mut winning = 0;
+	for &(col_step, row_step) in DIRECTIONS.iter() {
+		for col in 0..rules.num_cols as isize {
+			for row in 0..rules.num_rows as isize {
+				let last_col = col + col_step * (line_length - 1);
+				let last_row = row + row_step * (line_length - 1);
+				if last_col >= rules.num_cols as isize
+					|| !(0..rules.num_rows as isize).contains(&last_row)
+				{
+					continue;
+				}
+
+				let window = (0..line_length).fold(0, |window, i| {
+					let cell =
+						(col + col_step * i) as usize * COL_BITS + (row + row_step * i) as usize;
+					window | 1 << cell
+				});
+
+				// A window missing just one of the color's pieces, and none of the other's
+				if window & opponent == 0 && (window & own).count_ones() as isize == line_length - 1
+				{
+					winning |= window & !own;
+				}
+			}
+		}
+	}
+
+	winning
+}
+
+/// Gets the next cell a piece would land in, for every column that isn't full
+fn playable_cells(board: &Connect4) -> u128 {
+	(0..board.rules.num_cols)
+		.filter(|&col| board.column_heights[col] < board.rules.num_rows)
+		.fold(0, |playable, col| {
+			playable | 1 << (col * COL_BITS + board.column_heights[col])
+		})
+}
+
+/// Gets the index of every cell set in a bitboard, lowest first
+fn cells(mut bitboard: u128) -> impl Iterator<Item = usize> {
+	std::iter::from_fn(move || {
+		if bitboard == 0 {
+			return None;
+		}
+
+		let cell = bitboard.trailing_zeros() as usize;
+		bitboard &= bitboard - 1;
+		Some(cell)
+	})
+}
+
+/// Whether a cell is on an odd row, counting the bottom row as 1
+fn is_odd_row(cell: usize) -> bool {
+	(cell % COL_BITS + 1) % 2 == 1
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	/// Plays `moves` from the empty board
+	fn play(moves: &[usize]) -> Connect4 {
+		let mut board = Connect4::new();
+		for &col in moves {
+			board.drop(col).unwrap();
+		}
+		board
+	}
+
+	#[test]
+	fn no_threats_leaves_yellow_in_control() {
+		let zugzwang = zugzwang(&Connect4::new()).unwrap();
+
+		assert_eq!(zugzwang.control, YELLOW);
+		assert_eq!(zugzwang.winner, None);
+	}
+
+	#[test]
+	fn red_wins_with_an_odd_threat() {
+		// Red threatens the third row of column 3 with Yellow to move
+		let board = play(&[2, 2, 2, 1, 1, 0, 1, 0, 0]);
+		let zugzwang = zugzwang(&board).unwrap();
+
+		assert_eq!(analyse(&board, RED).odd_threats, 1);
+		assert_eq!(zugzwang.control, RED);
+		assert_eq!(zugzwang.winner, Some(RED));
+	}
+
+	#[test]
+	fn yellow_only_draws_with_an_odd_threat() {
+		// The same threat for Yellow, with Red to move
+		let board = play(&[6, 2, 2, 2, 1, 1, 0, 1, 0, 0]);
+		let zugzwang = zugzwang(&board).unwrap();
+
+		assert_eq!(analyse(&board, YELLOW).odd_threats, 1);
+		assert_eq!(zugzwang.control, YELLOW);
+		assert_eq!(zugzwang.winner, None);
+	}
+}
@@ -0,0 +1,98 @@
+use crate::engine::weights::{format_weights, parse_weights};
+use std::fmt::{Display, Formatter, Result};
+
+/// How much each kind of window counts towards `Connect4::calculate_score`
+///
+/// A window is a run of cells as long as a winning line, and only scores when
+/// it holds pieces of just one color
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Connect4Weights {
+	/// Each of the color's pieces in the center column
+	pub center_col: i32,
+	/// Two pieces short of a full line
+	pub line_of_two: i32,
+	/// One piece short of a full line
+	pub line_of_three: i32,
+	/// A full line, the game is won
+	pub line_of_four: i32,
+	/// The opponent is one piece short of a line, with the missing cell not
+	/// playable yet
+	pub opponent_line_of_three: i32,
+	/// The opponent can finish a line with their next piece
+	pub opponent_line_of_three_with_below: i32,
+	/// The opponent is two pieces short of a line
+	pub opponent_line_of_two: i32,
+}
+
+impl Connect4Weights {
+	/// The names of the weights in a weights file, in the order of `values`
+	pub const NAMES: [&'static str; 7] = [
+		"center_col",
+		"line_of_two",
+		"line_of_three",
+		"line_of_four",
+		"opponent_line_of_three",
+		"opponent_line_of_three_with_below",
+		"opponent_line_of_two",
+	];
+
+	/// The weights the CPU has always played with
+	pub const DEFAULT: Self = Connect4Weights {
+		center_col: 5,
+		line_of_two: 1,
+		line_of_three: 10,
+		line_of_four: 100_000,
+		opponent_line_of_three: -20,
+		opponent_line_of_three_with_below: -10_000,
+		opponent_line_of_two: -3,
+	};
+
+	/// Every weight, in the order of `NAMES`
+	pub fn values(&self) -> [i32; 7] {
+		[
+			self.center_col,
+			self.line_of_two,
+			self.line_of_three,
+			self.line_of_four,
+			self.opponent_line_of_three,
+			self.opponent_line_of_three_with_below,
+			self.opponent_line_of_two,
+		]
+	}
+
+	/// Builds weights from values in the order of `NAMES`
+	pub fn from_values(values: [i32; 7]) -> Self {
+		Connect4Weights {
+			center_col: values[0],
+			line_of_two: values[1],
+			line_of_three: values[2],
+			line_of_four: values[3],
+			opponent_line_of_three: values[4],
+			opponent_line_of_three_with_below: values[5],
+			opponent_line_of_two: values[6],
+		}
+	}
+
+	/// Reads weights written by `Display`, any weight left out keeps its
+	/// default value
+	#[allow(dead_code)] // Weights are only read from files by the CLI tools
+	pub fn parse(text: &str) -> std::result::Result<Self, String> {
+		let mut values = Connect4Weights::DEFAULT.values();
+		parse_weights(text, &Connect4Weights::NAMES, &mut values)?;
+
+		Ok(Connect4Weights::from_values(values))
+	}
+}
+
+impl Default for Connect4Weights {
+	fn default() -> Self {
+		Connect4Weights::DEFAULT
+	}
+}
+
+impl Display for Connect4Weights {
+	/// Prints out every weight on its own line, like `center_col = 5`
+	fn fmt(&self, f: &mut Formatter) -> Result {
+		f.write_str(&format_weights(&Connect4Weights::NAMES, &self.values()))
+	}
+}
@@ -0,0 +1,357 @@
+// The CPU searches in a web worker, so the page keeps responding while it
+// thinks. The worker is built from `src/bin/worker.rs` into `worker.js`.
+//
+// Boards can't be sent to the worker as they are, so positions are sent as
+// position strings and moves packed into numbers. Pop Out positions rebuilt
+// from a string don't remember repetitions from earlier in the game
+
+use crate::{
+	connect4::{connect4::Connect4, connect4_move::Connect4Move, cpu_con4},
+	engine::{
+		config::EngineConfig,
+		minimax::{MoveScore, SearchLimits, SearchReport},
+		transposition::Bound,
+	},
+	toot_and_otto::{cpu_toot, piece_letter::PieceLetter, toot_and_otto::TootAndOtto},
+	types::opponent::Opponent,
+};
+use serde::{Deserialize, Serialize};
+use strum::IntoEnumIterator;
+use yew::{
+	agent::{Agent, AgentLink, Bridge, Bridged, HandlerId, Public},
+	Callback,
+};
+
+/// A position for the worker, by the game it's from
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Position {
+	Connect4(String),
+	TootAndOtto(String),
+}
+
+/// What the worker is asked to do with a position
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Task {
+	/// Pick the move of the CPU at this index in `Opponent::iter`
+	Move(usize),
+	/// Score every move for the hints
+	Hints,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CpuRequest {
+	pub position: Position,
+	pub task: Task,
+}
+
+impl CpuRequest {
+	/// Asks for the move `engine` makes in `position`
+	pub fn cpu_move(position: Position, engine: Opponent) -> Self {
+		CpuRequest {
+			position,
+			task: Task::Move(
+				Opponent::iter()
+					.position(|opponent| opponent == engine)
+					.expect("Every opponent is iterated"),
+			),
+		}
+	}
+
+	/// Asks for the hints in `position`
+	pub fn hints(position: Position) -> Self {
+		CpuRequest {
+			position,
+			task: Task::Hints,
+		}
+	}
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum CpuResponse {
+	/// The worker has loaded and is taking requests
+	Ready,
+	/// The move the CPU picked in `position`, `None` when the game is over,
+	/// with what its search found when it searched a limited depth
+	Move {
+		position: Position,
+		mv: Option<u32>,
+		report: Option<Report>,
+	},
+	/// The score of every move in `position`, `None` when the game is over
+	Hints {
+		position: Position,
+		report: Option<Report>,
+	},
+}
+
+/// Moves packed into a number to be sent to and from the worker
+pub trait MoveCode: Copy {
+	fn encode(self) -> u32;
+	fn decode(code: u32) -> Self;
+}
+
+impl MoveCode for Connect4Move {
+	fn encode(self) -> u32 {
+		match self {
+			Connect4Move::Drop(col) => 2 * col as u32,
+			Connect4Move::Pop(col) => 2 * col as u32 + 1,
+		}
+	}
+
+	fn decode(code: u32) -> Self {
+		let col = (code / 2) as usize;
+		match code % 2 {
+			0 => Connect4Move::Drop(col),
+			_ => Connect4Move::Pop(col),
+		}
+	}
+}
+
+impl MoveCode for (usize, PieceLetter) {
+	fn encode(self) -> u32 {
+		match self {
+			(col, PieceLetter::T) => 2 * col as u32,
+			(col, PieceLetter::O) => 2 * col as u32 + 1,
+		}
+	}
+
+	fn decode(code: u32) -> Self {
+		let col = (code / 2) as usize;
+		match code % 2 {
+			0 => (col, PieceLetter::T),
+			_ => (col, PieceLetter::O),
+		}
+	}
+}
+
+/// A `SearchReport` with its moves packed
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Report {
+	best_move: u32,
+	score: i32,
+	principal_variation: Vec<u32>,
+	depth: usize,
+	nodes: u64,
+	/// Every root move with its score, and whether the score is only an upper
+	/// bound, the only other bound the root gives
+	moves: Vec<(u32, i32, bool)>,
+}
+
+impl Report {
+	fn pack<M: MoveCode>(report: SearchReport<M>) -> Self {
+		Report {
+			best_move: report.best_move.encode(),
+			score: report.score,
+			principal_variation: report
+				.principal_variation
+				.iter()
+				.map(|&mv| mv.encode())
+				.collect(),
+			depth: report.depth,
+			nodes: report.nodes,
+			moves: report
+				.moves
+				.iter()
+				.map(|scored| {
+					(
+						scored.mv.encode(),
+						scored.score,
+						scored.bound == Bound::Upper,
+					)
+				})
+				.collect(),
+		}
+	}
+
+	pub fn unpack<M: MoveCode>(self) -> SearchReport<M> {
+		SearchReport {
+			best_move: M::decode(self.best_move),
+			score: self.score,
+			principal_variation: self
+				.principal_variation
+				.into_iter()
+				.map(M::decode)
+				.collect(),
+			depth: self.depth,
+			nodes: self.nodes,
+			moves: self
+				.moves
+				.into_iter()
+				.map(|(mv, score, is_upper)| MoveScore {
+					mv: M::decode(mv),
+					score,
+					bound: match is_upper {
+						true => Bound::Upper,
+						false => Bound::Exact,
+					},
+				})
+				.collect(),
+		}
+	}
+}
+
+/// Searches the positions the boards send it, one at a time
+pub struct CpuWorker {
+	link: AgentLink<Self>,
+}
+
+impl Agent for CpuWorker {
+	type Reach = Public<Self>;
+	type Message = ();
+	type Input = CpuRequest;
+	type Output = CpuResponse;
+
+	fn create(link: AgentLink<Self>) -> Self {
+		CpuWorker { link }
+	}
+
+	fn update(&mut self, _msg: Self::Message) {}
+
+	/// Lets the bridge know the worker has loaded
+	fn connected(&mut self, id: HandlerId) {
+		self.link.respond(id, CpuResponse::Ready);
+	}
+
+	fn handle_input(&mut self, request: Self::Input, id: HandlerId) {
+		let config = EngineConfig::DEFAULT;
+		let response = match (&request.position, request.task) {
+			(Position::Connect4(position), Task::Move(engine)) => {
+				let (mv, report) = match Connect4::from_position(position) {
+					Ok(board) => cpu_move(
+						engine,
+						|limits| {
+							cpu_con4::minimax_search(board, board.active_player, limits, config)
+						},
+						|engine| cpu_con4::pick_move(board, board.active_player, engine, config),
+					),
+					Err(_) => (None, None),
+				};
+				CpuResponse::Move {
+					position: request.position,
+					mv,
+					report,
+				}
+			}
+			(Position::TootAndOtto(position), Task::Move(engine)) => {
+				let (mv, report) = match TootAndOtto::from_position(position) {
+					Ok(board) => cpu_move(
+						engine,
+						|limits| {
+							cpu_toot::minimax_search(board, board.active_player, limits, config)
+						},
+						|engine| cpu_toot::pick_move(board, board.active_player, engine, config),
+					),
+					Err(_) => (None, None),
+				};
+				CpuResponse::Move {
+					position: request.position,
+					mv,
+					report,
+				}
+			}
+			(Position::Connect4(position), Task::Hints) => CpuResponse::Hints {
+				report: Connect4::from_position(position)
+					.ok()
+					.and_then(cpu_con4::hints)
+					.map(Report::pack),
+				position: request.position,
+			},
+			(Position::TootAndOtto(position), Task::Hints) => CpuResponse::Hints {
+				report: TootAndOtto::from_position(position)
+					.ok()
+					.and_then(cpu_toot::hints)
+					.map(Report::pack),
+				position: request.position,
+			},
+		};
+
+		self.link.respond(id, response);
+	}
+
+	fn name_of_resource() -> &'static str {
+		"worker.js"
+	}
+}
+
+/// Picks the move of the CPU at `engine`'s index, keeping what its search found
+/// when it searched a limited depth
+fn cpu_move<M: MoveCode>(
+	engine: usize,
+	search: impl Fn(SearchLimits) -> Option<SearchReport<M>>,
+	pick_move: impl Fn(Opponent) -> Option<M>,
+) -> (Option<u32>, Option<Report>) {
+	let engine = match Opponent::iter().nth(engine) {
+		Some(engine) => engine,
+		None => return (None, None),
+	};
+
+	match engine.search_limits().and_then(search) {
+		Some(report) => (Some(report.best_move.encode()), Some(Report::pack(report))),
+		None => (pick_move(engine).map(MoveCode::encode), None),
+	}
+}
+
+/// A bridge to the CPU worker that can swap the worker for a new one, giving
+/// up on what the old one was searching
+pub struct CpuWorkerBridge {
+	/// `None` only while the worker is being swapped
+	bridge: Option<Box<dyn Bridge<CpuWorker>>>,
+	callback: Callback<CpuResponse>,
+	/// Whether the worker has loaded, it can't be swapped before then
+	is_ready: bool,
+	/// The requests sent that haven't been answered yet
+	pending: usize,
+}
+
+impl CpuWorkerBridge {
+	/// Starts a worker that answers through `callback`
+	pub fn new(callback: Callback<CpuResponse>) -> Self {
+		CpuWorkerBridge {
+			bridge: Some(CpuWorker::bridge(callback.clone())),
+			callback,
+			is_ready: false,
+			pending: 0,
+		}
+	}
+
+	pub fn send(&mut self, request: CpuRequest) {
+		if let Some(bridge) = &mut self.bridge {
+			bridge.send(request);
+			self.pending += 1;
+		}
+	}
+
+	/// Keeps track of the worker's response, `None` when it just says the
+	/// worker has loaded
+	pub fn receive(&mut self, response: CpuResponse) -> Option<CpuResponse> {
+		match response {
+			CpuResponse::Ready => {
+				self.is_ready = true;
+				None
+			}
+			response => {
+				self.pending = self.pending.saturating_sub(1);
+				Some(response)
+			}
+		}
+	}
+
+	/// Gives up on every request that hasn't been answered, with a new worker
+	/// for the next ones
+	///
+	/// A search can't be interrupted, so the old worker finishes what it was
+	/// sent before closing, but nothing it sends back is received. A worker
+	/// that is still loading is kept, yew loses track of workers dropped before
+	/// they load
+	pub fn cancel(&mut self) {
+		if self.pending == 0 || !self.is_ready {
+			return;
+		}
+
+		// The old bridge has to go first, or the new one joins the same worker
+		self.bridge = None;
+		self.bridge = Some(CpuWorker::bridge(self.callback.clone()));
+		self.is_ready = false;
+		self.pending = 0;
+	}
+}
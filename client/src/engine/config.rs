@@ -0,0 +1,129 @@
+use super::game::Game;
+use rand::{rngs::StdRng, seq::SliceRandom, thread_rng, Rng, SeedableRng};
+use std::fmt::{Display, Formatter, Result};
+
+/// How the CPU picks between moves that search equally well
+#[allow(dead_code)] // The web client always breaks ties randomly
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum TieBreak {
+	/// Any of the moves, picked at random
+	Random,
+	/// The move in the leftmost column
+	Leftmost,
+	/// The move closest to the center column, the left one of two equally close
+	CenterFirst,
+}
+
+impl TieBreak {
+	/// Reads a tie break policy written like its `Display`
+	#[allow(dead_code)] // Only read from the command line by the CLI tools
+	pub fn parse(text: &str) -> std::result::Result<Self, String> {
+		match text.trim().to_lowercase().as_str() {
+			"random" => Ok(TieBreak::Random),
+			"leftmost" => Ok(TieBreak::Leftmost),
+			"center-first" | "center" => Ok(TieBreak::CenterFirst),
+			other => Err(format!(
+				"'{}' isn't a tie break, use random, leftmost or center-first",
+				other
+			)),
+		}
+	}
+}
+
+impl Display for TieBreak {
+	fn fmt(&self, f: &mut Formatter) -> Result {
+		match self {
+			TieBreak::Random => write!(f, "random"),
+			TieBreak::Leftmost => write!(f, "leftmost"),
+			TieBreak::CenterFirst => write!(f, "center-first"),
+		}
+	}
+}
+
+/// How the CPU picks its move once the search has scored them
+///
+/// With a seed the CPU always plays the same move in the same position, so a
+/// game against it can be replayed exactly by playing the same moves against
+/// it again. Searches limited by time rather than depth or nodes, or split
+/// between threads, can still differ from run to run
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct EngineConfig {
+	/// Seeds every random choice the CPU makes, `None` to seed them randomly
+	pub seed: Option<u64>,
+	pub tie_break: TieBreak,
+	/// How far the CPU strays from the best move on purpose, 0 always plays the
+	/// best move, otherwise a move scoring `temperature` less than the best is
+	/// played about a third as often as the best
+	pub temperature: f64,
+	/// How many threads the search is split between, the web client always
+	/// searches on one
+	pub threads: usize,
+}
+
+impl EngineConfig {
+	/// Plays the best move, picking between equally good ones at random
+	pub const DEFAULT: Self = EngineConfig {
+		seed: None,
+		tie_break: TieBreak::Random,
+		temperature: 0.0,
+		threads: 1,
+	};
+
+	/// A random number generator for the choices made in the position with
+	/// `hash`, the same one every time when there is a seed
+	pub fn rng(&self, hash: u64) -> StdRng {
+		match self.seed {
+			Some(seed) => StdRng::seed_from_u64(seed ^ hash),
+			None => StdRng::from_rng(thread_rng()).expect("The thread RNG can seed another"),
+		}
+	}
+
+	/// Picks the move to play from every move `board` was searched with and its
+	/// score, the best scoring one unless there is a temperature
+	pub fn pick<G: Game>(&self, board: &G, scored: &[(G::Move, i32)]) -> G::Move {
+		let best = scored
+			.iter()
+			.map(|&(_, score)| score)
+			.max()
+			.expect("pick called without any moves");
+		let mut rng = self.rng(board.hash());
+
+		if self.temperature > 0.0 {
+			return scored
+				.choose_weighted(&mut rng, |&(_, score)| {
+					((score - best) as f64 / self.temperature).exp()
+				})
+				.expect("The best move always has a weight")
+				.0;
+		}
+
+		let best_moves: Vec<G::Move> = scored
+			.iter()
+			.filter(|&&(_, score)| score == best)
+			.map(|&(mv, _)| mv)
+			.collect();
+		self.break_tie(board, &best_moves, &mut rng)
+	}
+
+	/// Picks one of the equally good `moves` by the tie break policy
+	pub fn break_tie<G: Game>(&self, board: &G, moves: &[G::Move], rng: &mut impl Rng) -> G::Move {
+		let center_distance =
+			|mv| (2 * board.column(mv) as isize - board.num_cols() as isize + 1).abs();
+
+		match self.tie_break {
+			TieBreak::Random => moves.choose(rng).copied(),
+			TieBreak::Leftmost => moves.iter().copied().min_by_key(|&mv| board.column(mv)),
+			TieBreak::CenterFirst => moves
+				.iter()
+				.copied()
+				.min_by_key(|&mv| (center_distance(mv), board.column(mv))),
+		}
+		.expect("break_tie called without any moves")
+	}
+}
+
+impl Default for EngineConfig {
+	fn default() -> Self {
+		EngineConfig::DEFAULT
+	}
+}
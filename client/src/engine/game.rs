@@ -0,0 +1,85 @@
+/// The common surface shared by the two player board games in the client
+///
+/// Anything that implements `Game` can be searched by the generic CPU in
+/// `engine::minimax`, so a new game only has to describe its rules here. Games
+/// are `Send` and `Sync` so the search can be split between threads
+pub trait Game: Clone + Send + Sync {
+	/// A single move that can be played by the active player
+	type Move: Copy + PartialEq + Send + Sync;
+
+	/// One of the two sides playing the game
+	type Player: Copy + PartialEq + Send;
+
+	/// Gets every legal move for the active player, most promising first
+	fn legal_moves(&self) -> Vec<Self::Move>;
+
+	/// Plays a move for the active player, returns false if it is illegal
+	fn apply(&mut self, mv: Self::Move) -> bool;
+
+	/// Takes back the last move played, returns false if there is none
+	fn undo(&mut self) -> bool;
+
+	/// Plays the last move taken back again, returns false if there is none
+	fn redo(&mut self) -> bool;
+
+	/// The player whose turn it is
+	fn active_player(&self) -> Self::Player;
+
+	/// Whether the game has ended in a win or a draw
+	fn is_terminal(&self) -> bool;
+
+	/// The winner of the game, `None` while in progress or after a draw
+	fn winner(&self) -> Option<Self::Player>;
+
+	/// A heuristic score of the position from the point of view of `player`
+	fn evaluate(&self, player: Self::Player) -> i32;
+
+	/// The most moves a single game can last
+	fn max_moves(&self) -> usize;
+
+	/// A Zobrist hash of the position, kept up to date as moves are played
+	fn hash(&self) -> u64;
+
+	/// The hash of the position reflected left to right
+	fn mirrored_hash(&self) -> u64;
+
+	/// Reflects a move left to right
+	fn mirror_move(&self, mv: Self::Move) -> Self::Move;
+
+	/// The column a move is played in, counting from the left
+	fn column(&self, mv: Self::Move) -> usize;
+
+	/// How many columns the board has
+	fn num_cols(&self) -> usize;
+
+	/// Takes back moves until it is `player`'s turn again, so playing against
+	/// the CPU takes back its reply along with the player's move
+	fn undo_turn(&mut self, player: Self::Player) -> bool {
+		if !self.undo() {
+			return false;
+		}
+
+		while self.active_player() != player && self.undo() {}
+
+		true
+	}
+
+	/// Plays moves taken back again until it is `player`'s turn, or the game is over
+	fn redo_turn(&mut self, player: Self::Player) -> bool {
+		if !self.redo() {
+			return false;
+		}
+
+		while self.active_player() != player && self.redo() {}
+
+		true
+	}
+}
+
+/// Who wins a position with perfect play, from the view of the player to move
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Outcome {
+	Win,
+	Draw,
+	Loss,
+}
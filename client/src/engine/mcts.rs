@@ -0,0 +1,251 @@
+use super::{config::EngineConfig, game::Game, minimax::now_millis};
+use rand::seq::SliceRandom;
+
+/// How strongly the search favors trying moves it has played out less, the
+/// square root of two balances the two evenly for results between 0 and 1
+const EXPLORATION: f64 = std::f64::consts::SQRT_2;
+
+/// How many playouts are run between checks of the clock, a power of two
+const CLOCK_CHECK_INTERVAL: u64 = 64;
+
+/// How many playouts, or for how long, the Monte Carlo search runs
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct MctsLimits {
+	/// Stops once this many games have been played out
+	pub playouts: Option<u64>,
+	/// Stops once this many milliseconds have passed
+	pub millis: Option<u64>,
+}
+
+impl MctsLimits {
+	/// Plays out a fixed number of games, however long they take
+	pub fn playouts(playouts: u64) -> Self {
+		MctsLimits {
+			playouts: Some(playouts),
+			millis: None,
+		}
+	}
+}
+
+/// How a move at the root fared during a Monte Carlo search
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct MoveStats<M> {
+	pub mv: M,
+	/// How many playouts started with the move
+	pub visits: u64,
+	/// The points the player to move won in those playouts, with a draw worth
+	/// half a point
+	pub wins: f64,
+}
+
+impl<M> MoveStats<M> {
+	/// The share of the points the move won, between 0 and 1
+	pub fn win_rate(&self) -> f64 {
+		match self.visits {
+			0 => 0.0,
+			visits => self.wins / visits as f64,
+		}
+	}
+}
+
+/// A position in the search tree
+struct Node<P, M> {
+	/// The move that led here from the parent, `None` at the root
+	mv: Option<M>,
+	/// The player who made `mv`, whose points `wins` counts
+	player: Option<P>,
+	parent: Option<usize>,
+	children: Vec<usize>,
+	/// Moves that haven't been given a child yet, in a random order
+	untried: Vec<M>,
+	visits: u64,
+	wins: f64,
+}
+
+/// Searches the position for the active player with Monte Carlo tree search,
+/// playing out random games for as long as `limits` allow
+///
+/// Each playout walks down the tree picking the child with the best upper
+/// confidence bound (UCT), adds a child for a move not tried yet, then plays
+/// random moves to the end of the game. Returns every move with how often it
+/// was played out and how well it did, the most played out first
+///
+/// The random moves are drawn from `config`'s random number generator, so with
+/// a seed and a playout limit the search always gives the same result
+pub fn search<G: Game>(
+	board: &G,
+	limits: MctsLimits,
+	config: EngineConfig,
+) -> Vec<MoveStats<G::Move>> {
+	let mut rng = config.rng(board.hash());
+	let start = now_millis();
+
+	let mut untried = board.legal_moves();
+	untried.shuffle(&mut rng);
+	let mut nodes = vec![Node {
+		mv: None,
+		player: None,
+		parent: None,
+		children: vec![],
+		untried,
+		visits: 0,
+		wins: 0.0,
+	}];
+
+	let mut playouts = 0;
+	while !is_out_of_budget(limits, playouts, start) {
+		let mut board = board.clone();
+		let mut node = 0;
+
+		// Selection, down the tree while every move of the node has a child
+		while nodes[node].untried.is_empty() && !nodes[node].children.is_empty() {
+			node = best_child(&nodes, node);
+			board.apply(nodes[node].mv.expect("Only the root has no move"));
+		}
+
+		// Expansion, one child for a move that hasn't been tried
+		if let Some(mv) = nodes[node].untried.pop() {
+			let player = board.active_player();
+			board.apply(mv);
+
+			let mut untried = match board.is_terminal() {
+				true => vec![],
+				false => board.legal_moves(),
+			};
+			untried.shuffle(&mut rng);
+
+			nodes.push(Node {
+				mv: Some(mv),
+				player: Some(player),
+				parent: Some(node),
+				children: vec![],
+				untried,
+				visits: 0,
+				wins: 0.0,
+			});
+			let child = nodes.len() - 1;
+			nodes[node].children.push(child);
+			node = child;
+		}
+
+		// Simulation, random moves to the end of the game
+		while !board.is_terminal() {
+			let mv = *board
+				.legal_moves()
+				.choose(&mut rng)
+				.expect("A game that isn't over has moves");
+			board.apply(mv);
+		}
+
+		// Backpropagation, every node on the way counts the result for the
+		// player who moved into it
+		let winner = board.winner();
+		let mut current = Some(node);
+		while let Some(index) = current {
+			let node = &mut nodes[index];
+			node.visits += 1;
+			node.wins += match (winner, node.player) {
+				(None, _) => 0.5,
+				(Some(winner), Some(player)) if winner == player => 1.0,
+				_ => 0.0,
+			};
+			current = node.parent;
+		}
+
+		playouts += 1;
+	}
+
+	let mut moves: Vec<_> = nodes[0]
+		.children
+		.iter()
+		.map(|&child| MoveStats {
+			mv: nodes[child].mv.expect("Only the root has no move"),
+			visits: nodes[child].visits,
+			wins: nodes[child].wins,
+		})
+		.collect();
+
+	// Moves that never got a playout are still reported, after the rest
+	moves.extend(nodes[0].untried.iter().map(|&mv| MoveStats {
+		mv,
+		visits: 0,
+		wins: 0.0,
+	}));
+
+	moves.sort_by(|a, b| {
+		b.visits.cmp(&a.visits).then(
+			b.win_rate()
+				.partial_cmp(&a.win_rate())
+				.expect("Win rates are never NaN"),
+		)
+	});
+	moves
+}
+
+/// The child of `node` with the highest upper confidence bound, trading off
+/// how well each child did against how little it has been tried
+fn best_child<P, M>(nodes: &[Node<P, M>], node: usize) -> usize {
+	let log_visits = (nodes[node].visits as f64).ln();
+	let bound = |child: usize| {
+		let visits = nodes[child].visits as f64;
+		nodes[child].wins / visits + EXPLORATION * (log_visits / visits).sqrt()
+	};
+
+	*nodes[node]
+		.children
+		.iter()
+		.max_by(|&&a, &&b| {
+			bound(a)
+				.partial_cmp(&bound(b))
+				.expect("Bounds are never NaN")
+		})
+		.expect("The node has children")
+}
+
+/// Checks whether the search has used up its playout or time budget, always
+/// allowing a single playout so there is a move to pick
+fn is_out_of_budget(limits: MctsLimits, playouts: u64, start: f64) -> bool {
+	if playouts == 0 {
+		return false;
+	}
+
+	if let Some(max_playouts) = limits.playouts {
+		if playouts >= max_playouts {
+			return true;
+		}
+	}
+
+	match limits.millis {
+		Some(millis) if playouts & (CLOCK_CHECK_INTERVAL - 1) == 0 => {
+			now_millis() - start > millis as f64
+		}
+		_ => false,
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::connect4::connect4::Connect4;
+
+	#[test]
+	fn seeded_playouts_are_reproducible() {
+		let mut board = Connect4::new();
+		board.drop(3).unwrap();
+		let config = EngineConfig {
+			seed: Some(42),
+			..EngineConfig::DEFAULT
+		};
+		let visits = || -> Vec<_> {
+			search(&board, MctsLimits::playouts(500), config)
+				.iter()
+				.map(|stats| (stats.mv, stats.visits, stats.wins))
+				.collect()
+		};
+
+		let first = visits();
+		assert_eq!(first, visits());
+		assert_eq!(first.iter().map(|&(_, visits, _)| visits).sum::<u64>(), 500);
+		assert!(first.windows(2).all(|pair| pair[0].1 >= pair[1].1));
+	}
+}
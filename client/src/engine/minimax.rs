@@ -0,0 +1,709 @@
+use super::{
+	config::EngineConfig,
+	game::Game,
+	transposition::{Bound, Entry, TranspositionTable},
+	zobrist,
+};
+use std::{
+	sync::{
+		atomic::{AtomicBool, AtomicI32, AtomicU64, AtomicUsize, Ordering},
+		Arc,
+	},
+	thread,
+};
+
+/// The score of a won position, wins found in fewer moves score higher
+const WIN_SCORE: i32 = 1_000_000_000;
+
+/// A score no position can reach, used as the initial search window
+const INFINITY: i32 = WIN_SCORE + 1;
+
+/// The transposition table holds `2^TABLE_SIZE_BITS` positions
+const TABLE_SIZE_BITS: usize = 16;
+
+/// Scores past this are wins or losses rather than heuristic scores
+const WIN_THRESHOLD: i32 = WIN_SCORE - 1_000;
+
+/// How many nodes are searched between checks of the clock and of the other
+/// threads, a power of two
+const CLOCK_CHECK_INTERVAL: u64 = 1024;
+
+/// How deep and for how long the CPU is allowed to search
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct SearchLimits {
+	/// The deepest iteration that will be searched
+	pub depth: usize,
+	/// Stops deepening once this many positions have been visited
+	pub nodes: Option<u64>,
+	/// Stops deepening once this many milliseconds have passed
+	pub millis: Option<u64>,
+}
+
+impl SearchLimits {
+	/// Searches to a fixed depth, however long it takes
+	pub fn depth(depth: usize) -> Self {
+		SearchLimits {
+			depth,
+			nodes: None,
+			millis: None,
+		}
+	}
+}
+
+/// The score the search gave one of the moves at the root
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct MoveScore<M> {
+	pub mv: M,
+	/// The score of the position after the move, for the player who made it
+	pub score: i32,
+	/// `Exact` for the true score, `Upper` when the move was only found to score
+	/// at most this, which is enough to know it isn't the best
+	pub bound: Bound,
+}
+
+/// What a search found, explaining the move the CPU picked
+#[derive(Clone, Debug, PartialEq)]
+pub struct SearchReport<M> {
+	pub best_move: M,
+	/// The score of `best_move` for the player to move, see `format_score`
+	pub score: i32,
+	/// The moves both sides are expected to play, starting with `best_move`
+	pub principal_variation: Vec<M>,
+	/// The deepest iteration that finished
+	pub depth: usize,
+	/// The number of positions visited, counting the unfinished iteration
+	pub nodes: u64,
+	/// Every root move with its score, the best scoring first
+	pub moves: Vec<MoveScore<M>>,
+}
+
+/// Searches the position for the active player as deep as `limits` allow,
+/// returning the move picked between the scored moves as `config` says along
+/// with the scores and line behind it
+///
+/// The position is searched one ply deeper at a time, so when the budget runs
+/// out mid-iteration the result of the last finished iteration is played. The
+/// first iteration always finishes, however small the budget, so there is a
+/// move to play. With
+/// more than one thread in `config` the root moves are split between them
+pub fn search<G: Game>(
+	board: &G,
+	limits: SearchLimits,
+	config: EngineConfig,
+) -> SearchReport<G::Move> {
+	// Picking with a temperature needs the exact score of every move, not just
+	// the best ones
+	search_root(board, limits, config, config.temperature > 0.0)
+}
+
+/// Scores every move in the position for the active player as deep as
+/// `limits` allow, exactly rather than only well enough to tell the best ones
+/// apart, for showing the user how good each of their moves is
+pub fn analyse<G: Game>(board: &G, limits: SearchLimits) -> SearchReport<G::Move> {
+	search_root(board, limits, EngineConfig::DEFAULT, true)
+}
+
+/// Searches the position one ply deeper at a time, only keeping every move's
+/// score exact when `exact` is set
+fn search_root<G: Game>(
+	board: &G,
+	limits: SearchLimits,
+	config: EngineConfig,
+	exact: bool,
+) -> SearchReport<G::Move> {
+	// Threads can't be started from the browser
+	let threads = match cfg!(target_arch = "wasm32") {
+		true => 1,
+		false => config.threads.max(1),
+	};
+
+	// Each thread gets its own table, but they share the budget
+	let start = now_millis();
+	let shared = Arc::new(Shared::default());
+	let mut searches: Vec<Search<G::Player, G::Move>> = (0..threads)
+		.map(|_| Search {
+			cpu: board.active_player(),
+			limits,
+			table: TranspositionTable::new(TABLE_SIZE_BITS),
+			nodes: 0,
+			start,
+			aborted: false,
+			is_first_iteration: true,
+			shared: Arc::clone(&shared),
+		})
+		.collect();
+
+	let mut scored: Vec<MoveScore<G::Move>> = vec![];
+	let mut finished_depth = 0;
+
+	// Always look at least one move ahead so there is a move to return, and
+	// never further than the game can last
+	for depth in 1..=limits.depth.clamp(1, board.max_moves()) {
+		let best_score = scored.iter().map(|scored| scored.score).max();
+		let best_moves: Vec<G::Move> = scored
+			.iter()
+			.filter(|scored| Some(scored.score) == best_score)
+			.map(|scored| scored.mv)
+			.collect();
+
+		let (moves, score) = match searches.as_mut_slice() {
+			[search] => search.root(board, depth, &best_moves, exact),
+			searches => root_parallel(searches, board, depth, &best_moves, exact),
+		};
+		if searches.iter().any(|search| search.aborted) {
+			break;
+		}
+
+		for search in searches.iter_mut() {
+			search.is_first_iteration = false;
+		}
+
+		scored = moves;
+		finished_depth = depth;
+
+		// Searching deeper can't improve on a forced result
+		if score.abs() > WIN_SCORE - board.max_moves() as i32 {
+			break;
+		}
+	}
+
+	let pairs: Vec<(G::Move, i32)> = scored
+		.iter()
+		.map(|scored| (scored.mv, scored.score))
+		.collect();
+	let best_move = config.pick(board, &pairs);
+	let score = pairs
+		.iter()
+		.find(|&&(mv, _)| mv == best_move)
+		.map(|&(_, score)| score)
+		.expect("The picked move was scored");
+
+	// Stable, so moves that score the same stay in the order they were searched
+	scored.sort_by_key(|scored| -scored.score);
+
+	SearchReport {
+		best_move,
+		score,
+		principal_variation: principal_variation(&searches, board, best_move, finished_depth),
+		depth: finished_depth,
+		nodes: searches.iter().map(|search| search.nodes).sum(),
+		moves: scored,
+	}
+}
+
+/// Describes a score from `SearchReport`, wins and losses by how many moves
+/// away they are and anything else by its heuristic value
+pub fn format_score(score: i32) -> String {
+	if score > WIN_THRESHOLD {
+		format!("win in {}", WIN_SCORE - score)
+	} else if score < -WIN_THRESHOLD {
+		format!("loss in {}", WIN_SCORE + score)
+	} else {
+		format!("{:+}", score)
+	}
+}
+
+/// The state of a single search for the best move
+struct Search<P, M> {
+	/// The player the CPU is searching for
+	cpu: P,
+	limits: SearchLimits,
+	/// Results of positions already searched, shared between iterations
+	table: TranspositionTable<M>,
+	/// The number of positions visited so far
+	nodes: u64,
+	/// When the search started, in milliseconds
+	start: f64,
+	/// Whether the budget ran out before the current iteration finished
+	aborted: bool,
+	/// Whether the one ply iteration is being searched, which ignores the budget
+	is_first_iteration: bool,
+	/// The budget of every search split between threads with this one
+	shared: Arc<Shared>,
+}
+
+/// What the searches split between threads share
+#[derive(Default)]
+struct Shared {
+	/// The positions every thread has visited, counted `CLOCK_CHECK_INTERVAL`
+	/// at a time
+	nodes: AtomicU64,
+	/// Set by the first search to run out of budget, so the rest stop too
+	stop: AtomicBool,
+}
+
+impl<P: Copy + PartialEq, M: Copy + PartialEq> Search<P, M> {
+	/// Searches every root move to `depth`, returning each move with its score
+	/// and the best score
+	///
+	/// Only the scores of the moves tied for best are sure to be exact unless
+	/// `exact` is set, the rest may just be known to be worse. The previous
+	/// iteration's best moves are searched first, which lets alpha-beta prune
+	/// far more of the remaining moves
+	fn root<G: Game<Player = P, Move = M>>(
+		&mut self,
+		board: &G,
+		depth: usize,
+		previous_best: &[G::Move],
+		exact: bool,
+	) -> (Vec<MoveScore<G::Move>>, i32) {
+		let mut moves = board.legal_moves();
+		moves.sort_by_key(|mv| !previous_best.contains(mv));
+
+		let mut best_score = -INFINITY;
+		let mut scored = vec![];
+
+		// Moves are played and taken back on a single copy of the board
+		let mut board = board.clone();
+		for mv in moves {
+			let alpha = root_alpha(best_score, exact);
+			match self.root_move(&mut board, mv, depth, alpha) {
+				Some(move_score) => {
+					best_score = best_score.max(move_score.score);
+					scored.push(move_score);
+				}
+				None if self.aborted => break,
+				None => {}
+			}
+		}
+
+		(scored, best_score)
+	}
+
+	/// Searches a single root move to `depth`, `None` when the move is illegal or
+	/// the budget ran out
+	///
+	/// The score is only exact when it's more than `alpha`
+	fn root_move<G: Game<Player = P, Move = M>>(
+		&mut self,
+		board: &mut G,
+		mv: M,
+		depth: usize,
+		alpha: i32,
+	) -> Option<MoveScore<M>> {
+		if !board.apply(mv) {
+			return None;
+		}
+
+		let score = -self.negamax(board, depth - 1, 1, -INFINITY, -alpha);
+		board.undo();
+		if self.aborted {
+			return None;
+		}
+
+		let bound = match score > alpha {
+			true => Bound::Exact,
+			false => Bound::Upper,
+		};
+		Some(MoveScore { mv, score, bound })
+	}
+
+	/// Scores the position for the player to move, pruning lines outside `alpha..beta`
+	///
+	/// Moves are searched by playing them on `board` and taking them back after
+	fn negamax<G: Game<Player = P, Move = M>>(
+		&mut self,
+		board: &mut G,
+		depth: usize,
+		ply: usize,
+		mut alpha: i32,
+		beta: i32,
+	) -> i32 {
+		self.nodes += 1;
+		if !self.is_first_iteration && self.is_out_of_budget() {
+			self.aborted = true;
+			self.shared.stop.store(true, Ordering::Relaxed);
+			return 0;
+		}
+
+		let to_move = board.active_player();
+
+		if board.is_terminal() {
+			return match board.winner() {
+				// Draw
+				None => 0,
+				// Wins found sooner score higher than wins found later
+				Some(player) if player == to_move => WIN_SCORE - ply as i32,
+				Some(_) => -WIN_SCORE + ply as i32,
+			};
+		} else if depth == 0 {
+			// Positions are always scored from the CPU's point of view
+			let score = board.evaluate(self.cpu);
+			return if to_move == self.cpu { score } else { -score };
+		}
+
+		// Mirror images share an entry, so a stored move may need reflecting back
+		let (hash, is_mirrored) = zobrist::canonical(board.hash(), board.mirrored_hash());
+		let orient = |board: &G, mv: M| {
+			if is_mirrored {
+				board.mirror_move(mv)
+			} else {
+				mv
+			}
+		};
+
+		let mut table_move = None;
+		if let Some(entry) = self.table.probe(hash) {
+			if entry.depth >= depth {
+				let score = score_from_table(entry.score, ply);
+				let is_usable = match entry.bound {
+					Bound::Exact => true,
+					Bound::Lower => score >= beta,
+					Bound::Upper => score <= alpha,
+				};
+
+				if is_usable {
+					return score;
+				}
+			}
+
+			table_move = entry.best_move.map(|mv| orient(board, mv));
+		}
+
+		// The best move found last time this position was searched is tried first
+		let mut moves = board.legal_moves();
+		if let Some(table_move) = table_move {
+			moves.sort_by_key(|&mv| mv != table_move);
+		}
+
+		let original_alpha = alpha;
+		let mut best_score = -INFINITY;
+		let mut best_move = None;
+
+		for mv in moves {
+			if !board.apply(mv) {
+				continue;
+			}
+
+			let score = -self.negamax(board, depth - 1, ply + 1, -beta, -alpha);
+			board.undo();
+			if self.aborted {
+				return 0;
+			}
+
+			if score > best_score {
+				best_score = score;
+				best_move = Some(mv);
+			}
+
+			alpha = alpha.max(score);
+			if alpha >= beta {
+				break;
+			}
+		}
+
+		let bound = if best_score <= original_alpha {
+			Bound::Upper
+		} else if best_score >= beta {
+			Bound::Lower
+		} else {
+			Bound::Exact
+		};
+
+		self.table.store(Entry {
+			hash,
+			depth,
+			score: score_to_table(best_score, ply),
+			bound,
+			best_move: best_move.map(|mv| orient(board, mv)),
+		});
+
+		best_score
+	}
+
+	/// Checks whether the search has used up its node or time budget
+	fn is_out_of_budget(&self) -> bool {
+		if let Some(nodes) = self.limits.nodes {
+			if self.nodes > nodes {
+				return true;
+			}
+		}
+
+		if self.nodes & (CLOCK_CHECK_INTERVAL - 1) != 0 {
+			return false;
+		}
+
+		// Positions visited by the other threads count against the budget too
+		let shared_nodes = self
+			.shared
+			.nodes
+			.fetch_add(CLOCK_CHECK_INTERVAL, Ordering::Relaxed)
+			+ CLOCK_CHECK_INTERVAL;
+		if let Some(nodes) = self.limits.nodes {
+			if shared_nodes > nodes {
+				return true;
+			}
+		}
+
+		match self.limits.millis {
+			Some(millis) if now_millis() - self.start > millis as f64 => true,
+			_ => self.shared.stop.load(Ordering::Relaxed),
+		}
+	}
+}
+
+/// Searches every root move to `depth` like `Search::root`, splitting the moves
+/// between `searches` with a thread each
+///
+/// The previous iteration's best move is searched alone first, so every thread
+/// starts with its score to prune the rest against. Which moves are exact can
+/// differ from a search on one thread, as it depends on the order the threads
+/// finish their moves in
+fn root_parallel<G: Game>(
+	searches: &mut [Search<G::Player, G::Move>],
+	board: &G,
+	depth: usize,
+	previous_best: &[G::Move],
+	exact: bool,
+) -> (Vec<MoveScore<G::Move>>, i32) {
+	let mut moves = board.legal_moves();
+	moves.sort_by_key(|mv| !previous_best.contains(mv));
+
+	// Moves are kept with their index, so they can be listed in search order
+	let mut scored = vec![];
+	let mut first_board = board.clone();
+	let mut remaining = moves.iter().copied().enumerate();
+	for (index, mv) in &mut remaining {
+		if let Some(move_score) = searches[0].root_move(&mut first_board, mv, depth, -INFINITY) {
+			scored.push((index, move_score));
+			break;
+		} else if searches[0].aborted {
+			return (vec![], -INFINITY);
+		}
+	}
+
+	let remaining: Vec<(usize, G::Move)> = remaining.collect();
+	let best_score = AtomicI32::new(scored.first().map_or(-INFINITY, |(_, first)| first.score));
+	let next = AtomicUsize::new(0);
+
+	thread::scope(|scope| {
+		let (remaining, best_score, next) = (&remaining, &best_score, &next);
+		let threads: Vec<_> = searches
+			.iter_mut()
+			.map(|search| {
+				scope.spawn(move || {
+					let mut board = board.clone();
+					let mut scored = vec![];
+
+					// Each thread takes the next move nobody has searched yet
+					while let Some(&(index, mv)) =
+						remaining.get(next.fetch_add(1, Ordering::Relaxed))
+					{
+						let alpha = root_alpha(best_score.load(Ordering::Relaxed), exact);
+						match search.root_move(&mut board, mv, depth, alpha) {
+							Some(move_score) => {
+								best_score.fetch_max(move_score.score, Ordering::Relaxed);
+								scored.push((index, move_score));
+							}
+							None if search.aborted => break,
+							None => {}
+						}
+					}
+
+					scored
+				})
+			})
+			.collect();
+
+		for thread in threads {
+			scored.extend(thread.join().expect("A search thread panicked"));
+		}
+	});
+
+	scored.sort_by_key(|&(index, _)| index);
+	let scored = scored
+		.into_iter()
+		.map(|(_, move_score)| move_score)
+		.collect();
+	(scored, best_score.into_inner())
+}
+
+/// The alpha a root move is searched with once the best move so far scores
+/// `best_score`, one below it so moves that tie with it stay exact
+fn root_alpha(best_score: i32, exact: bool) -> i32 {
+	if exact || best_score == -INFINITY {
+		-INFINITY
+	} else {
+		best_score - 1
+	}
+}
+
+/// Follows the best moves stored in the tables of `searches` from `first`, up
+/// to `depth` moves long, giving the line both sides are expected to play
+///
+/// The line ends early where no table holds the position, and where more than
+/// one does the deepest search of it is followed
+fn principal_variation<G: Game>(
+	searches: &[Search<G::Player, G::Move>],
+	board: &G,
+	first: G::Move,
+	depth: usize,
+) -> Vec<G::Move> {
+	let mut board = board.clone();
+	let mut line = vec![first];
+	board.apply(first);
+
+	while line.len() < depth && !board.is_terminal() {
+		let (hash, is_mirrored) = zobrist::canonical(board.hash(), board.mirrored_hash());
+		let stored = searches
+			.iter()
+			.filter_map(|search| search.table.probe(hash))
+			.filter(|entry| entry.best_move.is_some())
+			.max_by_key(|entry| entry.depth)
+			.and_then(|entry| entry.best_move);
+		let mv = match stored {
+			Some(mv) if is_mirrored => board.mirror_move(mv),
+			Some(mv) => mv,
+			None => break,
+		};
+
+		if !board.legal_moves().contains(&mv) || !board.apply(mv) {
+			break;
+		}
+		line.push(mv);
+	}
+
+	line
+}
+
+/// Stores wins relative to the position rather than the root, so the same
+/// position reached at a different ply still reports the right distance
+fn score_to_table(score: i32, ply: usize) -> i32 {
+	if score > WIN_THRESHOLD {
+		score + ply as i32
+	} else if score < -WIN_THRESHOLD {
+		score - ply as i32
+	} else {
+		score
+	}
+}
+
+/// Converts a stored win back to be relative to the root
+fn score_from_table(score: i32, ply: usize) -> i32 {
+	if score > WIN_THRESHOLD {
+		score - ply as i32
+	} else if score < -WIN_THRESHOLD {
+		score + ply as i32
+	} else {
+		score
+	}
+}
+
+/// The current time in milliseconds, `std::time` isn't available in the browser
+#[cfg(target_arch = "wasm32")]
+pub(crate) fn now_millis() -> f64 {
+	js_sys::Date::now()
+}
+
+/// The current time in milliseconds
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) fn now_millis() -> f64 {
+	use std::time::{SystemTime, UNIX_EPOCH};
+
+	SystemTime::now()
+		.duration_since(UNIX_EPOCH)
+		.map(|time| time.as_secs_f64() * 1000.0)
+		.unwrap_or(0.0)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::connect4::{connect4::Connect4, connect4_move::Connect4Move::Drop};
+	use crate::engine::config::TieBreak;
+	use std::collections::HashSet;
+
+	#[test]
+	fn tiny_node_budget_still_plays_a_legal_move() {
+		let board = Connect4::new();
+		let limits = SearchLimits {
+			depth: 6,
+			nodes: Some(3),
+			millis: None,
+		};
+
+		for &threads in [1, 2].iter() {
+			let config = EngineConfig {
+				threads,
+				..EngineConfig::DEFAULT
+			};
+			let report = search(&board, limits, config);
+
+			assert_eq!(report.depth, 1);
+			assert_eq!(report.moves.len(), board.legal_moves().len());
+			assert!(board.legal_moves().contains(&report.best_move));
+		}
+	}
+
+	#[test]
+	fn seeded_searches_pick_the_same_move() {
+		let board = Connect4::new();
+		let limits = SearchLimits {
+			depth: 2,
+			nodes: None,
+			millis: None,
+		};
+
+		// Hot enough that every seed could pick almost any move
+		let mut picked = HashSet::new();
+		for seed in 0..8 {
+			let config = EngineConfig {
+				seed: Some(seed),
+				temperature: 1000.0,
+				..EngineConfig::DEFAULT
+			};
+			let best_move = search(&board, limits, config).best_move;
+			assert_eq!(search(&board, limits, config).best_move, best_move);
+			picked.insert(board.column(best_move));
+		}
+		assert!(picked.len() > 1);
+	}
+
+	#[test]
+	fn zero_temperature_plays_the_best_move() {
+		let mut board = Connect4::new();
+		for &col in &[3, 3, 2] {
+			board.drop(col).unwrap();
+		}
+		let limits = SearchLimits {
+			depth: 4,
+			nodes: None,
+			millis: None,
+		};
+
+		for seed in 0..8 {
+			let config = EngineConfig {
+				seed: Some(seed),
+				..EngineConfig::DEFAULT
+			};
+			let report = search(&board, limits, config);
+			let best = report.moves.iter().map(|mv| mv.score).max().unwrap();
+			assert_eq!(report.score, best);
+			assert!(report
+				.moves
+				.iter()
+				.any(|mv| mv.mv == report.best_move && mv.score == best));
+		}
+	}
+
+	#[test]
+	fn tie_breaks_pick_the_same_move_every_time() {
+		let board = Connect4::new();
+		let moves = [Drop(1), Drop(5), Drop(2)];
+		let config = |tie_break| EngineConfig {
+			seed: Some(7),
+			tie_break,
+			..EngineConfig::DEFAULT
+		};
+
+		let leftmost = config(TieBreak::Leftmost);
+		let center_first = config(TieBreak::CenterFirst);
+		let random = config(TieBreak::Random);
+		let mut rng = leftmost.rng(board.hash);
+		assert_eq!(leftmost.break_tie(&board, &moves, &mut rng), Drop(1));
+		assert_eq!(center_first.break_tie(&board, &moves, &mut rng), Drop(2));
+
+		let pick = || random.break_tie(&board, &moves, &mut random.rng(board.hash));
+		assert_eq!(pick(), pick());
+	}
+}
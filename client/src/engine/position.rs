@@ -0,0 +1,158 @@
+/// Reads the grid part of a position string into rows of cells, top row first
+///
+/// Rows are separated by `/` and list each cell from left to right, with a
+/// piece as its letter and a run of empty cells as their count, so `2R4` is
+/// two empty cells, a red piece and four more empty cells. Pieces have to rest
+/// on the bottom of the board or on another piece
+///
+/// The board has to be `num_rows` by `num_cols`, see `grid_size` for reading
+/// the size from the grid itself
+pub fn parse_grid<P: Copy>(
+	grid: &str,
+	num_rows: usize,
+	num_cols: usize,
+	piece: impl Fn(char) -> Option<P>,
+) -> Result<Vec<Vec<Option<P>>>, String> {
+	let rows: Vec<&str> = grid.split('/').collect();
+	if rows.len() != num_rows {
+		return Err(format!(
+			"The board has {} rows, not {}",
+			num_rows,
+			rows.len()
+		));
+	}
+
+	let too_many_cells = |row: usize| format!("Row {} has more than {} cells", row + 1, num_cols);
+
+	let mut cells = Vec::with_capacity(num_rows);
+	for (row, text) in rows.iter().enumerate() {
+		// The row is only as long as the text says, which may be nowhere near
+		// `num_cols` when the size was read from somewhere else
+		let mut cells_in_row = Vec::new();
+		let mut empty: usize = 0;
+		for c in text.chars() {
+			match (c.to_digit(10), piece(c)) {
+				(Some(digit), _) if empty > 0 || digit > 0 => {
+					// A run is turned down as soon as it can't fit in the row, so
+					// a long one never gets as far as the row being filled in
+					empty = empty
+						.checked_mul(10)
+						.and_then(|empty| empty.checked_add(digit as usize))
+						.filter(|&empty| empty <= num_cols.saturating_sub(cells_in_row.len()))
+						.ok_or_else(|| too_many_cells(row))?;
+					continue;
+				}
+				(_, Some(_)) if empty >= num_cols.saturating_sub(cells_in_row.len()) => {
+					return Err(too_many_cells(row));
+				}
+				(_, Some(p)) => {
+					cells_in_row.extend((0..empty).map(|_| None));
+					cells_in_row.push(Some(p));
+				}
+				_ => return Err(format!("Row {} has a '{}' in it", row + 1, c)),
+			}
+			empty = 0;
+		}
+		cells_in_row.extend((0..empty).map(|_| None));
+
+		if cells_in_row.len() != num_cols {
+			return Err(format!(
+				"Row {} has {} cells, not {}",
+				row + 1,
+				cells_in_row.len(),
+				num_cols
+			));
+		}
+		cells.push(cells_in_row);
+	}
+
+	// Every piece sits on top of another piece, or on the bottom row
+	for (row, (cells_in_row, cells_below)) in cells.iter().zip(&cells[1..]).enumerate() {
+		for (col, (cell, below)) in cells_in_row.iter().zip(cells_below).enumerate() {
+			if cell.is_some() && below.is_none() {
+				return Err(format!(
+					"The piece in row {} column {} is floating",
+					row + 1,
+					col
+				));
+			}
+		}
+	}
+
+	Ok(cells)
+}
+
+/// Gets the number of rows and columns in the grid part of a position string,
+/// counting the cells of its first row
+pub fn grid_size(grid: &str) -> (usize, usize) {
+	let first_row = grid.split('/').next().unwrap_or_default();
+	let mut num_cols: usize = 0;
+	let mut empty: usize = 0;
+	for c in first_row.chars() {
+		match c.to_digit(10) {
+			Some(digit) => empty = empty.saturating_mul(10).saturating_add(digit as usize),
+			None => {
+				num_cols = num_cols.saturating_add(empty).saturating_add(1);
+				empty = 0;
+			}
+		}
+	}
+
+	// A size too big to count is still far too big for any board, so the rules
+	// turn it down rather than it wrapping around to something that fits
+	(grid.split('/').count(), num_cols.saturating_add(empty))
+}
+
+/// Writes rows of cells, top row first, as the grid part of a position string
+pub fn format_grid<'a, P: 'a>(
+	rows: impl Iterator<Item = &'a [Option<P>]>,
+	piece: impl Fn(&P) -> char,
+) -> String {
+	let rows: Vec<String> = rows
+		.map(|row| {
+			let mut text = String::new();
+			let mut empty = 0;
+			for cell in row {
+				match cell {
+					None => empty += 1,
+					Some(p) => {
+						if empty > 0 {
+							text.push_str(&empty.to_string());
+							empty = 0;
+						}
+						text.push(piece(p));
+					}
+				}
+			}
+			if empty > 0 {
+				text.push_str(&empty.to_string());
+			}
+			text
+		})
+		.collect();
+
+	rows.join("/")
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn piece(c: char) -> Option<char> {
+		match c {
+			'X' => Some(c),
+			_ => None,
+		}
+	}
+
+	#[test]
+	fn oversized_runs_are_turned_down() {
+		let huge = "99999999999999999999999999";
+		assert!(parse_grid(huge, 1, 7, piece).is_err());
+		assert!(parse_grid(&format!("{}X", huge), 1, 7, piece).is_err());
+		assert!(parse_grid("7X", 1, 7, piece).is_err());
+		assert!(parse_grid("6X", 1, 7, piece).is_ok());
+
+		assert_eq!(grid_size(&format!("{}/7", huge)), (2, usize::MAX));
+	}
+}
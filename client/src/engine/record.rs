@@ -0,0 +1,353 @@
+use super::game::Game;
+
+/// A game whose moves can be written down in a game record
+pub trait Notation: Game {
+	/// The name of the game in the `Game` header
+	const NAME: &'static str;
+
+	/// The names of the two sides, the side that moves first first
+	const SIDES: [&'static str; 2];
+
+	/// Starts a game with an empty board
+	fn new_game() -> Self;
+
+	/// Describes the rules the game is played with for the `Variant` header,
+	/// `None` when they are the usual ones
+	fn variant(&self) -> Option<String> {
+		None
+	}
+
+	/// Starts a game with an empty board and the rules `variant` describes
+	fn new_variant(variant: &str) -> Result<Self, String> {
+		Err(format!("{} has no variant {}", Self::NAME, variant))
+	}
+
+	/// Gets the position the game started from, `None` when it started from an
+	/// empty board
+	fn start_position(&self) -> Option<String>;
+
+	/// Sets up a board from a position written by `start_position`
+	fn from_start_position(position: &str) -> Result<Self, String>;
+
+	/// Gets every move played since the game started, in order
+	fn moves(&self) -> Vec<Self::Move>;
+
+	/// Writes a single move down, for example `3` or `T3`
+	fn format_move(mv: Self::Move) -> String;
+
+	/// Reads a move written by `format_move`, `None` when it isn't one
+	fn parse_move(text: &str) -> Option<Self::Move>;
+}
+
+/// A game written down with who played it and how it ended
+///
+/// As text a record is a header per line, `[Name "Value"]`, followed by a
+/// blank line and every move separated by whitespace:
+///
+/// ```text
+/// [Game "Connect 4"]
+/// [Red "Human"]
+/// [Yellow "CPU"]
+/// [Difficulty "Hard"]
+/// [Date "2026-10-18"]
+/// [Result "Red"]
+///
+/// 3 3 4 4 5 5 6
+/// ```
+///
+/// The result is the name of the winning side, `Draw`, or `*` when the game
+/// isn't over. Games played with other rules than the usual ones have a
+/// `Variant` header after the `Game` header, and games that didn't start from
+/// an empty board have a `Position` header next with the position they
+/// started from, like `[Position "7/7/7/7/7/3R3 y"]`. Quotes and backslashes
+/// in a header value are escaped with a backslash
+#[derive(Clone, Debug, PartialEq)]
+pub struct Record<M> {
+	/// The rules the game was played with, `None` for the usual ones
+	pub variant: Option<String>,
+	/// The position the game started from, `None` for an empty board
+	pub start: Option<String>,
+	/// Who played each side, the side that moves first first
+	pub players: [String; 2],
+	/// The CPU level played against
+	pub difficulty: String,
+	/// When the game was played, as `YYYY-MM-DD`
+	pub date: String,
+	pub result: String,
+	pub moves: Vec<M>,
+}
+
+/// The value of a header that was left out of a record
+const UNKNOWN: &str = "?";
+
+/// The result of a game that isn't over yet
+const UNFINISHED: &str = "*";
+
+/// The result of a drawn game
+const DRAW: &str = "Draw";
+
+impl<M> Record<M> {
+	/// Records the game played on `board` so far
+	pub fn new<G: Notation<Move = M>>(
+		board: &G,
+		players: [String; 2],
+		difficulty: String,
+		date: String,
+	) -> Self {
+		Record {
+			variant: board.variant(),
+			start: board.start_position(),
+			players,
+			difficulty,
+			date,
+			result: result(board),
+			moves: board.moves(),
+		}
+	}
+}
+
+/// Writes a record out as text
+pub fn format<G: Notation>(record: &Record<G::Move>) -> String {
+	let mut headers = vec![("Game", G::NAME)];
+	if let Some(variant) = &record.variant {
+		headers.push(("Variant", variant.as_str()));
+	}
+	if let Some(start) = &record.start {
+		headers.push(("Position", start.as_str()));
+	}
+	headers.extend_from_slice(&[
+		(G::SIDES[0], record.players[0].as_str()),
+		(G::SIDES[1], record.players[1].as_str()),
+		("Difficulty", record.difficulty.as_str()),
+		("Date", record.date.as_str()),
+		("Result", record.result.as_str()),
+	]);
+
+	let mut text = String::new();
+	for (name, value) in headers.iter() {
+		text.push_str(&format!("[{} \"{}\"]\n", name, escape(value)));
+	}
+
+	let moves: Vec<String> = record.moves.iter().map(|&mv| G::format_move(mv)).collect();
+	text.push('\n');
+	text.push_str(&moves.join(" "));
+	text.push('\n');
+
+	text
+}
+
+/// Reads a record written by `format`, playing every move to check that it is
+/// legal, and returns it along with the board at the end of the game
+///
+/// Headers other than `Game` can be left out, but a `Result` that doesn't match
+/// how the moves play out is an error
+pub fn parse<G: Notation>(text: &str) -> Result<(Record<G::Move>, G), String> {
+	match header(text, "Game") {
+		Some(name) if name == G::NAME => {}
+		_ => return Err(format!("Not a {} game record", G::NAME)),
+	}
+
+	let value = |name: &str| header(text, name).unwrap_or_else(|| String::from(UNKNOWN));
+
+	// Everything that isn't a header is part of the move list
+	let move_list = text
+		.lines()
+		.map(str::trim)
+		.filter(|line| !line.starts_with('['))
+		.flat_map(str::split_whitespace);
+
+	// The starting position has the rules in it too
+	let mut board = match (header(text, "Position"), header(text, "Variant")) {
+		(Some(start), _) => G::from_start_position(&start)?,
+		(None, Some(variant)) => G::new_variant(&variant)?,
+		(None, None) => G::new_game(),
+	};
+	for (i, word) in move_list.enumerate() {
+		let mv = match G::parse_move(word) {
+			Some(mv) => mv,
+			None => return Err(format!("Move {} ({}) isn't a move", i + 1, word)),
+		};
+
+		if board.is_terminal() {
+			return Err(format!(
+				"Move {} ({}) comes after the game is over",
+				i + 1,
+				word
+			));
+		} else if !board.apply(mv) {
+			return Err(format!("Move {} ({}) can't be played", i + 1, word));
+		}
+	}
+
+	let record = Record::new(
+		&board,
+		[value(G::SIDES[0]), value(G::SIDES[1])],
+		value("Difficulty"),
+		value("Date"),
+	);
+
+	match header(text, "Result") {
+		Some(result) if result != record.result => Err(format!(
+			"The record says the result is {} but the moves end in {}",
+			result, record.result
+		)),
+		_ => Ok((record, board)),
+	}
+}
+
+/// Gets the value of a header in a record, `None` if the record doesn't have it
+pub fn header(text: &str, name: &str) -> Option<String> {
+	text.lines().find_map(|line| {
+		let line = line.trim().strip_prefix('[')?.strip_suffix(']')?;
+		let (key, value) = line.split_at(line.find(' ')?);
+		let value = value.trim().strip_prefix('"')?.strip_suffix('"')?;
+
+		match key == name {
+			true => Some(unescape(value)),
+			false => None,
+		}
+	})
+}
+
+/// Puts a backslash before every quote and backslash in a header value
+fn escape(value: &str) -> String {
+	value.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+/// Takes out the backslashes `escape` put in
+fn unescape(value: &str) -> String {
+	let mut unescaped = String::with_capacity(value.len());
+	let mut chars = value.chars();
+	while let Some(c) = chars.next() {
+		match c {
+			'\\' => unescaped.extend(chars.next()),
+			c => unescaped.push(c),
+		}
+	}
+
+	unescaped
+}
+
+/// Gets the result of the game on `board` as it is written in a record
+fn result<G: Notation>(board: &G) -> String {
+	if !board.is_terminal() {
+		return String::from(UNFINISHED);
+	}
+
+	match board.winner() {
+		Some(player) if player == G::new_game().active_player() => G::SIDES[0].to_string(),
+		Some(_) => G::SIDES[1].to_string(),
+		None => String::from(DRAW),
+	}
+}
+
+/// Gets today's date as `YYYY-MM-DD`, in UTC
+pub fn today() -> String {
+	use std::time::{SystemTime, UNIX_EPOCH};
+
+	let seconds = SystemTime::now()
+		.duration_since(UNIX_EPOCH)
+		.map(|time| time.as_secs())
+		.unwrap_or(0);
+
+	// Converts days since 1970-01-01 to a calendar date, counting years from
+	// March so the leap day falls at the end of the year
+	let days = (seconds / 86_400) as i64 + 719_468;
+	let era = days.div_euclid(146_097);
+	let day_of_era = days.rem_euclid(146_097);
+	let year_of_era =
+		(day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+	let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+	let shifted_month = (5 * day_of_year + 2) / 153;
+	let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+	let month = if shifted_month < 10 {
+		shifted_month + 3
+	} else {
+		shifted_month - 9
+	};
+	let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+
+	format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::connect4::{
+		connect4::Connect4,
+		connect4_move::{Connect4Move, Connect4Move::*},
+	};
+
+	/// Writes the game on `board` out and reads it back in
+	fn round_trip(board: &Connect4, players: [String; 2]) -> (Record<Connect4Move>, Connect4) {
+		let record = Record::new(
+			board,
+			players,
+			String::from("Hard"),
+			String::from("2026-10-18"),
+		);
+		let text = format::<Connect4>(&record);
+		let (parsed, parsed_board) = parse::<Connect4>(&text).unwrap();
+
+		assert_eq!(parsed, record);
+		assert_eq!(parsed_board.position(), board.position());
+		(parsed, parsed_board)
+	}
+
+	fn players() -> [String; 2] {
+		[String::from("Human"), String::from("CPU")]
+	}
+
+	#[test]
+	fn games_round_trip() {
+		let mut board = Connect4::new();
+		for &col in [3, 3, 4, 4, 5, 5, 6].iter() {
+			board.drop(col).unwrap();
+		}
+
+		let (record, _) = round_trip(&board, players());
+		assert_eq!(record.result, "Red");
+		assert_eq!(record.variant, None);
+		assert_eq!(record.start, None);
+	}
+
+	#[test]
+	fn variant_and_position_headers_round_trip() {
+		let mut board = Connect4::from_position("9/9/9/9/9/9/9 r 5").unwrap();
+		board.drop(4).unwrap();
+		let (record, _) = round_trip(&board, players());
+		assert_eq!(record.variant.as_deref(), Some("9x7 connect 5"));
+		assert_eq!(record.start, None);
+
+		let mut board = Connect4::from_position("7/7/7/7/7/3R3 y popout").unwrap();
+		board.drop(2).unwrap();
+		board.pop(3).unwrap();
+		let (record, parsed) = round_trip(&board, players());
+		assert_eq!(record.start.as_deref(), Some("7/7/7/7/7/3R3 y popout"));
+		assert_eq!(record.moves, vec![Drop(2), Pop(3)]);
+		assert_eq!(record.result, "*");
+
+		// The moves from the position can be undone, but not the ones setting it up
+		let mut parsed = parsed;
+		while parsed.undo() {}
+		assert_eq!(parsed.position(), "7/7/7/7/7/3R3 y popout");
+	}
+
+	#[test]
+	fn header_values_are_escaped() {
+		let name = String::from("\"Quotes\" and \\ backslashes \\\"");
+		assert_eq!(unescape(&escape(&name)), name);
+		assert_eq!(escape("a\"b\\c"), "a\\\"b\\\\c");
+
+		let (record, _) = round_trip(&Connect4::new(), [name.clone(), String::from("CPU")]);
+		assert_eq!(record.players[0], name);
+	}
+
+	#[test]
+	fn wrong_results_are_turned_down() {
+		let text = "[Game \"Connect 4\"]\n[Result \"Yellow\"]\n\n3 3 4 4 5 5 6\n";
+
+		assert!(parse::<Connect4>(text).is_err());
+		assert!(parse::<Connect4>(&text.replace("Yellow", "Red")).is_ok());
+	}
+}
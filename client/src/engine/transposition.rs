@@ -0,0 +1,63 @@
+/// How a stored score relates to the true score of the position
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Bound {
+	/// The score is the true score
+	Exact,
+	/// The search failed high, the true score is at least this
+	Lower,
+	/// The search failed low, the true score is at most this
+	Upper,
+}
+
+/// The result of searching a single position
+#[derive(Copy, Clone, Debug)]
+pub struct Entry<M> {
+	pub hash: u64,
+	pub depth: usize,
+	pub score: i32,
+	pub bound: Bound,
+	pub best_move: Option<M>,
+}
+
+/// A fixed size cache of searched positions, keyed by their hash
+///
+/// Each hash maps to a single slot, a new result replaces the old one unless
+/// the old one is for the same position and was searched deeper
+pub struct TranspositionTable<M> {
+	entries: Vec<Option<Entry<M>>>,
+}
+
+impl<M: Copy> TranspositionTable<M> {
+	/// Creates an empty table with `2^size_bits` slots
+	pub fn new(size_bits: usize) -> Self {
+		TranspositionTable {
+			entries: vec![None; 1 << size_bits],
+		}
+	}
+
+	/// Looks up the stored result for a position
+	pub fn probe(&self, hash: u64) -> Option<Entry<M>> {
+		match self.entries[self.slot(hash)] {
+			Some(entry) if entry.hash == hash => Some(entry),
+			_ => None,
+		}
+	}
+
+	/// Stores the result of searching a position
+	pub fn store(&mut self, entry: Entry<M>) {
+		let slot = self.slot(entry.hash);
+
+		if let Some(old) = self.entries[slot] {
+			if old.hash == entry.hash && old.depth > entry.depth {
+				return;
+			}
+		}
+
+		self.entries[slot] = Some(entry);
+	}
+
+	/// Gets the slot a hash is stored in
+	fn slot(&self, hash: u64) -> usize {
+		(hash as usize) & (self.entries.len() - 1)
+	}
+}
@@ -0,0 +1,43 @@
+/// Reads heuristic weights written by `format_weights` into `values`, which
+/// lines up with `names`
+///
+/// Each weight is on its own line as `name = value`. Blank lines and lines
+/// starting with `#` are skipped, and weights that are left out keep the value
+/// they had
+pub fn parse_weights(text: &str, names: &[&str], values: &mut [i32]) -> Result<(), String> {
+	for (number, line) in text.lines().enumerate() {
+		let line = line.trim();
+		if line.is_empty() || line.starts_with('#') {
+			continue;
+		}
+
+		let (name, value) = line
+			.split_once('=')
+			.ok_or_else(|| format!("Line {} isn't a weight, use name = value", number + 1))?;
+		let index = names
+			.iter()
+			.position(|&known| known == name.trim())
+			.ok_or_else(|| {
+				format!(
+					"'{}' isn't a weight, use one of {}",
+					name.trim(),
+					names.join(", ")
+				)
+			})?;
+		values[index] = value
+			.trim()
+			.parse()
+			.map_err(|_| format!("'{}' isn't a whole number", value.trim()))?;
+	}
+
+	Ok(())
+}
+
+/// Writes heuristic weights one to a line as `name = value`
+pub fn format_weights(names: &[&str], values: &[i32]) -> String {
+	names
+		.iter()
+		.zip(values)
+		.map(|(name, value)| format!("{} = {}\n", name, value))
+		.collect()
+}
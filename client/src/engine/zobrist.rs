@@ -0,0 +1,22 @@
+/// Gets the `index`th key of a fixed pseudo random sequence
+///
+/// The keys are generated with SplitMix64 at compile time, so every build and
+/// every run of the game hashes the same position to the same value
+pub const fn key(index: u64) -> u64 {
+	let mut z = (index + 1).wrapping_mul(0x9E37_79B9_7F4A_7C15);
+	z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+	z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+	z ^ (z >> 31)
+}
+
+/// Picks which of a position's two hashes is used to look it up
+///
+/// A position and its mirror image share the smaller of their two hashes,
+/// the flag says whether the mirror image's hash was the one picked
+pub fn canonical(hash: u64, mirrored_hash: u64) -> (u64, bool) {
+	if mirrored_hash < hash {
+		(mirrored_hash, true)
+	} else {
+		(hash, false)
+	}
+}
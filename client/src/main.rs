@@ -1,6 +1,7 @@
 #![recursion_limit = "1024"]
 
 mod app;
+mod cpu_worker;
 mod switch;
 
 mod components {
@@ -11,21 +12,44 @@ mod components {
     pub mod toot_and_otto_board;
 }
 
+mod engine {
+    pub mod config;
+    pub mod game;
+    pub mod mcts;
+    pub mod minimax;
+    pub mod position;
+    pub mod transposition;
+    pub mod weights;
+    pub mod zobrist;
+}
+
 mod connect4 {
     pub mod connect4;
+    pub mod connect4_move;
     pub mod cpu_con4;
     pub mod piece_color;
+    pub mod position;
+    pub mod rules;
+    pub mod solver;
+    pub mod threats;
+    pub mod weights;
 }
 
 mod toot_and_otto {
     pub mod cpu_toot;
     pub mod piece_letter;
     pub mod player;
+    pub mod position;
+    pub mod rules;
+    pub mod solver;
     pub mod toot_and_otto;
+    pub mod weights;
 }
 
 mod types {
+    pub mod move_error;
     pub mod opponent;
+    pub mod spectator;
 }
 
 use wasm_logger;
@@ -97,3 +97,18 @@ impl Transformer<AppRoute, PublicUrlSwitch> for VComp {
 // Type aliases to make life just a bit easier
 pub type AppRouter = Router<PublicUrlSwitch>;
 pub type RouterButton = yew_router::components::RouterButton<AppRoute>;
+
+/// Gets the position shared in the page's URL, the part after the `#`
+pub fn shared_position() -> Option<String> {
+	let hash = web_sys::window()?.location().hash().ok()?;
+	let encoded = hash
+		.strip_prefix('#')
+		.filter(|encoded| !encoded.is_empty())?;
+
+	js_sys::decode_uri_component(encoded).ok()?.as_string()
+}
+
+/// Gets a link to the current page that opens with `position` on the board
+pub fn position_link(position: &str) -> String {
+	format!("#{}", String::from(js_sys::encode_uri_component(position)))
+}
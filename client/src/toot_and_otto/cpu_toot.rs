@@ -1,71 +1,139 @@
 use super::{
-	piece_letter::{PieceLetter, PieceLetter::*},
-	player::Player::*,
+	piece_letter::PieceLetter, player::Player, rules::TootAndOttoRules, solver::Solver,
 	toot_and_otto::TootAndOtto,
 };
-use rand::seq::SliceRandom;
-use strum::IntoEnumIterator;
+use crate::{
+	engine::{
+		config::EngineConfig,
+		mcts::{self, MctsLimits, MoveStats},
+		minimax::{self, SearchLimits, SearchReport},
+	},
+	types::opponent::Opponent,
+};
+use std::cell::RefCell;
+
+/// How many positions the perfect CPU solves before giving up on a move, a
+/// few times what the slowest positions past the solution table take
+const SOLVE_NODES: u64 = 50_000_000;
 
-pub fn make_move(board: TootAndOtto, depth: usize) -> (usize, PieceLetter) {
-	let (col, letter, _) = minmax(board, depth, true);
-	return (col, letter);
+thread_local! {
+	/// The perfect CPU's solver, kept between moves so it remembers the
+	/// positions it has already solved
+	static SOLVER: RefCell<Solver> = RefCell::new(Solver::new());
 }
 
-fn minmax(board: TootAndOtto, depth: usize, is_cpu_turn: bool) -> (usize, PieceLetter, i32) {
-	if board.is_terminal {
-		return match board.winner {
-			None => (3, O, 0), // Draw
-			Some(player) => match player {
-				OTTO => (3, O, i32::MAX - 25 + depth as i32), // Computer won, good
-				TOOT => (3, O, i32::MIN + 25 - depth as i32), // Human won, bad
-			},
-		};
-	} else if depth == 0 {
-		return (3, O, board.calculate_score(OTTO));
+/// Whether `engine` can play a game with `rules`, the perfect CPU only plays
+/// the board and letters the solver can solve
+pub fn can_play(engine: Opponent, rules: TootAndOttoRules) -> bool {
+	engine != Opponent::PerfectCPU || rules == TootAndOttoRules::STANDARD
+}
+
+/// Picks the column and letter `engine` drops playing `side`, choosing between
+/// equally good moves as `config` says, `None` when `engine` is a human or
+/// can't play the board, or when it isn't `side`'s turn or the game is over
+pub fn pick_move(
+	board: TootAndOtto,
+	side: Player,
+	engine: Opponent,
+	config: EngineConfig,
+) -> Option<(usize, PieceLetter)> {
+	match (engine, engine.search_limits(), engine.mcts_limits()) {
+		(Opponent::Human, _, _) => None,
+		(_, Some(limits), _) => make_move(board, side, limits, config),
+		(_, _, Some(limits)) => make_mcts_move(board, side, limits, config),
+		(_, None, None) => make_perfect_move(board, side, config),
 	}
+}
 
-	if is_cpu_turn {
-		let mut best_options = vec![(0, T, i32::MIN)];
+/// Picks the column and letter the CPU playing `side` should drop, `None` when
+/// it isn't `side`'s turn or the game is over
+pub fn make_move(
+	board: TootAndOtto,
+	side: Player,
+	limits: SearchLimits,
+	config: EngineConfig,
+) -> Option<(usize, PieceLetter)> {
+	minimax_search(board, side, limits, config).map(|report| report.best_move)
+}
 
-		for letter in PieceLetter::iter() {
-			for col in board.get_columns().iter() {
-				let mut copy_board = board.clone();
+/// Searches the position for `side`, returning the column and letter the CPU picks along
+/// with its score, the line it expects and the score of every other move, or
+/// `None` when it isn't `side`'s turn or the game is over
+pub fn minimax_search(
+	board: TootAndOtto,
+	side: Player,
+	limits: SearchLimits,
+	config: EngineConfig,
+) -> Option<SearchReport<(usize, PieceLetter)>> {
+	if board.is_terminal || board.active_player != side {
+		return None;
+	}
 
-				if copy_board.drop(letter, *col) == false {
-					continue;
-				}
+	Some(minimax::search(&board, limits, config))
+}
 
-				let new_value = minmax(copy_board, depth - 1, false).2;
+/// Scores every column and letter for the player to move as deep as the hard CPU
+/// searches, the hints shown to the user, or `None` when the game is over
+#[allow(dead_code)] // Only the web boards show hints
+pub fn hints(board: TootAndOtto) -> Option<SearchReport<(usize, PieceLetter)>> {
+	if board.is_terminal {
+		return None;
+	}
 
-				if new_value == best_options[0].2 {
-					best_options.push((*col, letter, new_value));
-				} else if new_value > best_options[0].2 {
-					best_options = vec![(*col, letter, new_value)];
-				}
-			}
-		}
+	let limits = Opponent::HardCPU
+		.search_limits()
+		.expect("The hard CPU searches a limited depth");
+	Some(minimax::analyse(&board, limits))
+}
 
-		*best_options.choose(&mut rand::thread_rng()).unwrap()
-	} else {
-		let mut best_options = vec![(0, T, i32::MAX)];
+/// Picks the column and letter the Monte Carlo search playing `side` played
+/// out the most, `None` when it isn't `side`'s turn or the game is over
+pub fn make_mcts_move(
+	board: TootAndOtto,
+	side: Player,
+	limits: MctsLimits,
+	config: EngineConfig,
+) -> Option<(usize, PieceLetter)> {
+	mcts_search(board, side, limits, config).map(|moves| moves[0].mv)
+}
 
-		for letter in PieceLetter::iter() {
-			for col in board.get_columns().iter() {
-				let mut copy_board = board.clone();
-				if copy_board.drop(letter, *col) == false {
-					continue;
-				}
+/// Plays out games from the position for `side`, returning how often each
+/// column and letter was played out and how well it did, the most played out
+/// first, or `None` when it isn't `side`'s turn or the game is over
+pub fn mcts_search(
+	board: TootAndOtto,
+	side: Player,
+	limits: MctsLimits,
+	config: EngineConfig,
+) -> Option<Vec<MoveStats<(usize, PieceLetter)>>> {
+	if board.is_terminal || board.active_player != side {
+		return None;
+	}
 
-				let new_value = minmax(copy_board, depth - 1, true).2;
+	Some(mcts::search(&board, limits, config))
+}
 
-				if new_value == best_options[0].2 {
-					best_options.push((*col, letter, new_value));
-				} else if new_value < best_options[0].2 {
-					best_options = vec![(*col, letter, new_value)];
-				}
-			}
-		}
+/// Picks a column and letter with perfect play for `side`, winning as fast as
+/// possible when it can, `None` when it isn't `side`'s turn, the game is over
+/// or the rules aren't ones the solver can solve, see `can_play`
+///
+/// Should the solver take more than `SOLVE_NODES` positions, the CPU plays the
+/// hard CPU's move rather than keep the user waiting
+pub fn make_perfect_move(
+	board: TootAndOtto,
+	side: Player,
+	config: EngineConfig,
+) -> Option<(usize, PieceLetter)> {
+	if board.is_terminal
+		|| board.active_player != side
+		|| !can_play(Opponent::PerfectCPU, board.rules)
+	{
+		return None;
+	}
 
-		*best_options.choose(&mut rand::thread_rng()).unwrap()
+	let solved = SOLVER.with(|solver| solver.borrow_mut().try_best_moves(&board, SOLVE_NODES));
+	match solved {
+		Some(moves) => Some(config.break_tie(&board, &moves, &mut config.rng(board.hash))),
+		None => pick_move(board, side, Opponent::HardCPU, config),
 	}
 }
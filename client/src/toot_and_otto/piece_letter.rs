@@ -10,12 +10,22 @@ pub enum PieceLetter {
 	O,
 }
 
+impl PieceLetter {
+	/// Gets the position of the letter in per-letter arrays
+	pub fn index(&self) -> usize {
+		match self {
+			T => 0,
+			O => 1,
+		}
+	}
+}
+
 impl Display for PieceLetter {
 	/// Prints out the `PieceLetter`
 	fn fmt(&self, f: &mut Formatter) -> Result {
 		match self {
-			O => write!(f, "{}", "O"),
-			T => write!(f, "{}", "T"),
+			O => f.write_str("O"),
+			T => f.write_str("T"),
 		}
 	}
 }
@@ -16,14 +16,22 @@ impl Player {
 			OTTO => TOOT,
 		}
 	}
+
+	/// Gets the position of the player in per-player arrays
+	pub fn index(&self) -> usize {
+		match self {
+			TOOT => 0,
+			OTTO => 1,
+		}
+	}
 }
 
 impl Display for Player {
 	/// Prints out the `PieceLetter`
 	fn fmt(&self, f: &mut Formatter) -> Result {
 		match self {
-			TOOT => write!(f, "{}", "TOOT"),
-			OTTO => write!(f, "{}", "OTTO"),
+			TOOT => f.write_str("TOOT"),
+			OTTO => f.write_str("OTTO"),
 		}
 	}
 }
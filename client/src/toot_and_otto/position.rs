@@ -0,0 +1,278 @@
+use super::{
+	piece_letter::{PieceLetter, PieceLetter::*},
+	player::{Player, Player::*},
+	rules::{TootAndOttoRules, MAX_COLS, MAX_PIECES},
+	toot_and_otto::TootAndOtto,
+};
+use crate::engine::position::{format_grid, grid_size, parse_grid};
+use std::collections::HashSet;
+
+/// TOOT-n-OTTO positions written as a single line of text
+///
+/// A position is the board, top row first, the player to move, and how many of
+/// each letter the players have left, as TOOT's T's, TOOT's O's, OTTO's T's and
+/// OTTO's O's:
+///
+/// ```text
+/// 6/6/6/2TO2 t 5665
+/// ```
+///
+/// Pieces are `T` and `O`, and the player to move is `t` or `o`. The size of
+/// the board comes from the grid and the letters each player started with from
+/// the pieces left. Counts over 9 are separated by commas, like `11,12,12,12`,
+/// and when the players never run out of letters the counts are just `-`
+impl TootAndOtto {
+	/// Sets up the board from a position string, checking that the position can
+	/// be reached in a real game
+	///
+	/// The pieces are played in an order that leads to the position, so the
+	/// position's moves can be undone like any other
+	pub fn from_position(position: &str) -> Result<Self, String> {
+		let fields: Vec<&str> = position.split_whitespace().collect();
+		if fields.len() != 3 {
+			return Err(String::from(
+				"A position is the board, the player to move and the pieces left, like \"6/6/6/2T3 o 5666\"",
+			));
+		}
+
+		// The size is checked before the grid is read, since the letters each
+		// player starts with can only be worked out from the grid
+		let (num_rows, num_cols) = grid_size(fields[0]);
+		TootAndOttoRules::new(num_cols, num_rows, None)?;
+		let grid = parse_grid(fields[0], num_rows, num_cols, |c| match c {
+			'T' => Some(T),
+			'O' => Some(O),
+			_ => None,
+		})?;
+
+		let active_player = match fields[1] {
+			"t" => TOOT,
+			"o" => OTTO,
+			side => return Err(format!("'{}' isn't a player to move, use t or o", side)),
+		};
+
+		let not_a_count = |count: &str| format!("'{}' isn't a number of pieces left", count);
+		let counts: Vec<usize> = match fields[2] {
+			"-" => vec![],
+			counts if counts.contains(',') => counts
+				.split(',')
+				.map(|count| count.parse().map_err(|_| not_a_count(count)))
+				.collect::<Result<_, _>>()?,
+			counts => counts
+				.chars()
+				.map(|c| {
+					c.to_digit(10)
+						.map(|count| count as usize)
+						.ok_or_else(|| not_a_count(&c.to_string()))
+				})
+				.collect::<Result<_, _>>()?,
+		};
+		let is_unlimited = fields[2] == "-";
+		let is_valid_count = |&count: &usize| count <= MAX_PIECES;
+		if !is_unlimited
+			&& (counts.len() != 4
+				|| !counts.iter().all(is_valid_count)
+				|| (!fields[2].contains(',') && fields[2].len() != 4))
+		{
+			return Err(format!(
+				"'{}' isn't the pieces left, use four numbers like 5666 or 11,12,12,12, or - for unlimited letters",
+				fields[2]
+			));
+		}
+
+		// Both players start with the same number of each letter, so the pieces
+		// left and the letters on the board give that number
+		let on_board = |letter: PieceLetter| {
+			grid.iter()
+				.flatten()
+				.filter(|&&cell| cell == Some(letter))
+				.count()
+		};
+		let pieces_per_letter = match is_unlimited {
+			true => None,
+			false => {
+				let ts = on_board(T) + counts[0] + counts[2];
+				let os = on_board(O) + counts[1] + counts[3];
+				if ts != os || ts % 2 == 1 || counts.iter().any(|&count| 2 * count > ts) {
+					return Err(format!(
+						"There are {} T's and {} O's on the board, which doesn't match the pieces left of {}",
+						on_board(T),
+						on_board(O),
+						fields[2]
+					));
+				}
+				Some(ts / 2)
+			}
+		};
+		let rules = TootAndOttoRules::new(num_cols, num_rows, pieces_per_letter)?;
+
+		// Places the pieces without playing them to work out an order they could
+		// have been played in. With unlimited letters nobody can run out, so the
+		// counts just start from nothing
+		let mut setup = TootAndOtto::with_rules(rules);
+		for (row, cells) in grid.iter().enumerate() {
+			for (col, &cell) in cells.iter().enumerate() {
+				if let Some(letter) = cell {
+					setup.board[row][col] = Some(letter);
+					setup.column_heights[col] += 1;
+					setup.moves_played += 1;
+				}
+			}
+		}
+		setup.piece_counts = match is_unlimited {
+			true => [[0; 2]; 2],
+			false => [[counts[0], counts[1]], [counts[2], counts[3]]],
+		};
+
+		// TOOT moves first, so TOOT has always played as many pieces as OTTO or one more
+		let played = |player: Player| match is_unlimited {
+			true => (setup.moves_played + 1 - player.index()) / 2,
+			false => {
+				2 * rules.starting_pieces()
+					- setup.piece_counts[player.index()].iter().sum::<usize>()
+			}
+		};
+		let to_move = match played(TOOT).checked_sub(played(OTTO)) {
+			Some(0) => TOOT,
+			Some(1) => OTTO,
+			_ => {
+				return Err(format!(
+					"TOOT has played {} pieces and OTTO has played {}, TOOT must have played as many as OTTO or one more",
+					played(TOOT),
+					played(OTTO)
+				))
+			}
+		};
+		if to_move != active_player {
+			return Err(format!("It has to be {}'s turn", to_move));
+		}
+
+		let mut moves = Vec::with_capacity(setup.moves_played);
+		if !setup.find_move_order(&mut moves, &mut HashSet::new()) {
+			return Err(String::from("The position can't be reached in a game"));
+		}
+
+		let mut board = TootAndOtto::with_rules(rules);
+		for &(col, letter) in moves.iter() {
+			board.drop(letter, col).map_err(|err| err.to_string())?;
+		}
+		board.set_up_moves = board.moves_played;
+
+		Ok(board)
+	}
+
+	/// Writes the board, the player to move and the pieces left as a position string
+	pub fn position(&self) -> String {
+		let rows = self.board[..self.rules.num_rows]
+			.iter()
+			.map(|row| &row[..self.rules.num_cols]);
+		let grid = format_grid(rows, |letter| match letter {
+			T => 'T',
+			O => 'O',
+		});
+
+		let active_player = match self.active_player {
+			TOOT => 't',
+			OTTO => 'o',
+		};
+
+		let counts: Vec<String> = self
+			.piece_counts
+			.iter()
+			.flatten()
+			.map(|count| count.to_string())
+			.collect();
+		let counts = match self.rules.pieces_per_letter {
+			None => String::from("-"),
+			Some(pieces) if pieces < 10 => counts.concat(),
+			Some(_) => counts.join(","),
+		};
+
+		format!("{} {} {}", grid, active_player, counts)
+	}
+
+	/// Takes pieces back off the top of the columns, last move first, until the
+	/// board is empty, pushing the moves in the order they were played
+	///
+	/// Every piece taken back goes back to the player who made that move, who
+	/// must have played that letter, and has to leave a board without a word
+	/// since the game ends as soon as one is spelled. Positions that turn out to
+	/// be dead ends are kept in `dead_ends`
+	fn find_move_order(
+		&mut self,
+		moves: &mut Vec<(usize, PieceLetter)>,
+		dead_ends: &mut HashSet<([usize; MAX_COLS], [[usize; 2]; 2])>,
+	) -> bool {
+		if self.moves_played == 0 {
+			return true;
+		} else if dead_ends.contains(&(self.column_heights, self.piece_counts)) {
+			return false;
+		}
+
+		// The player who made the last move
+		let player = match self.moves_played % 2 {
+			1 => TOOT,
+			_ => OTTO,
+		};
+
+		for col in 0..self.rules.num_cols {
+			let col_height = self.column_heights[col];
+			if col_height == 0 {
+				continue;
+			}
+
+			let row = self.rules.num_rows - col_height;
+			let letter = match self.board[row][col] {
+				Some(letter) => letter,
+				None => continue,
+			};
+			if self.piece_counts[player.index()][letter.index()] == self.rules.starting_pieces() {
+				continue;
+			}
+
+			self.board[row][col] = None;
+			self.piece_counts[player.index()][letter.index()] += 1;
+			self.column_heights[col] -= 1;
+			self.moves_played -= 1;
+
+			if self.check_for_win(TOOT).is_none()
+				&& self.check_for_win(OTTO).is_none()
+				&& self.find_move_order(moves, dead_ends)
+			{
+				moves.push((col, letter));
+				return true;
+			}
+
+			self.board[row][col] = Some(letter);
+			self.piece_counts[player.index()][letter.index()] -= 1;
+			self.column_heights[col] += 1;
+			self.moves_played += 1;
+		}
+
+		dead_ends.insert((self.column_heights, self.piece_counts));
+		false
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn pieces_left_are_read_into_piece_counts() {
+		let board = TootAndOtto::from_position("6/6/6/2TO2 t 5665").unwrap();
+		assert_eq!(board.piece_counts, [[5, 6], [6, 5]]);
+		assert_eq!(board.position(), "6/6/6/2TO2 t 5665");
+
+		// The same board, but TOOT played the O and OTTO the T
+		let board = TootAndOtto::from_position("6/6/6/2TO2 t 6556").unwrap();
+		assert_eq!(board.piece_counts, [[6, 5], [5, 6]]);
+		assert_eq!(board.position(), "6/6/6/2TO2 t 6556");
+	}
+
+	#[test]
+	fn pieces_left_have_to_match_the_board() {
+		assert!(TootAndOtto::from_position("6/6/6/2TO2 t 6666").is_err());
+		assert!(TootAndOtto::from_position("6/6/6/2TO2 t 566").is_err());
+	}
+}
@@ -0,0 +1,75 @@
+use super::{
+	piece_letter::PieceLetter,
+	rules::{TootAndOttoRules, MAX_COLS},
+	toot_and_otto::TootAndOtto,
+};
+use crate::engine::record::{self, Notation, Record};
+
+/// TOOT and OTTO moves are written as the letter then the column, `T0` to `O5`
+/// on the standard board, and the variant is the board size and letters, like
+/// `8x6 with 12 of each letter`
+impl Notation for TootAndOtto {
+	const NAME: &'static str = "TOOT and OTTO";
+	const SIDES: [&'static str; 2] = ["TOOT", "OTTO"];
+
+	fn new_game() -> Self {
+		TootAndOtto::new()
+	}
+
+	fn variant(&self) -> Option<String> {
+		match self.rules {
+			TootAndOttoRules::STANDARD => None,
+			rules => Some(rules.to_string()),
+		}
+	}
+
+	fn new_variant(variant: &str) -> Result<Self, String> {
+		TootAndOttoRules::parse(variant).map(TootAndOtto::with_rules)
+	}
+
+	fn start_position(&self) -> Option<String> {
+		let mut start = *self;
+		while start.moves_played > start.set_up_moves && start.undo() {}
+
+		match start.position() == TootAndOtto::with_rules(self.rules).position() {
+			true => None,
+			false => Some(start.position()),
+		}
+	}
+
+	fn from_start_position(position: &str) -> Result<Self, String> {
+		TootAndOtto::from_position(position)
+	}
+
+	fn moves(&self) -> Vec<(usize, PieceLetter)> {
+		self.history[self.set_up_moves.min(self.moves_played)..self.moves_played].to_vec()
+	}
+
+	fn format_move((col, letter): (usize, PieceLetter)) -> String {
+		format!("{}{}", letter, col)
+	}
+
+	fn parse_move(text: &str) -> Option<(usize, PieceLetter)> {
+		let mut chars = text.chars();
+		let letter = match chars.next()?.to_ascii_uppercase() {
+			'T' => PieceLetter::T,
+			'O' => PieceLetter::O,
+			_ => return None,
+		};
+
+		match chars.as_str().parse() {
+			Ok(col) if col < MAX_COLS => Some((col, letter)),
+			_ => None,
+		}
+	}
+}
+
+/// Reads a TOOT and OTTO game record, see `Record` for the format
+pub fn parse(text: &str) -> Result<(Record<(usize, PieceLetter)>, TootAndOtto), String> {
+	record::parse(text)
+}
+
+/// Writes a TOOT and OTTO game record
+pub fn format(record: &Record<(usize, PieceLetter)>) -> String {
+	record::format::<TootAndOtto>(record)
+}
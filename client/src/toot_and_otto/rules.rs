@@ -0,0 +1,184 @@
+use std::fmt::{Display, Formatter, Result};
+
+/// The most columns a board can have
+pub const MAX_COLS: usize = 10;
+
+/// The most rows a board can have
+pub const MAX_ROWS: usize = 10;
+
+/// The most pieces of a letter a player can start with, enough to fill the largest board
+pub const MAX_PIECES: usize = MAX_COLS * MAX_ROWS;
+
+/// The size of a TOOT-n-OTTO board and how many letters the players get
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct TootAndOttoRules {
+	pub num_cols: usize,
+	pub num_rows: usize,
+	/// How many of each letter each player starts with, `None` when they never run out
+	pub pieces_per_letter: Option<usize>,
+}
+
+impl TootAndOttoRules {
+	/// The 6x4 board where each player has six T's and six O's
+	pub const STANDARD: Self = TootAndOttoRules {
+		num_cols: 6,
+		num_rows: 4,
+		pieces_per_letter: Some(6),
+	};
+
+	/// Checks that a board of `num_cols` by `num_rows` can be played on, and
+	/// that `pieces_per_letter` is enough for the players to fill it
+	pub fn new(
+		num_cols: usize,
+		num_rows: usize,
+		pieces_per_letter: Option<usize>,
+	) -> std::result::Result<Self, String> {
+		if !(4..=MAX_COLS).contains(&num_cols) {
+			return Err(format!("A board has from 4 to {} columns", MAX_COLS));
+		} else if !(4..=MAX_ROWS).contains(&num_rows) {
+			return Err(format!("A board has from 4 to {} rows", MAX_ROWS));
+		}
+
+		let rules = TootAndOttoRules {
+			num_cols,
+			num_rows,
+			pieces_per_letter,
+		};
+
+		// The player who moves first plays half the board, rounded up, and needs
+		// at least half of those of one letter
+		#[allow(clippy::manual_div_ceil)] // `div_ceil` needs a newer compiler
+		let fewest_pieces = (rules.num_cells() + 3) / 4;
+		match pieces_per_letter {
+			Some(pieces) if pieces < fewest_pieces || pieces > rules.num_cells() => Err(format!(
+				"Each player needs from {} to {} of each letter on a {}x{} board",
+				fewest_pieces,
+				rules.num_cells(),
+				num_cols,
+				num_rows
+			)),
+			_ => Ok(rules),
+		}
+	}
+
+	/// Reads rules written by `Display`, like `8x6 with 12 of each letter` or
+	/// `8x6 with unlimited letters`
+	#[allow(dead_code)] // Rules are only read from text by the CLI
+	pub fn parse(text: &str) -> std::result::Result<Self, String> {
+		let invalid = || {
+			format!(
+				"'{}' isn't a board size, use something like 8x6 with 12 of each letter",
+				text
+			)
+		};
+
+		let words: Vec<&str> = text.split_whitespace().collect();
+		let (size, pieces_per_letter) = match words.as_slice() {
+			[size, "with", "unlimited", "letters"] => (size, None),
+			[size, "with", pieces, "of", "each", "letter"] => {
+				(size, Some(pieces.parse().map_err(|_| invalid())?))
+			}
+			_ => return Err(invalid()),
+		};
+		let (num_cols, num_rows) = size.split_once('x').ok_or_else(invalid)?;
+
+		match (num_cols.parse(), num_rows.parse()) {
+			(Ok(num_cols), Ok(num_rows)) => {
+				TootAndOttoRules::new(num_cols, num_rows, pieces_per_letter)
+			}
+			_ => Err(invalid()),
+		}
+	}
+
+	/// The number of cells on the board, and so the longest a game can last
+	pub fn num_cells(&self) -> usize {
+		self.num_cols * self.num_rows
+	}
+
+	/// How many of each letter each player starts with, with no limit a player
+	/// has as many as there are cells and never runs out
+	pub fn starting_pieces(&self) -> usize {
+		self.pieces_per_letter.unwrap_or_else(|| self.num_cells())
+	}
+}
+
+impl Default for TootAndOttoRules {
+	fn default() -> Self {
+		TootAndOttoRules::STANDARD
+	}
+}
+
+impl Display for TootAndOttoRules {
+	/// Prints out the board size and letters, like `6x4 with 6 of each letter`
+	fn fmt(&self, f: &mut Formatter) -> Result {
+		match self.pieces_per_letter {
+			Some(pieces) => write!(
+				f,
+				"{}x{} with {} of each letter",
+				self.num_cols, self.num_rows, pieces
+			),
+			None => write!(
+				f,
+				"{}x{} with unlimited letters",
+				self.num_cols, self.num_rows
+			),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn rules_read_back_what_display_writes() {
+		let all_rules = [
+			TootAndOttoRules::STANDARD,
+			TootAndOttoRules::new(8, 6, Some(12)).unwrap(),
+			TootAndOttoRules::new(7, 5, None).unwrap(),
+			TootAndOttoRules::new(MAX_COLS, MAX_ROWS, Some(MAX_PIECES)).unwrap(),
+		];
+
+		for rules in all_rules.iter() {
+			assert_eq!(TootAndOttoRules::parse(&rules.to_string()), Ok(*rules));
+		}
+		assert_eq!(
+			TootAndOttoRules::STANDARD.to_string(),
+			"6x4 with 6 of each letter"
+		);
+	}
+
+	#[test]
+	fn malformed_rules_are_turned_down() {
+		let malformed = [
+			"",
+			"6x4",
+			"6 x 4 with 6 of each letter",
+			"6by4 with 6 of each letter",
+			"6x4 with six of each letter",
+			"6x4 with -1 of each letter",
+			"6x4 with 6 of each letter please",
+			"6x4 with limited letters",
+			"11x4 with 6 of each letter",
+			"6x3 with 6 of each letter",
+		];
+
+		for text in malformed.iter() {
+			assert!(TootAndOttoRules::parse(text).is_err(), "{}", text);
+		}
+	}
+
+	#[test]
+	fn players_get_enough_letters_to_fill_the_board() {
+		// 24 cells, the first player plays 12 of them
+		assert!(TootAndOttoRules::new(6, 4, Some(5)).is_err());
+		assert!(TootAndOttoRules::new(6, 4, Some(6)).is_ok());
+		assert!(TootAndOttoRules::new(6, 4, Some(24)).is_ok());
+		assert!(TootAndOttoRules::new(6, 4, Some(25)).is_err());
+
+		// 35 cells, the first player plays 18 of them
+		assert!(TootAndOttoRules::new(7, 5, Some(8)).is_err());
+		assert!(TootAndOttoRules::new(7, 5, Some(9)).is_ok());
+		assert!(TootAndOttoRules::new(7, 5, None).is_ok());
+	}
+}
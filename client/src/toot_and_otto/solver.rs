@@ -0,0 +1,766 @@
+use super::{piece_letter::PieceLetter, rules::TootAndOttoRules, toot_and_otto::TootAndOtto};
+use crate::engine::game::Outcome;
+use rand::seq::SliceRandom;
+use strum::IntoEnumIterator;
+
+/// The solver only plays the standard board, where everything fits in a `u64`
+const NUM_COLS: usize = TootAndOttoRules::STANDARD.num_cols;
+const NUM_ROWS: usize = TootAndOttoRules::STANDARD.num_rows;
+
+/// The number of cells on the board
+const NUM_CELLS: usize = NUM_COLS * NUM_ROWS;
+
+/// The number of bits each column takes up in a bitboard, one more than the
+/// number of rows so adding a piece to a full column can't spill into the next
+const COL_BITS: usize = NUM_ROWS + 1;
+
+/// No position scores higher than this, winning with the first move
+const MAX_SCORE: i32 = NUM_CELLS as i32;
+
+/// How many positions the solver remembers, a prime so positions spread evenly
+const TABLE_SIZE: usize = 4_194_301;
+
+/// A bitboard with the bottom cell of every column set
+const BOTTOM_MASK: u64 = {
+	let mut mask = 0;
+	let mut col = 0;
+	while col < NUM_COLS {
+		mask |= 1 << (col * COL_BITS);
+		col += 1;
+	}
+	mask
+};
+
+/// A bitboard with every cell of the board set, leaving out the spare bit on top of each column
+const BOARD_MASK: u64 = BOTTOM_MASK * ((1 << NUM_ROWS) - 1);
+
+/// The order columns are tried in, center columns take part in the most lines
+const COLUMN_ORDER: [usize; NUM_COLS] = [2, 3, 1, 4, 0, 5];
+
+/// Four cells in a row that a word can be spelled in
+#[derive(Copy, Clone)]
+struct Line {
+	/// Every cell of the line
+	cells: u64,
+	/// The first and last cells, which hold the T's of TOOT and the O's of OTTO
+	ends: u64,
+}
+
+/// The number of rows, columns and diagonals four cells long
+const NUM_LINES: usize =
+	NUM_ROWS * (NUM_COLS - 3) + NUM_COLS * (NUM_ROWS - 3) + 2 * (NUM_COLS - 3) * (NUM_ROWS - 3);
+
+/// Every line on the board, with cells laid out as `col * COL_BITS + height`
+const LINES: [Line; NUM_LINES] = {
+	// Horizontal, vertical and both diagonals as (column, height) steps
+	let directions: [(i32, i32); 4] = [(1, 0), (0, 1), (1, 1), (1, -1)];
+	let mut lines = [Line { cells: 0, ends: 0 }; NUM_LINES];
+	let mut count = 0;
+
+	let mut direction = 0;
+	while direction < directions.len() {
+		let (col_step, height_step) = directions[direction];
+
+		let mut col = 0;
+		while col < NUM_COLS as i32 {
+			let mut height = 0;
+			while height < NUM_ROWS as i32 {
+				let end_col = col + 3 * col_step;
+				let end_height = height + 3 * height_step;
+
+				if end_col < NUM_COLS as i32 && end_height >= 0 && end_height < NUM_ROWS as i32 {
+					let mut cells = 0;
+					let mut i = 0;
+					while i < 4 {
+						cells |= cell(col + i * col_step, height + i * height_step);
+						i += 1;
+					}
+
+					lines[count] = Line {
+						cells,
+						ends: cell(col, height) | cell(end_col, end_height),
+					};
+					count += 1;
+				}
+
+				height += 1;
+			}
+			col += 1;
+		}
+		direction += 1;
+	}
+
+	lines
+};
+
+/// The lines going through each cell, as a set of bits indexing `LINES`
+const LINES_THROUGH: [u32; NUM_COLS * COL_BITS] = {
+	let mut lines_through = [0; NUM_COLS * COL_BITS];
+	let mut line = 0;
+	while line < NUM_LINES {
+		let mut bit = 0;
+		while bit < NUM_COLS * COL_BITS {
+			if LINES[line].cells & (1 << bit) != 0 {
+				lines_through[bit] |= 1 << line;
+			}
+			bit += 1;
+		}
+		line += 1;
+	}
+	lines_through
+};
+
+/// The bitboard with only the cell at `height` in `col` set
+const fn cell(col: i32, height: i32) -> u64 {
+	1 << (col as usize * COL_BITS + height as usize)
+}
+
+/// The exact scores of the positions early in the game, built by `Solver::write_table`
+const SOLUTION_TABLE: &[u8] = include_bytes!("solutions.bin");
+
+/// The bytes every solution table starts with
+const TABLE_MAGIC: &[u8; 4] = b"TOOT";
+
+/// The size of the header, the magic bytes then the depth and the number of entries
+const TABLE_HEADER_SIZE: usize = 9;
+
+/// The size of a single entry, a position's key and its score packed together
+const TABLE_ENTRY_SIZE: usize = 5;
+
+/// The number of bits the score of an entry takes up below the key
+const TABLE_SCORE_BITS: usize = 6;
+
+/// The exact game theoretic value of a position
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Solution {
+	/// Positive when the player to move wins and negative when they lose,
+	/// the further from zero the sooner the game is decided
+	pub score: i32,
+	pub outcome: Outcome,
+	/// How many more moves the game lasts when both sides play perfectly,
+	/// drawn games are counted as filling the board
+	pub moves_left: usize,
+}
+
+/// A position in the form the solver works with
+///
+/// TOOT always plays the even numbered moves, so the letters on the board and
+/// the pieces left are all that is needed to tell positions apart
+#[derive(Copy, Clone)]
+struct Position {
+	/// The cells holding a T
+	ts: u64,
+	/// The cells holding either letter
+	mask: u64,
+	moves: usize,
+	/// The pieces each player has left, laid out like `TootAndOtto::piece_counts`
+	piece_counts: [[usize; 2]; 2],
+}
+
+impl Position {
+	fn from_board(board: &TootAndOtto) -> Self {
+		let mut position = Position {
+			ts: 0,
+			mask: 0,
+			moves: board.moves_played,
+			piece_counts: board.piece_counts,
+		};
+
+		for row in 0..NUM_ROWS {
+			for col in 0..NUM_COLS {
+				let bit = cell(col as i32, (NUM_ROWS - 1 - row) as i32);
+				match board.board[row][col] {
+					None => {}
+					Some(PieceLetter::T) => {
+						position.ts |= bit;
+						position.mask |= bit;
+					}
+					Some(PieceLetter::O) => position.mask |= bit,
+				}
+			}
+		}
+
+		position
+	}
+
+	/// The index of the player to move in per-player arrays
+	fn player(&self) -> usize {
+		self.moves % 2
+	}
+
+	/// Plays the letter with index `letter` into the single cell set in `mv`
+	///
+	/// Returns the score of the position for the player who moved when the move
+	/// ends the game
+	fn play(&mut self, mv: u64, letter: usize) -> Option<i32> {
+		let player = self.player();
+
+		self.mask |= mv;
+		if letter == PieceLetter::T.index() {
+			self.ts |= mv;
+		}
+		self.piece_counts[player][letter] -= 1;
+		self.moves += 1;
+
+		// Only lines through the new piece can have just been completed
+		let mut toot_spelled = false;
+		let mut otto_spelled = false;
+		let mut lines = LINES_THROUGH[mv.trailing_zeros() as usize];
+		while lines != 0 {
+			let line = LINES[lines.trailing_zeros() as usize];
+			lines &= lines - 1;
+
+			if self.mask & line.cells == line.cells {
+				let ts = self.ts & line.cells;
+				toot_spelled |= ts == line.ends;
+				otto_spelled |= ts == line.cells ^ line.ends;
+			}
+		}
+
+		// Wins that happen sooner score higher
+		let score = (NUM_CELLS + 1 - self.moves) as i32;
+		match (toot_spelled, otto_spelled) {
+			(true, true) => Some(0),
+			(true, false) if player == 0 => Some(score),
+			(false, true) if player == 1 => Some(score),
+			(true, false) | (false, true) => Some(-score),
+			(false, false) if self.moves == NUM_CELLS => Some(0),
+			(false, false) => None,
+		}
+	}
+
+	/// Gets the lowest empty cell of every column that isn't full
+	fn possible(&self) -> u64 {
+		(self.mask + BOTTOM_MASK) & BOARD_MASK
+	}
+
+	/// A key that is the same for a position and its mirror image
+	///
+	/// Adding the T's to the mask is unique since every column of the mask is
+	/// a run of ones from the bottom, the number of T's TOOT has left then
+	/// tells apart positions with the same letters on the board
+	fn key(&self) -> u64 {
+		let mirror = |bitboard: u64| {
+			(0..NUM_COLS).fold(0, |mirrored, col| {
+				let column = (bitboard >> (col * COL_BITS)) & ((1 << COL_BITS) - 1);
+				mirrored | column << ((NUM_COLS - 1 - col) * COL_BITS)
+			})
+		};
+
+		let pieces = (self.piece_counts[0][0] as u64) << (NUM_COLS * COL_BITS);
+		let key = self.ts + self.mask;
+		let mirrored_key = mirror(self.ts) + mirror(self.mask);
+
+		key.min(mirrored_key) | pieces
+	}
+
+	/// Rates a move by how many lines it leaves one letter short of a word,
+	/// setting up the mover's own word counts for more than the opponent's
+	fn move_score(&self, mv: u64) -> i32 {
+		let player = self.player() ^ 1;
+		let mut score = 0;
+
+		let mut lines = LINES_THROUGH[mv.trailing_zeros() as usize];
+		while lines != 0 {
+			let line = LINES[lines.trailing_zeros() as usize];
+			lines &= lines - 1;
+
+			let filled = self.mask & line.cells;
+			if filled.count_ones() != 3 {
+				continue;
+			}
+
+			let ts = self.ts & line.cells;
+			if ts == line.ends & filled {
+				score += if player == 0 { 2 } else { -1 };
+			}
+			if ts == (line.cells ^ line.ends) & filled {
+				score += if player == 1 { 2 } else { -1 };
+			}
+		}
+
+		score
+	}
+
+	/// Gets every position one move on
+	fn children(&self) -> Vec<Child> {
+		let possible = self.possible();
+		let mut children = vec![];
+
+		for letter in PieceLetter::iter() {
+			if self.piece_counts[self.player()][letter.index()] == 0 {
+				continue;
+			}
+
+			for &col in COLUMN_ORDER.iter() {
+				let mv = possible & column_mask(col);
+				if mv == 0 {
+					continue;
+				}
+
+				let mut position = *self;
+				let result = position.play(mv, letter.index());
+				children.push(Child {
+					position,
+					mv: (col, letter),
+					result,
+					move_score: position.move_score(mv),
+				});
+			}
+		}
+
+		children
+	}
+}
+
+/// A position one move on from another
+struct Child {
+	position: Position,
+	/// The column and letter played to reach the position
+	mv: (usize, PieceLetter),
+	/// The score of the move for the player who made it when it ends the game
+	result: Option<i32>,
+	/// How promising the move looks, see `Position::move_score`
+	move_score: i32,
+}
+
+/// Gets the cells of a single column
+fn column_mask(col: usize) -> u64 {
+	((1 << NUM_ROWS) - 1) << (col * COL_BITS)
+}
+
+/// Solves TOOT and OTTO positions exactly with a full depth alpha-beta search
+///
+/// The scores of positions early in the game are read from a solution table
+/// rather than searched, the full game takes minutes to solve from scratch.
+/// The table only goes as deep as the searches that take longest, the one
+/// built into the game holds every position with at most 3 pieces. A table of
+/// every reachable position would be far too large to ship, so every other
+/// position is searched when it's reached. With 4 pieces that takes around 10
+/// million positions, about 5 seconds natively and longer in the browser, and
+/// it gets quicker as the board fills up. `try_best_moves` gives up on searches
+/// that run longer than asked. Only boards with `TootAndOttoRules::STANDARD`
+/// can be solved
+pub struct Solver {
+	/// The low 32 bits of each stored key, the table size being a prime larger
+	/// than 2 makes them enough to tell positions apart
+	keys: Vec<u32>,
+	/// The lowest and highest score each stored position could have
+	bounds: Vec<(i8, i8)>,
+	/// The exact scores of early positions sorted by key, from the solution table
+	solutions: Vec<(u64, i32)>,
+	/// Every position with at most this many pieces is in `solutions`
+	solutions_depth: usize,
+	/// The number of positions searched so far
+	pub nodes: u64,
+	/// The value of `nodes` the search gives up at, see `try_best_moves`
+	budget: Option<u64>,
+	/// Whether the search ran past `budget`
+	aborted: bool,
+}
+
+impl Solver {
+	/// Creates a solver that reads from the solution table built into the game
+	pub fn new() -> Self {
+		let mut solver = Solver::without_table();
+		solver
+			.load_table(SOLUTION_TABLE)
+			.expect("The built in TOOT and OTTO solution table is corrupt");
+		solver
+	}
+
+	/// Creates a solver that searches every position itself
+	pub fn without_table() -> Self {
+		Solver {
+			keys: vec![0; TABLE_SIZE],
+			bounds: vec![(-MAX_SCORE as i8, MAX_SCORE as i8); TABLE_SIZE],
+			solutions: vec![],
+			solutions_depth: 0,
+			nodes: 0,
+			budget: None,
+			aborted: false,
+		}
+	}
+
+	/// Reads a solution table written by `write_table`, returning an error when
+	/// the bytes aren't one
+	///
+	/// A table starts with the bytes `TOOT`, then the depth it was built to as a
+	/// byte and the number of entries as a little endian `u32`. Each entry is a
+	/// little endian 40 bit number, the key of the position shifted above the
+	/// score offset by `MAX_SCORE`, and the entries are sorted by key
+	pub fn load_table(&mut self, bytes: &[u8]) -> Result<(), String> {
+		if bytes.len() < TABLE_HEADER_SIZE || &bytes[..4] != TABLE_MAGIC {
+			return Err(String::from("Not a TOOT and OTTO solution table"));
+		}
+
+		let depth = bytes[4] as usize;
+		let mut count = [0; 4];
+		count.copy_from_slice(&bytes[5..TABLE_HEADER_SIZE]);
+		let count = u32::from_le_bytes(count) as usize;
+
+		if bytes.len() != TABLE_HEADER_SIZE + count * TABLE_ENTRY_SIZE {
+			return Err(format!("Expected {} entries in the solution table", count));
+		}
+
+		let mut solutions = Vec::with_capacity(count);
+		for entry in bytes[TABLE_HEADER_SIZE..].chunks(TABLE_ENTRY_SIZE) {
+			let mut packed = [0; 8];
+			packed[..TABLE_ENTRY_SIZE].copy_from_slice(entry);
+			let packed = u64::from_le_bytes(packed);
+
+			let key = packed >> TABLE_SCORE_BITS;
+			let score = (packed & ((1 << TABLE_SCORE_BITS) - 1)) as i32 - MAX_SCORE;
+			solutions.push((key, score));
+		}
+
+		if solutions.windows(2).any(|pair| pair[0].0 >= pair[1].0) {
+			return Err(String::from("The solution table isn't sorted"));
+		}
+
+		self.solutions = solutions;
+		self.solutions_depth = depth;
+		Ok(())
+	}
+
+	/// Solves every position with at most `depth` pieces on the board and
+	/// writes their scores out in the format `load_table` reads
+	///
+	/// Each position solved is reported to `progress` along with how many
+	/// there are in total
+	#[allow(dead_code)] // Tables are only built from the CLI
+	pub fn write_table(&mut self, depth: usize, mut progress: impl FnMut(usize, usize)) -> Vec<u8> {
+		// Finds every distinct position that isn't over, one ply at a time
+		let mut positions = vec![];
+		let mut frontier = vec![Position::from_board(&TootAndOtto::new())];
+		for _ in 0..=depth {
+			positions.extend_from_slice(&frontier);
+
+			let mut next = vec![];
+			for position in frontier {
+				for child in position.children() {
+					if child.result.is_none() {
+						next.push(child.position);
+					}
+				}
+			}
+
+			next.sort_by_key(Position::key);
+			next.dedup_by_key(|position| position.key());
+			frontier = next;
+		}
+
+		// Solving the deepest positions first leaves their results in the
+		// transposition table, where the shallower ones can reuse them
+		let mut solutions = vec![];
+		for (i, position) in positions.iter().rev().enumerate() {
+			solutions.push((position.key(), self.solve_position(position)));
+			progress(i + 1, positions.len());
+		}
+		solutions.sort_unstable();
+
+		let mut bytes = TABLE_MAGIC.to_vec();
+		bytes.push(depth as u8);
+		bytes.extend_from_slice(&(solutions.len() as u32).to_le_bytes());
+		for (key, score) in solutions {
+			let packed = key << TABLE_SCORE_BITS | (score + MAX_SCORE) as u64;
+			bytes.extend_from_slice(&packed.to_le_bytes()[..TABLE_ENTRY_SIZE]);
+		}
+
+		bytes
+	}
+
+	/// Finds the exact value of a position for the player to move
+	pub fn solve(&mut self, board: &TootAndOtto) -> Solution {
+		let score = if board.is_terminal {
+			// The previous move either spelled a word or filled the board
+			let score = (NUM_CELLS + 1 - board.moves_played) as i32;
+			match board.winner {
+				Some(player) if player == board.active_player => score,
+				Some(_) => -score,
+				None => 0,
+			}
+		} else {
+			self.solve_position(&Position::from_board(board))
+		};
+
+		solution(score, board.moves_played)
+	}
+
+	/// Picks one of the moves that wins fastest, or failing that loses slowest,
+	/// at random
+	#[allow(dead_code)] // Only the CLI asks for a single perfect move
+	pub fn best_move(&mut self, board: &TootAndOtto) -> (usize, PieceLetter) {
+		*self
+			.best_moves(board)
+			.choose(&mut rand::thread_rng())
+			.expect("best_move called on a finished game")
+	}
+
+	/// Finds every move that wins fastest, or failing that loses slowest
+	///
+	/// Only the current position is solved exactly, each move is then checked
+	/// against that score with a single null window search
+	pub fn best_moves(&mut self, board: &TootAndOtto) -> Vec<(usize, PieceLetter)> {
+		let score = self.solve(board).score;
+		let position = Position::from_board(board);
+		let mut best_moves = vec![];
+
+		for child in position.children() {
+			// A move keeps the score when the opponent can do no better than its negation
+			let keeps_score = match child.result {
+				Some(result) => result >= score,
+				None => self.negamax(&child.position, -score, -score + 1) <= -score,
+			};
+
+			if keeps_score {
+				best_moves.push(child.mv);
+			}
+		}
+
+		best_moves
+	}
+
+	/// Finds the moves `best_moves` does, unless that takes more than
+	/// `max_nodes` positions, when it gives up and returns `None`
+	///
+	/// The positions solved before giving up stay in the table, so asking again
+	/// picks up where this left off
+	pub fn try_best_moves(
+		&mut self,
+		board: &TootAndOtto,
+		max_nodes: u64,
+	) -> Option<Vec<(usize, PieceLetter)>> {
+		self.budget = Some(self.nodes.saturating_add(max_nodes));
+		self.aborted = false;
+
+		let moves = self.best_moves(board);
+		self.budget = None;
+
+		match self.aborted {
+			true => None,
+			false => Some(moves),
+		}
+	}
+
+	/// Narrows in on the exact score with null window searches
+	fn solve_position(&mut self, position: &Position) -> i32 {
+		if let Some(score) = self.table_score(position) {
+			return score;
+		}
+
+		let mut min = -MAX_SCORE;
+		let mut max = MAX_SCORE;
+
+		while min < max {
+			// Searching closer to zero first finds the result of most positions faster
+			let mut med = min + (max - min) / 2;
+			if med <= 0 && min / 2 < med {
+				med = min / 2;
+			} else if med >= 0 && max / 2 > med {
+				med = max / 2;
+			}
+
+			// Checks whether the score is above or below `med`
+			let score = self.negamax(position, med, med + 1);
+			if self.aborted {
+				// The score is thrown away, any that makes a valid solution will do
+				return 0;
+			} else if score <= med {
+				max = score;
+			} else {
+				min = score;
+			}
+		}
+
+		min
+	}
+
+	/// Looks up the exact score of a position in the solution table
+	fn table_score(&self, position: &Position) -> Option<i32> {
+		if position.moves > self.solutions_depth {
+			return None;
+		}
+
+		let key = position.key();
+		self.solutions
+			.binary_search_by_key(&key, |&(key, _)| key)
+			.ok()
+			.map(|i| self.solutions[i].1)
+	}
+
+	/// Scores a position that isn't over for the player to move
+	///
+	/// The result is exact inside `alpha..beta`, at most `alpha` when the true
+	/// score is lower and at least `beta` when it is higher
+	fn negamax(&mut self, position: &Position, mut alpha: i32, mut beta: i32) -> i32 {
+		self.nodes += 1;
+		if matches!(self.budget, Some(budget) if self.nodes > budget) {
+			self.aborted = true;
+			return 0;
+		}
+
+		if let Some(score) = self.table_score(position) {
+			return score;
+		}
+
+		// Playing a move that ends the game needs no further search, and
+		// spelling your own word right away is as good as it gets
+		let mut best_score = -MAX_SCORE;
+		let mut children = vec![];
+		for child in position.children() {
+			match child.result {
+				Some(score) if score > 0 => return score,
+				Some(score) => best_score = best_score.max(score),
+				None => children.push(child),
+			}
+		}
+
+		if children.is_empty() {
+			return best_score;
+		}
+
+		// The soonest anyone can win now is with the opponent's next move
+		let max = (NUM_CELLS - 1 - position.moves) as i32;
+		if beta > max {
+			beta = max;
+			if alpha >= beta {
+				return beta;
+			}
+		}
+
+		let key = position.key();
+		let slot = (key % TABLE_SIZE as u64) as usize;
+		let (mut lower, mut upper) = (-MAX_SCORE, MAX_SCORE);
+		if self.keys[slot] == key as u32 {
+			lower = self.bounds[slot].0 as i32;
+			upper = self.bounds[slot].1 as i32;
+
+			if lower >= beta {
+				return lower;
+			} else if upper <= alpha {
+				return upper;
+			}
+			alpha = alpha.max(lower);
+			beta = beta.min(upper);
+		}
+
+		let original_alpha = alpha;
+		let original_beta = beta;
+
+		// Tries moves that set up the most words first
+		children.sort_by_key(|child| -child.move_score);
+		for child in children {
+			// Nothing is stored for a position that wasn't finished
+			let score = -self.negamax(&child.position, -beta, -alpha);
+			if self.aborted {
+				return 0;
+			}
+			best_score = best_score.max(score);
+			alpha = alpha.max(score);
+			if alpha >= beta {
+				break;
+			}
+		}
+
+		if best_score <= original_alpha {
+			upper = best_score;
+		} else if best_score >= original_beta {
+			lower = best_score;
+		} else {
+			lower = best_score;
+			upper = best_score;
+		}
+
+		self.keys[slot] = key as u32;
+		self.bounds[slot] = (lower as i8, upper as i8);
+
+		best_score
+	}
+}
+
+/// Works out who wins and when from a score and the number of moves played
+fn solution(score: i32, moves_played: usize) -> Solution {
+	// A score of `s` means the game is decided by the move that fills the
+	// `NUM_CELLS + 1 - s`th cell
+	let moves_left = (NUM_CELLS as i32 + 1 - score.abs()) as usize - moves_played;
+
+	if score > 0 {
+		Solution {
+			score,
+			outcome: Outcome::Win,
+			moves_left,
+		}
+	} else if score < 0 {
+		Solution {
+			score,
+			outcome: Outcome::Loss,
+			moves_left,
+		}
+	} else {
+		Solution {
+			score,
+			outcome: Outcome::Draw,
+			moves_left: NUM_CELLS - moves_played,
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::toot_and_otto::piece_letter::PieceLetter::*;
+
+	/// Plays `moves` from the empty board, filling columns 4 and 5 first so
+	/// the positions are quick to search
+	fn play(moves: &[(PieceLetter, usize)]) -> TootAndOtto {
+		let setup = [(T, 4), (T, 5), (O, 4), (O, 5)];
+		let mut board = TootAndOtto::new();
+		for &(letter, col) in setup.iter().chain(&setup).chain(moves) {
+			board.drop(letter, col).unwrap();
+		}
+		board
+	}
+
+	#[test]
+	fn spells_its_word_right_away() {
+		// TOO on the bottom row with TOOT to move
+		let board = play(&[(T, 0), (O, 1), (O, 2), (O, 0)]);
+		let mut solver = Solver::without_table();
+
+		let solution = solver.solve(&board);
+		assert_eq!(solution.outcome, Outcome::Win);
+		assert_eq!(solution.moves_left, 1);
+		assert_eq!(solution.score, (NUM_CELLS - board.moves_played) as i32);
+		assert_eq!(solver.best_moves(&board), vec![(3, T)]);
+	}
+
+	#[test]
+	fn blocks_the_other_word() {
+		// OTTO has to fill the end of TOO, and a T would spell TOOT
+		let board = play(&[(T, 0), (O, 1), (O, 2)]);
+		let mut solver = Solver::without_table();
+
+		assert_eq!(solver.best_moves(&board), vec![(3, O)]);
+		assert!(solver.solve(&board).moves_left > 1);
+	}
+
+	#[test]
+	fn finished_games_are_lost_by_the_player_to_move() {
+		let mut board = play(&[(T, 0), (O, 1), (O, 2), (O, 0)]);
+		board.drop(T, 3).unwrap();
+		let solution = Solver::without_table().solve(&board);
+
+		assert_eq!(solution.outcome, Outcome::Loss);
+		assert_eq!(solution.moves_left, 0);
+	}
+
+	#[test]
+	fn gives_up_past_the_budget() {
+		let board = play(&[(T, 0), (O, 1), (O, 2)]);
+		let mut solver = Solver::without_table();
+
+		assert_eq!(solver.try_best_moves(&board, 1), None);
+		assert_eq!(solver.try_best_moves(&board, u64::MAX), Some(vec![(3, O)]));
+	}
+}
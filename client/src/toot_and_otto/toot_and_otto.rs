@@ -1,78 +1,145 @@
-use std::fmt::{Display, Formatter, Result};
+use std::fmt::{self, Display, Formatter};
+use strum::IntoEnumIterator;
 
 use super::{
 	piece_letter::{PieceLetter, PieceLetter::*},
 	player::{Player, Player::*},
+	rules::{TootAndOttoRules, MAX_COLS, MAX_PIECES, MAX_ROWS},
+	weights::TootAndOttoWeights,
+};
+use crate::{
+	engine::{game::Game, zobrist},
+	types::move_error::MoveError,
 };
 
 /// A wrapper around the primary `Board` component
 #[derive(Clone, Copy)]
 pub struct TootAndOtto {
+	pub rules: TootAndOttoRules,
+	/// How `calculate_score` values each kind of window
+	pub weights: TootAndOttoWeights,
 	pub board: Board,
 	pub winner: Option<Player>,
 	pub active_player: Player,
 	pub is_terminal: bool,
 	pub moves_played: usize,
-	pub column_heights: [usize; NUM_COLS],
+	pub column_heights: [usize; MAX_COLS],
 	pub piece_counts: [[usize; 2]; 2], // [[TOOTS T's, TOOTS O's], [OTTO's T's, OTTO's O's]]
+	pub hash: u64,
+	pub mirrored_hash: u64,
+	/// Every column and letter played in order, moves past `moves_played` were
+	/// undone and can be redone
+	pub history: [(usize, PieceLetter); MAX_COLS * MAX_ROWS],
+	/// The number of moves in `history` that can be replayed
+	pub history_len: usize,
+	/// How many of the first moves in `history` only set up the position the
+	/// board started from, game records start after them
+	pub set_up_moves: usize,
+	/// The columns ordered by distance from the center
+	column_order: [usize; MAX_COLS],
 }
 
-/// A 6x4 TOOT-n-OTTO Board
-type Board = [[BoardCell; NUM_COLS]; NUM_ROWS];
-pub const NUM_COLS: usize = 6;
-pub const NUM_ROWS: usize = 4;
+/// A TOOT-n-OTTO Board, top row first
+type Board = [[BoardCell; MAX_COLS]; MAX_ROWS];
 
 /// All the possible values for a board cell (None, Some(T), SOME(O))
 type BoardCell = Option<PieceLetter>;
 
+/// A random key for every letter in every cell, indexed by `[letter][col * MAX_ROWS + height]`
+const CELL_KEYS: [[u64; MAX_COLS * MAX_ROWS]; 2] = {
+	let mut keys = [[0; MAX_COLS * MAX_ROWS]; 2];
+	let mut i = 0;
+	while i < MAX_COLS * MAX_ROWS {
+		keys[0][i] = zobrist::key(2 * i as u64);
+		keys[1][i] = zobrist::key(2 * i as u64 + 1);
+		i += 1;
+	}
+	keys
+};
+
+/// A random key for every number of pieces left, indexed by `[player * 2 + letter][count]`,
+/// two positions with the same board can still differ in who has which letters left
+const COUNT_KEYS: [[u64; MAX_PIECES + 1]; 4] = {
+	let mut keys = [[0; MAX_PIECES + 1]; 4];
+	let mut i = 0;
+	while i < 4 {
+		let mut count = 0;
+		while count <= MAX_PIECES {
+			let offset = 2 * MAX_COLS * MAX_ROWS + i * (MAX_PIECES + 1) + count;
+			keys[i][count] = zobrist::key(offset as u64);
+			count += 1;
+		}
+		i += 1;
+	}
+	keys
+};
+
 impl TootAndOtto {
-	/// Initializes an empty TOOT-n-OTTO board
+	/// Initializes an empty TOOT-n-OTTO board of the standard size
 	pub fn new() -> Self {
+		TootAndOtto::with_rules(TootAndOttoRules::STANDARD)
+	}
+
+	/// Initializes an empty board of the size and letters set by `rules`, which
+	/// should come from `TootAndOttoRules::new`
+	pub fn with_rules(rules: TootAndOttoRules) -> Self {
+		// Sorts the columns by distance from the center, leftmost first on ties
+		let mut column_order = [0; MAX_COLS];
+		let mut columns: Vec<usize> = (0..rules.num_cols).collect();
+		columns.sort_by_key(|&col| (2 * col as isize - rules.num_cols as isize + 1).abs());
+		column_order[..rules.num_cols].copy_from_slice(&columns);
+
 		TootAndOtto {
-			board: [[None; NUM_COLS]; NUM_ROWS],
-			column_heights: [0; NUM_COLS],
+			rules,
+			weights: TootAndOttoWeights::DEFAULT,
+			board: [[None; MAX_COLS]; MAX_ROWS],
+			column_heights: [0; MAX_COLS],
 			active_player: TOOT,
 			is_terminal: false,
 			moves_played: 0,
 			winner: None,
-			piece_counts: [[6; 2]; 2],
+			piece_counts: [[rules.starting_pieces(); 2]; 2],
+			hash: 0,
+			mirrored_hash: 0,
+			history: [(0, T); MAX_COLS * MAX_ROWS],
+			history_len: 0,
+			set_up_moves: 0,
+			column_order,
 		}
 	}
 
 	/// Drops a piece of some `letter` into a `col` on the the board
-	pub fn drop(&mut self, letter: PieceLetter, col: usize) -> bool {
+	pub fn drop(&mut self, letter: PieceLetter, col: usize) -> Result<(), MoveError> {
 		// Checks for some simple input errors
-		if col >= NUM_COLS {
-			return false;
+		if self.is_terminal {
+			return Err(MoveError::GameOver);
+		} else if col >= self.rules.num_cols {
+			return Err(MoveError::ColumnOutOfRange(col));
 		}
 
-		let piece_count = match self.active_player {
-			TOOT => match letter {
-				T => self.piece_counts[0][0],
-				O => self.piece_counts[0][1],
-			},
-			OTTO => match letter {
-				T => self.piece_counts[1][0],
-				O => self.piece_counts[1][1],
-			},
-		};
+		let player_index = self.active_player.index();
+		let letter_index = letter.index();
+		let piece_count = self.piece_counts[player_index][letter_index];
 
 		if piece_count == 0 {
-			return false;
+			return Err(MoveError::NoPiecesLeft);
 		}
 
 		// Gets the number of pieces in the column and checks if it is full
 		let col_height = self.get_col_height(col);
 
-		if col_height == NUM_ROWS {
-			return false;
+		if col_height == self.rules.num_rows {
+			return Err(MoveError::ColumnFull(col));
 		}
 
 		// Calculates which row the the piece should be inserted into
-		let row = NUM_ROWS - 1 - col_height;
+		let row = self.rules.num_rows - 1 - col_height;
 
 		// Inserts the piece into the board
 		self.board[row][col] = Some(letter);
+		self.hash ^= CELL_KEYS[letter_index][col * MAX_ROWS + col_height];
+		self.mirrored_hash ^=
+			CELL_KEYS[letter_index][(self.rules.num_cols - 1 - col) * MAX_ROWS + col_height];
 
 		let toot_won = self.check_for_win(TOOT);
 		let otto_won = self.check_for_win(OTTO);
@@ -93,34 +160,95 @@ impl TootAndOtto {
 			(None, None) => None,
 		};
 
-		// Updates the piece count for the player
-		match self.active_player {
-			TOOT => match letter {
-				T => self.piece_counts[0][0] -= 1,
-				O => self.piece_counts[0][1] -= 1,
-			},
-			OTTO => match letter {
-				T => self.piece_counts[1][0] -= 1,
-				O => self.piece_counts[1][1] -= 1,
-			},
+		// Updates the piece count for the player, with unlimited letters it never changes
+		if self.rules.pieces_per_letter.is_some() {
+			let count_keys = COUNT_KEYS[player_index * 2 + letter_index];
+			let count_change = count_keys[piece_count] ^ count_keys[piece_count - 1];
+			self.piece_counts[player_index][letter_index] -= 1;
+			self.hash ^= count_change;
+			self.mirrored_hash ^= count_change;
 		}
 
+		// A new move replaces any moves that were undone
+		self.history[self.moves_played] = (col, letter);
+		self.history_len = self.moves_played + 1;
+
+		self.column_heights[col] += 1;
 		self.moves_played += 1;
-		if self.moves_played == NUM_COLS * NUM_ROWS {
+		if self.moves_played == self.rules.num_cells() {
 			self.is_terminal = true
 		}
 
 		self.active_player = self.active_player.switch();
 
+		Ok(())
+	}
+
+	/// Takes back the last move played, returns false if there is none
+	pub fn undo(&mut self) -> bool {
+		if self.moves_played == 0 {
+			return false;
+		}
+
+		let (col, letter) = self.history[self.moves_played - 1];
+		self.active_player = self.active_player.switch();
+		self.column_heights[col] -= 1;
+
+		// Removes the top piece of the column
+		let col_height = self.column_heights[col];
+		let row = self.rules.num_rows - 1 - col_height;
+		let letter_index = letter.index();
+		self.board[row][col] = None;
+		self.hash ^= CELL_KEYS[letter_index][col * MAX_ROWS + col_height];
+		self.mirrored_hash ^=
+			CELL_KEYS[letter_index][(self.rules.num_cols - 1 - col) * MAX_ROWS + col_height];
+
+		// Hands the piece back to the player who played it
+		if self.rules.pieces_per_letter.is_some() {
+			let player_index = self.active_player.index();
+			let piece_count = self.piece_counts[player_index][letter_index];
+			let count_keys = COUNT_KEYS[player_index * 2 + letter_index];
+			let count_change = count_keys[piece_count] ^ count_keys[piece_count + 1];
+			self.piece_counts[player_index][letter_index] += 1;
+			self.hash ^= count_change;
+			self.mirrored_hash ^= count_change;
+		}
+
+		self.moves_played -= 1;
+
+		// No moves are played once the game is over, so it wasn't over before this one
+		self.winner = None;
+		self.is_terminal = false;
+
 		true
 	}
 
+	/// Plays the last move that was undone again, returns false if there is none
+	pub fn redo(&mut self) -> bool {
+		if !self.can_redo() {
+			return false;
+		}
+
+		// Replaying the same move keeps the rest of the undone moves
+		let history_len = self.history_len;
+		let (col, letter) = self.history[self.moves_played];
+		let is_redone = self.drop(letter, col).is_ok();
+		self.history_len = history_len;
+
+		is_redone
+	}
+
+	/// Checks if there are any undone moves left to play again
+	pub fn can_redo(&self) -> bool {
+		self.history_len > self.moves_played
+	}
+
 	/// Gets the number of chips that have been placed in a column
 	fn get_col_height(&self, col: usize) -> usize {
-		for row in 0..NUM_ROWS {
+		for row in 0..self.rules.num_rows {
 			match self.board[row][col] {
 				None => {}
-				_ => return NUM_ROWS - row,
+				_ => return self.rules.num_rows - row,
 			}
 		}
 
@@ -128,91 +256,60 @@ impl TootAndOtto {
 		0
 	}
 
-	// Checks to see if a color has one the game
-	pub fn check_for_win(&self, player: Player) -> Option<Vec<[usize; 2]>> {
-		let win_pattern = match player {
-			TOOT => [T, O, O, T],
-			OTTO => [O, T, T, O],
-		};
-
-		let check_for_win_in_window = |window: &[BoardCell]| -> bool {
-			for (i, cell) in window.iter().enumerate() {
-				match cell {
-					None => return false,
-					Some(c) => {
-						if *c != win_pattern[i] {
-							return false;
-						}
-					}
-				}
+	/// Every row, column and diagonal window of four cells as `[row, col]`
+	/// pairs, along with whether the window runs down a column
+	fn windows(&self) -> impl Iterator<Item = ([[usize; 2]; 4], bool)> {
+		let (num_cols, num_rows) = (self.rules.num_cols, self.rules.num_rows);
+
+		// The four cells from `[row, col]` stepping `row_step` rows and `col_step` columns
+		let window = |row: usize, col: usize, row_step: isize, col_step: usize| {
+			let mut window = [[0; 2]; 4];
+			for (i, cell) in window.iter_mut().enumerate() {
+				*cell = [
+					(row as isize + i as isize * row_step) as usize,
+					col + i * col_step,
+				];
 			}
-
-			true
+			window
 		};
 
-		// Checks all the rows
-		for row in 0..NUM_ROWS {
-			for start_col in 0..NUM_COLS - 3 {
-				let window = &self.board[row][start_col..start_col + 4];
-				if check_for_win_in_window(window) {
-					return Some((0..4).into_iter().map(|i| [row, start_col + i]).collect());
-				}
-			}
-		}
-
-		// Performs a check across all columns
-		for col in 0..NUM_COLS {
-			for start_row in 0..NUM_ROWS - 3 {
-				let mut window = vec![];
-				(start_row..start_row + 4)
-					.into_iter()
-					.for_each(|row| window.push(self.board[row][col]));
+		let rows = (0..num_rows)
+			.flat_map(move |row| (0..num_cols - 3).map(move |col| (window(row, col, 0, 1), false)));
+		let columns = (0..num_cols)
+			.flat_map(move |col| (0..num_rows - 3).map(move |row| (window(row, col, 1, 0), true)));
 
-				if check_for_win_in_window(&window) {
-					return Some((0..4).into_iter().map(|i| [start_row + i, col]).collect());
-				}
-			}
-		}
-
-		// Perform a check across positively sloped diagonals
-		for col in 0..NUM_COLS - 3 {
-			let mut window = vec![];
-			(0..4)
-				.into_iter()
-				.for_each(|i| window.push(self.board[NUM_ROWS - 1 - i][col + i]));
-			if check_for_win_in_window(&window) {
-				return Some(
-					(0..4)
-						.into_iter()
-						.map(|i| [NUM_ROWS - 1 - i, col + i])
-						.collect(),
-				);
-			}
-		}
+		// Diagonals going up and to the right, then down and to the right
+		let rising = (3..num_rows).flat_map(move |row| {
+			(0..num_cols - 3).map(move |col| (window(row, col, -1, 1), false))
+		});
+		let falling = (0..num_rows - 3)
+			.flat_map(move |row| (0..num_cols - 3).map(move |col| (window(row, col, 1, 1), false)));
 
-		// Perform a check across positively sloped diagonals
-		for col in 0..NUM_COLS - 3 {
-			let mut window = vec![];
-			(0..4)
-				.into_iter()
-				.for_each(|i| window.push(self.board[i][col + i]));
+		rows.chain(columns).chain(rising).chain(falling)
+	}
 
-			if check_for_win_in_window(&window) {
-				return Some((0..4).into_iter().map(|i| [i, col + i]).collect());
-			}
-		}
+	// Checks to see if a color has one the game
+	pub fn check_for_win(&self, player: Player) -> Option<Vec<[usize; 2]>> {
+		let win_pattern = match player {
+			TOOT => [T, O, O, T],
+			OTTO => [O, T, T, O],
+		};
 
-		None
+		self.windows()
+			.map(|(window, _)| window)
+			.find(|window| {
+				window
+					.iter()
+					.zip(win_pattern.iter())
+					.all(|(&[row, col], &letter)| self.board[row][col] == Some(letter))
+			})
+			.map(|window| window.to_vec())
 	}
 
+	/// Calculates a heuristic score for `player` and the board position,
+	/// weighing each kind of window by `weights`
 	pub fn calculate_score(&self, player: Player) -> i32 {
-		const _LINE_OF_TWO: i32 = 1; // Two pieces in a line
-		const _LINE_OF_THREE: i32 = 10; // 3 pieces in a line
-		const _LINE_OF_FOUR: i32 = 100_000; // Self Won
-
-		const _OPPONENT_LINE_OF_THREE: i32 = -20; // Opponent can win
-		const _OPPONENT_LINE_OF_THREE_WITH_BELOW: i32 = -10_000; // Opponent can win
-		const _OPPONENT_LINE_OF_TWO: i32 = -3; // Opponent can setup for win
+		let weights = self.weights;
 
 		let win_pattern = match player {
 			TOOT => [T, O, O, T],
@@ -253,70 +350,110 @@ impl TootAndOtto {
 				empty_with_below_count,
 				empty_no_below_count,
 			) {
-				(4, 0, 0, 0) => _LINE_OF_FOUR,
-				(3, 0, _, _) => _LINE_OF_THREE,
-				(2, 0, _, _) => _LINE_OF_TWO,
-				(0, 3, 1, 0) => _OPPONENT_LINE_OF_THREE_WITH_BELOW,
-				(0, 3, 0, 1) => _OPPONENT_LINE_OF_THREE,
-				(0, 2, _, _) => _OPPONENT_LINE_OF_TWO,
+				(4, 0, 0, 0) => weights.line_of_four,
+				(3, 0, _, _) => weights.line_of_three,
+				(2, 0, _, _) => weights.line_of_two,
+				(0, 3, 1, 0) => weights.opponent_line_of_three_with_below,
+				(0, 3, 0, 1) => weights.opponent_line_of_three,
+				(0, 2, _, _) => weights.opponent_line_of_two,
 				_ => 0,
 			}
 		};
 
-		// Performs a check across all rows
-		for row in 0..NUM_ROWS {
-			for start_col in 0..NUM_COLS - 3 {
-				let mut window: Vec<(BoardCell, bool)> = vec![];
-				(start_col..start_col + 4).into_iter().for_each(|col| {
-					window.push((
-						self.board[row][col],
-						self.column_heights[col] >= NUM_ROWS - row - 1,
-					))
-				});
-				score += calculate_window_score(&window);
+		// Empty cells in a column always have a piece below them once they're next
+		for (window, is_column) in self.windows() {
+			let mut cells = [(None, false); 4];
+			for (cell, &[row, col]) in cells.iter_mut().zip(window.iter()) {
+				*cell = (
+					self.board[row][col],
+					is_column || self.column_heights[col] >= self.rules.num_rows - row - 1,
+				);
 			}
+			score += calculate_window_score(&cells);
 		}
 
-		// Performs a check across all columns
-		for col in 0..NUM_COLS {
-			for start_row in 0..NUM_ROWS - 3 {
-				let mut window: Vec<(BoardCell, bool)> = vec![];
-				(start_row..start_row + 4)
-					.into_iter()
-					.for_each(|row| window.push((self.board[row][col], true)));
-				score += calculate_window_score(&window);
+		score
+	}
+
+	pub fn get_columns(&self) -> &[usize] {
+		&self.column_order[..self.rules.num_cols]
+	}
+}
+
+impl Game for TootAndOtto {
+	type Move = (usize, PieceLetter);
+	type Player = Player;
+
+	/// Gets every letter the active player has left in every column that isn't full
+	fn legal_moves(&self) -> Vec<(usize, PieceLetter)> {
+		let player_index = self.active_player.index();
+
+		let mut moves = vec![];
+		for letter in PieceLetter::iter() {
+			if self.piece_counts[player_index][letter.index()] == 0 {
+				continue;
 			}
-		}
 
-		// Perform a check across positively sloped diagonals
-		for col in 0..NUM_COLS - 3 {
-			let mut window: Vec<(BoardCell, bool)> = vec![];
-			(0..4).into_iter().for_each(|i| {
-				window.push((
-					self.board[NUM_ROWS - 1 - i][col + i],
-					self.column_heights[col + i] >= i,
-				))
-			});
-			score += calculate_window_score(&window);
+			for &col in self.get_columns().iter() {
+				if self.get_col_height(col) < self.rules.num_rows {
+					moves.push((col, letter));
+				}
+			}
 		}
 
-		// Perform a check across positively sloped diagonals
-		for col in 0..NUM_COLS - 3 {
-			let mut window = vec![];
-			(0..4).into_iter().for_each(|i| {
-				window.push((
-					self.board[i][col + i],
-					self.column_heights[col + i] >= NUM_ROWS - i - 1,
-				))
-			});
-			score += calculate_window_score(&window);
-		}
+		moves
+	}
 
-		score
+	fn apply(&mut self, (col, letter): (usize, PieceLetter)) -> bool {
+		self.drop(letter, col).is_ok()
+	}
+
+	fn undo(&mut self) -> bool {
+		TootAndOtto::undo(self)
+	}
+
+	fn redo(&mut self) -> bool {
+		TootAndOtto::redo(self)
 	}
 
-	pub fn get_columns(&self) -> [usize; NUM_COLS] {
-		[2, 3, 1, 4, 0, 5]
+	fn active_player(&self) -> Player {
+		self.active_player
+	}
+
+	fn is_terminal(&self) -> bool {
+		self.is_terminal
+	}
+
+	fn winner(&self) -> Option<Player> {
+		self.winner
+	}
+
+	fn evaluate(&self, player: Player) -> i32 {
+		self.calculate_score(player)
+	}
+
+	fn max_moves(&self) -> usize {
+		self.rules.num_cells()
+	}
+
+	fn hash(&self) -> u64 {
+		self.hash
+	}
+
+	fn mirrored_hash(&self) -> u64 {
+		self.mirrored_hash
+	}
+
+	fn mirror_move(&self, (col, letter): (usize, PieceLetter)) -> (usize, PieceLetter) {
+		(self.rules.num_cols - 1 - col, letter)
+	}
+
+	fn column(&self, (col, _): (usize, PieceLetter)) -> usize {
+		col
+	}
+
+	fn num_cols(&self) -> usize {
+		self.rules.num_cols
 	}
 }
 
@@ -324,12 +461,12 @@ impl Display for TootAndOtto {
 	/**
 	 * Defines the print functionality for the board
 	 */
-	fn fmt(&self, f: &mut Formatter) -> Result {
+	fn fmt(&self, f: &mut Formatter) -> fmt::Result {
 		// Initializes an empty string that will be built on
 		let mut print_string = String::new();
 
-		for row in 0..NUM_ROWS {
-			for col in 0..NUM_COLS {
+		for row in 0..self.rules.num_rows {
+			for col in 0..self.rules.num_cols {
 				// Places a -, R, Y depending on what is in the cell
 				match self.board[row][col] {
 					None => print_string.push('-'),
@@ -342,8 +479,75 @@ impl Display for TootAndOtto {
 			print_string.push('\n');
 		}
 
-		print_string.push_str("0 1 2 3 4 5");
+		let col_numbers: Vec<String> = (0..self.rules.num_cols)
+			.map(|col| col.to_string())
+			.collect();
+		print_string.push_str(&col_numbers.join(" "));
 
 		write!(f, "\nCurrent Board:\n{}\n", print_string)
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	/// Everything about a board that undoing and redoing has to restore
+	type Snapshot = (
+		Board,
+		[usize; MAX_COLS],
+		[[usize; 2]; 2],
+		Player,
+		usize,
+		Option<Player>,
+		bool,
+		u64,
+		u64,
+	);
+
+	fn snapshot(board: &TootAndOtto) -> Snapshot {
+		(
+			board.board,
+			board.column_heights,
+			board.piece_counts,
+			board.active_player,
+			board.moves_played,
+			board.winner,
+			board.is_terminal,
+			board.hash,
+			board.mirrored_hash,
+		)
+	}
+
+	#[test]
+	fn undo_and_redo_retrace_drops() {
+		let mut board = TootAndOtto::new();
+		let moves = [(O, 4), (T, 0), (O, 4), (O, 1), (O, 2), (T, 3)];
+		let mut states = vec![snapshot(&board)];
+		for &(letter, col) in &moves {
+			board.drop(letter, col).unwrap();
+			states.push(snapshot(&board));
+		}
+		assert_eq!(board.winner, Some(TOOT));
+
+		for state in states.iter().rev().skip(1) {
+			assert!(board.undo());
+			assert_eq!(&snapshot(&board), state);
+		}
+		assert!(!board.undo());
+
+		for state in states.iter().skip(1) {
+			assert!(board.redo());
+			assert_eq!(&snapshot(&board), state);
+		}
+		assert!(!board.redo());
+
+		// A new move replaces the moves that were undone
+		board.undo();
+		board.undo();
+		board.drop(T, 5).unwrap();
+		assert!(!board.can_redo());
+		assert!(!board.redo());
+		assert_eq!(board.history_len, board.moves_played);
+	}
+}
@@ -0,0 +1,92 @@
+use crate::engine::weights::{format_weights, parse_weights};
+use std::fmt::{Display, Formatter, Result};
+
+/// How much each kind of window counts towards `TootAndOtto::calculate_score`
+///
+/// A window is a run of four cells, and only scores when its letters fit just
+/// one of the players' words
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct TootAndOttoWeights {
+	/// Two letters of the player's word in place
+	pub line_of_two: i32,
+	/// Three letters of the player's word in place
+	pub line_of_three: i32,
+	/// The player's whole word, the game is won
+	pub line_of_four: i32,
+	/// The opponent is one letter short of their word, with the missing cell
+	/// not playable yet
+	pub opponent_line_of_three: i32,
+	/// The opponent can finish their word with their next letter
+	pub opponent_line_of_three_with_below: i32,
+	/// The opponent has two letters of their word in place
+	pub opponent_line_of_two: i32,
+}
+
+impl TootAndOttoWeights {
+	/// The names of the weights in a weights file, in the order of `values`
+	pub const NAMES: [&'static str; 6] = [
+		"line_of_two",
+		"line_of_three",
+		"line_of_four",
+		"opponent_line_of_three",
+		"opponent_line_of_three_with_below",
+		"opponent_line_of_two",
+	];
+
+	/// The weights the CPU has always played with
+	pub const DEFAULT: Self = TootAndOttoWeights {
+		line_of_two: 1,
+		line_of_three: 10,
+		line_of_four: 100_000,
+		opponent_line_of_three: -20,
+		opponent_line_of_three_with_below: -10_000,
+		opponent_line_of_two: -3,
+	};
+
+	/// Every weight, in the order of `NAMES`
+	pub fn values(&self) -> [i32; 6] {
+		[
+			self.line_of_two,
+			self.line_of_three,
+			self.line_of_four,
+			self.opponent_line_of_three,
+			self.opponent_line_of_three_with_below,
+			self.opponent_line_of_two,
+		]
+	}
+
+	/// Builds weights from values in the order of `NAMES`
+	pub fn from_values(values: [i32; 6]) -> Self {
+		TootAndOttoWeights {
+			line_of_two: values[0],
+			line_of_three: values[1],
+			line_of_four: values[2],
+			opponent_line_of_three: values[3],
+			opponent_line_of_three_with_below: values[4],
+			opponent_line_of_two: values[5],
+		}
+	}
+
+	/// Reads weights written by `Display`, any weight left out keeps its
+	/// default value
+	#[allow(dead_code)] // Weights are only read from files by the CLI tools
+	pub fn parse(text: &str) -> std::result::Result<Self, String> {
+		let mut values = TootAndOttoWeights::DEFAULT.values();
+		parse_weights(text, &TootAndOttoWeights::NAMES, &mut values)?;
+
+		Ok(TootAndOttoWeights::from_values(values))
+	}
+}
+
+impl Default for TootAndOttoWeights {
+	fn default() -> Self {
+		TootAndOttoWeights::DEFAULT
+	}
+}
+
+impl Display for TootAndOttoWeights {
+	/// Prints out every weight on its own line, like `line_of_two = 1`
+	fn fmt(&self, f: &mut Formatter) -> Result {
+		f.write_str(&format_weights(&TootAndOttoWeights::NAMES, &self.values()))
+	}
+}
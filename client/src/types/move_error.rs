@@ -0,0 +1,38 @@
+use std::fmt::{Display, Formatter, Result};
+
+/// The reasons a piece can't be dropped into, or popped out of, the board
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum MoveError {
+	/// The column isn't on the board
+	ColumnOutOfRange(usize),
+	/// The column has no room left for another piece
+	ColumnFull(usize),
+	/// The player has already dropped every piece of the letter they picked
+	NoPiecesLeft,
+	/// Someone has already won or the board is full
+	GameOver,
+	/// The bottom piece of the column isn't the player's own, or there is none
+	CannotPop(usize),
+	/// Pieces can only be popped out in the Pop Out variant
+	PopOutNotAllowed,
+}
+
+impl Display for MoveError {
+	/// Prints out why the move was refused
+	fn fmt(&self, f: &mut Formatter) -> Result {
+		match self {
+			MoveError::ColumnOutOfRange(col) => write!(f, "Column {} isn't on the board", col),
+			MoveError::ColumnFull(col) => write!(f, "Column {} is full", col),
+			MoveError::NoPiecesLeft => write!(f, "There are no pieces of that letter left"),
+			MoveError::GameOver => write!(f, "The game is already over"),
+			MoveError::CannotPop(col) => write!(
+				f,
+				"There's none of your pieces at the bottom of column {}",
+				col
+			),
+			MoveError::PopOutNotAllowed => {
+				write!(f, "Pieces can only be popped out in Pop Out")
+			}
+		}
+	}
+}
@@ -1,3 +1,4 @@
+use crate::engine::{mcts::MctsLimits, minimax::SearchLimits};
 use std::fmt::{Display, Formatter, Result};
 use strum_macros::EnumIter;
 
@@ -7,16 +8,48 @@ pub enum Opponent {
 	EasyCPU,
 	MediumCPU,
 	HardCPU,
+	PerfectCPU,
+	MctsCPU,
+}
+
+impl Opponent {
+	/// How hard the CPU searches for its moves, `None` when playing a human or
+	/// when the CPU solves the game or plays out games instead of searching a
+	/// limited depth
+	pub fn search_limits(&self) -> Option<SearchLimits> {
+		match self {
+			Opponent::Human | Opponent::PerfectCPU | Opponent::MctsCPU => None,
+			Opponent::EasyCPU => Some(SearchLimits::depth(2)),
+			Opponent::MediumCPU => Some(SearchLimits::depth(4)),
+			// Searches as deep as it can before running out of nodes
+			Opponent::HardCPU => Some(SearchLimits {
+				depth: usize::MAX,
+				nodes: Some(250_000),
+				millis: None,
+			}),
+		}
+	}
+
+	/// How many games the CPU plays out to pick its moves, `None` unless it
+	/// picks them with Monte Carlo tree search
+	pub fn mcts_limits(&self) -> Option<MctsLimits> {
+		match self {
+			Opponent::MctsCPU => Some(MctsLimits::playouts(20_000)),
+			_ => None,
+		}
+	}
 }
 
 impl Display for Opponent {
 	/// Prints out the piece color
 	fn fmt(&self, f: &mut Formatter) -> Result {
 		match self {
-			Opponent::Human => write!(f, "{}", "Human"),
-			Opponent::EasyCPU => write!(f, "{}", "Easy"),
-			Opponent::MediumCPU => write!(f, "{}", "Medium"),
-			Opponent::HardCPU => write!(f, "{}", "Hard"),
+			Opponent::Human => f.write_str("Human"),
+			Opponent::EasyCPU => f.write_str("Easy"),
+			Opponent::MediumCPU => f.write_str("Medium"),
+			Opponent::HardCPU => f.write_str("Hard"),
+			Opponent::PerfectCPU => f.write_str("Perfect"),
+			Opponent::MctsCPU => f.write_str("MCTS"),
 		}
 	}
 }
@@ -30,6 +63,8 @@ impl PartialEq for Opponent {
 			(EasyCPU, EasyCPU) => true,
 			(MediumCPU, MediumCPU) => true,
 			(HardCPU, HardCPU) => true,
+			(PerfectCPU, PerfectCPU) => true,
+			(MctsCPU, MctsCPU) => true,
 			_ => false,
 		}
 	}
@@ -0,0 +1,40 @@
+use super::opponent::Opponent;
+use std::fmt::Display;
+
+/// Two CPUs playing each other while the user watches
+#[derive(Copy, Clone, PartialEq)]
+pub struct Spectator {
+	/// The CPU playing each side, the side that moves first first
+	pub engines: [Opponent; 2],
+}
+
+impl Spectator {
+	/// Describes how the game ended, like `Red (Hard) beat Yellow (Easy) in 21
+	/// moves`, given the names of the sides and the index of the winner
+	pub fn summary<S: Display>(
+		&self,
+		sides: [S; 2],
+		winner: Option<usize>,
+		moves: usize,
+	) -> String {
+		let name = |index: usize| format!("{} ({})", sides[index], self.engines[index]);
+
+		match winner {
+			Some(index) => format!(
+				"{} beat {} in {} moves",
+				name(index),
+				name(1 - index),
+				moves
+			),
+			None => format!("{} and {} drew after {} moves", name(0), name(1), moves),
+		}
+	}
+}
+
+impl Default for Spectator {
+	fn default() -> Self {
+		Spectator {
+			engines: [Opponent::HardCPU, Opponent::HardCPU],
+		}
+	}
+}
@@ -0,0 +1,416 @@
+//! Tunes the weights the CPU scores positions with against solved positions
+//!
+//! Run from `client/tournament` with the game to tune:
+//!
+//! ```text
+//! cargo run --release --bin tune -- connect4 --positions 2000 --out weights.txt
+//! ```
+//!
+//! The positions come from games the easy CPU plays against itself after a
+//! couple of random moves, and the solver works out who wins each of them. The
+//! weights are then nudged one at a time for as long as the scores they give
+//! the positions get closer to the real results. `--from` is the earliest move
+//! positions are taken from, Connect 4 positions take much longer to solve the
+//! earlier they are. `--weights` starts from the weights in a file rather than
+//! the default ones, and the tuned weights are written to `--out` or printed.
+//!
+//! Only the standard boards can be solved, so the weights are tuned for those.
+use rand::{seq::SliceRandom, thread_rng};
+use std::{convert::TryInto, env, fs, process, time::Instant};
+
+// The games are shared with the web client and the CLI, which use the parts
+// of them tuning doesn't need, like redoing moves and setting up positions
+#[allow(dead_code)]
+#[path = "../../../src/connect4"]
+mod connect4 {
+    pub mod connect4;
+    pub mod connect4_move;
+    pub mod cpu_con4;
+    pub mod piece_color;
+    pub mod rules;
+    pub mod solver;
+    pub mod weights;
+}
+use connect4::{connect4::Connect4, cpu_con4, solver::Solver, weights::Connect4Weights};
+
+#[allow(dead_code)]
+#[path = "../../../src/toot_and_otto"]
+mod toot_and_otto {
+    pub mod cpu_toot;
+    pub mod piece_letter;
+    pub mod player;
+    pub mod rules;
+    pub mod solver;
+    pub mod toot_and_otto;
+    pub mod weights;
+}
+use toot_and_otto::{cpu_toot, toot_and_otto::TootAndOtto, weights::TootAndOttoWeights};
+
+#[allow(dead_code)]
+#[path = "../../../src/engine"]
+mod engine {
+    pub mod config;
+    pub mod game;
+    pub mod mcts;
+    pub mod minimax;
+    pub mod transposition;
+    pub mod weights;
+    pub mod zobrist;
+}
+use engine::{
+    config::EngineConfig,
+    game::{Game, Outcome},
+    weights::{format_weights, parse_weights},
+};
+
+#[path = "../../../src/types"]
+mod types {
+    pub mod move_error;
+    pub mod opponent;
+}
+use types::opponent::Opponent;
+
+const USAGE: &str =
+    "Usage: tune <connect4|toot> [--positions N] [--from MOVE] [--weights FILE] [--out FILE]";
+
+/// How many random moves start each self-play game
+const RANDOM_MOVES: usize = 2;
+
+/// The most rounds of nudging every weight before the tuner gives up
+const MAX_ROUNDS: usize = 200;
+
+/// What the tuner was asked to do
+struct Options {
+    game: String,
+    /// How many solved positions the weights are tuned against
+    positions: usize,
+    /// The earliest move positions are taken from, `None` for the game's default
+    from: Option<usize>,
+    /// The weights file to start from, `None` for the default weights
+    weights: Option<String>,
+    /// The file to write the tuned weights to, `None` to print them
+    out: Option<String>,
+}
+
+/// Reads the command line arguments, not counting the program name
+fn parse_args(args: &[String]) -> Result<Options, String> {
+    let mut options = Options {
+        game: String::new(),
+        positions: 1000,
+        from: None,
+        weights: None,
+        out: None,
+    };
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .ok_or_else(|| format!("{} needs a value after it", arg))
+        };
+
+        match arg.as_str() {
+            "--positions" => {
+                options.positions = value()?
+                    .parse()
+                    .map_err(|_| String::from("--positions needs a number of positions"))?
+            }
+            "--from" => {
+                options.from = Some(
+                    value()?
+                        .parse()
+                        .map_err(|_| String::from("--from needs a move number"))?,
+                )
+            }
+            "--weights" => options.weights = Some(value()?.clone()),
+            "--out" => options.out = Some(value()?.clone()),
+            game if options.game.is_empty() => options.game = String::from(game),
+            other => return Err(format!("'{}' isn't an option", other)),
+        }
+    }
+
+    if options.game.is_empty() {
+        return Err(String::from("Pick a game to tune"));
+    }
+
+    Ok(options)
+}
+
+/// A position and how it ends with perfect play, from the point of view of
+/// the player to move
+struct Sample<G> {
+    board: G,
+    /// 1 for a win, 0.5 for a draw and 0 for a loss
+    result: f64,
+}
+
+/// Plays games of the CPU against itself from `board` and solves a position
+/// from each one, picked from the moves after `from`
+fn sample_positions<G: Game>(
+    board: &G,
+    count: usize,
+    from: usize,
+    pick_move: impl Fn(&G) -> G::Move,
+    mut solve: impl FnMut(&G) -> Outcome,
+) -> Vec<Sample<G>> {
+    let mut rng = thread_rng();
+    let mut samples = Vec::with_capacity(count);
+
+    while samples.len() < count {
+        let mut game = board.clone();
+        let mut positions = vec![];
+
+        for ply in 0.. {
+            if game.is_terminal() {
+                break;
+            }
+            if ply >= from {
+                positions.push(game.clone());
+            }
+
+            let mv = match ply < RANDOM_MOVES {
+                true => *game
+                    .legal_moves()
+                    .choose(&mut rng)
+                    .expect("A game that isn't over has moves"),
+                false => pick_move(&game),
+            };
+            game.apply(mv);
+        }
+
+        if let Some(position) = positions.choose(&mut rng) {
+            let result = match solve(position) {
+                Outcome::Win => 1.0,
+                Outcome::Draw => 0.5,
+                Outcome::Loss => 0.0,
+            };
+            samples.push(Sample {
+                board: position.clone(),
+                result,
+            });
+
+            if samples.len() % 100 == 0 {
+                println!("Solved {} of {} positions", samples.len(), count);
+            }
+        }
+    }
+
+    samples
+}
+
+/// The chance of winning a score suggests, where a score of `scale` is worth
+/// ten to one odds
+fn win_chance(score: i32, scale: f64) -> f64 {
+    1.0 / (1.0 + 10f64.powf(-score as f64 / scale))
+}
+
+/// The mean squared difference between the winning chances the weights give
+/// each sample and its real result
+fn error<G>(
+    samples: &[Sample<G>],
+    values: &[i32],
+    scale: f64,
+    evaluate: &impl Fn(&G, &[i32]) -> i32,
+) -> f64 {
+    let total: f64 = samples
+        .iter()
+        .map(|sample| (sample.result - win_chance(evaluate(&sample.board, values), scale)).powi(2))
+        .sum();
+
+    total / samples.len() as f64
+}
+
+/// Finds the scale that makes the scores from `values` line up best with the
+/// results, trying ten scales for every power of ten
+fn fit_scale<G>(
+    samples: &[Sample<G>],
+    values: &[i32],
+    evaluate: &impl Fn(&G, &[i32]) -> i32,
+) -> f64 {
+    (0..60)
+        .map(|step| 10f64.powf(step as f64 / 10.0))
+        .map(|scale| (scale, error(samples, values, scale, evaluate)))
+        .min_by(|a, b| a.1.partial_cmp(&b.1).expect("Errors are never NaN"))
+        .expect("There are scales to try")
+        .0
+}
+
+/// Nudges each weight up and down for as long as that lowers the error
+///
+/// Each weight starts with a step of half its value, which doubles whenever a
+/// step pays off and halves whenever it doesn't, so the weights can move far
+/// quickly and settle precisely
+fn tune<G>(
+    samples: &[Sample<G>],
+    mut values: Vec<i32>,
+    names: &[&str],
+    evaluate: impl Fn(&G, &[i32]) -> i32,
+) -> Vec<i32> {
+    let scale = fit_scale(samples, &values, &evaluate);
+    let mut best = error(samples, &values, scale, &evaluate);
+    println!("Scale {:.0}, error {:.5} before tuning", scale, best);
+
+    let mut steps: Vec<i32> = values
+        .iter()
+        .map(|value| (value.abs() / 2).max(1))
+        .collect();
+    for round in 1..=MAX_ROUNDS {
+        let mut improved = false;
+
+        for index in 0..values.len() {
+            let mut moved = false;
+            for &direction in &[1, -1] {
+                let mut tried = values.clone();
+                tried[index] += direction * steps[index];
+
+                let tried_error = error(samples, &tried, scale, &evaluate);
+                if tried_error < best {
+                    best = tried_error;
+                    values = tried;
+                    moved = true;
+                    break;
+                }
+            }
+
+            steps[index] = match moved {
+                true => steps[index].saturating_mul(2),
+                false => (steps[index] / 2).max(1),
+            };
+            improved |= moved;
+        }
+
+        println!("Round {}, error {:.5}", round, best);
+        if !improved && steps.iter().all(|&step| step == 1) {
+            break;
+        }
+    }
+
+    println!("Error {:.5} after tuning\n", best);
+    print!("{}", format_weights(names, &values));
+    values
+}
+
+/// Reads the weights file the tuner starts from into `values`
+fn read_weights(path: &Option<String>, names: &[&str], values: &mut [i32]) -> Result<(), String> {
+    if let Some(path) = path {
+        let text =
+            fs::read_to_string(path).map_err(|err| format!("Couldn't read {}: {}", path, err))?;
+        parse_weights(&text, names, values).map_err(|err| format!("{}: {}", path, err))?;
+    }
+
+    Ok(())
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let options = match parse_args(&args) {
+        Ok(options) => options,
+        Err(err) => {
+            eprintln!("{}\n\n{}", err, USAGE);
+            process::exit(1);
+        }
+    };
+
+    let start = Instant::now();
+    let tuned = match options.game.as_str() {
+        "connect4" => {
+            let mut values = Connect4Weights::DEFAULT.values();
+            if let Err(err) = read_weights(&options.weights, &Connect4Weights::NAMES, &mut values) {
+                eprintln!("{}", err);
+                process::exit(1);
+            }
+
+            let board = Connect4::new();
+            let mut solver = Solver::new();
+            let samples = sample_positions(
+                &board,
+                options.positions,
+                options.from.unwrap_or(14),
+                |board| {
+                    cpu_con4::pick_move(
+                        *board,
+                        board.active_player,
+                        Opponent::EasyCPU,
+                        EngineConfig::DEFAULT,
+                    )
+                    .expect("The CPU moves on its own turn")
+                },
+                |board| solver.solve(board).outcome,
+            );
+
+            let tuned = tune(
+                &samples,
+                values.to_vec(),
+                &Connect4Weights::NAMES,
+                |board, values| {
+                    let mut board = *board;
+                    board.weights = Connect4Weights::from_values(
+                        values.try_into().expect("Connect 4 has 7 weights"),
+                    );
+                    board.calculate_score(board.active_player)
+                },
+            );
+            Connect4Weights::from_values(tuned[..].try_into().expect("Connect 4 has 7 weights"))
+                .to_string()
+        }
+        "toot" => {
+            let mut values = TootAndOttoWeights::DEFAULT.values();
+            if let Err(err) =
+                read_weights(&options.weights, &TootAndOttoWeights::NAMES, &mut values)
+            {
+                eprintln!("{}", err);
+                process::exit(1);
+            }
+
+            let board = TootAndOtto::new();
+            let mut solver = toot_and_otto::solver::Solver::new();
+            let samples = sample_positions(
+                &board,
+                options.positions,
+                options.from.unwrap_or(4),
+                |board| {
+                    cpu_toot::pick_move(
+                        *board,
+                        board.active_player,
+                        Opponent::EasyCPU,
+                        EngineConfig::DEFAULT,
+                    )
+                    .expect("The CPU moves on its own turn")
+                },
+                |board| solver.solve(board).outcome,
+            );
+
+            let tuned = tune(
+                &samples,
+                values.to_vec(),
+                &TootAndOttoWeights::NAMES,
+                |board, values| {
+                    let mut board = *board;
+                    board.weights = TootAndOttoWeights::from_values(
+                        values.try_into().expect("TOOT and OTTO has 6 weights"),
+                    );
+                    board.calculate_score(board.active_player)
+                },
+            );
+            TootAndOttoWeights::from_values(
+                tuned[..].try_into().expect("TOOT and OTTO has 6 weights"),
+            )
+            .to_string()
+        }
+        game => {
+            eprintln!("'{}' isn't a game, use connect4 or toot\n\n{}", game, USAGE);
+            process::exit(1);
+        }
+    };
+
+    println!("Tuned in {:.1}s", start.elapsed().as_secs_f64());
+    if let Some(path) = &options.out {
+        match fs::write(path, tuned) {
+            Ok(()) => println!("Wrote the weights to {}", path),
+            Err(err) => {
+                eprintln!("Couldn't write {}: {}", path, err);
+                process::exit(1);
+            }
+        }
+    }
+}
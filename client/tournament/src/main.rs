@@ -0,0 +1,588 @@
+//! Plays CPUs against each other to tell which one is stronger
+//!
+//! Run from `client/tournament` with the game and two or more engines:
+//!
+//! ```text
+//! cargo run --release -- connect4 hard depth=4 nodes=50000 --games 40
+//! ```
+//!
+//! An engine is a CPU level, `easy`, `medium`, `hard`, `perfect` or `mcts`, or
+//! search limits like `depth=6` or `nodes=100000,millis=200`. Monte Carlo
+//! engines are limited by playouts instead, like `playouts=5000` or
+//! `mcts,millis=200`. Every pair of engines plays `--games` games, taking turns
+//! moving first, and each opening of `--openings` random moves is played once
+//! from either side so neither engine gets the better openings. `--rules` plays
+//! on another board, like `--rules "9x6 connect 5"` or
+//! `--rules "8x6 with 12 of each letter"`.
+//!
+//! Adding `weights=FILE` to an engine, like `hard,weights=tuned.txt`, has it
+//! score positions with weights written by the `tune` binary. `tie-break=` and
+//! `temperature=` change how it picks between the moves it scored, like
+//! `medium,tie-break=center-first` or `hard,temperature=20`.
+//!
+//! The perfect CPU solves the standard boards, and only plays on those.
+use rand::{seq::SliceRandom, thread_rng};
+use std::{env, fs, process, time::Instant};
+
+// The games are shared with the web client and the CLI, which use the parts
+// of them a tournament doesn't need, like redoing moves and setting up positions
+#[allow(dead_code)]
+#[path = "../../src/connect4"]
+mod connect4 {
+    pub mod connect4;
+    pub mod connect4_move;
+    pub mod cpu_con4;
+    pub mod piece_color;
+    pub mod rules;
+    pub mod solver;
+    pub mod weights;
+}
+use connect4::{connect4::Connect4, cpu_con4, rules::Connect4Rules, weights::Connect4Weights};
+
+#[allow(dead_code)]
+#[path = "../../src/toot_and_otto"]
+mod toot_and_otto {
+    pub mod cpu_toot;
+    pub mod piece_letter;
+    pub mod player;
+    pub mod rules;
+    pub mod solver;
+    pub mod toot_and_otto;
+    pub mod weights;
+}
+use toot_and_otto::{
+    cpu_toot, rules::TootAndOttoRules, toot_and_otto::TootAndOtto, weights::TootAndOttoWeights,
+};
+
+#[allow(dead_code)]
+#[path = "../../src/engine"]
+mod engine {
+    pub mod config;
+    pub mod game;
+    pub mod mcts;
+    pub mod minimax;
+    pub mod transposition;
+    pub mod weights;
+    pub mod zobrist;
+}
+use engine::{
+    config::{EngineConfig, TieBreak},
+    game::Game,
+    mcts::MctsLimits,
+    minimax::SearchLimits,
+};
+
+#[path = "../../src/types"]
+mod types {
+    pub mod move_error;
+    pub mod opponent;
+}
+use types::opponent::Opponent;
+
+const USAGE: &str = "Usage: tournament <connect4|toot> <engine> <engine>... [--games N] [--openings N] [--rules RULES]
+An engine is easy, medium, hard, perfect, mcts, or limits like depth=6, nodes=100000,millis=200 or playouts=5000,
+with weights=FILE, tie-break=POLICY or temperature=T added to change how it scores and picks moves";
+
+/// A CPU taking part in the tournament
+#[derive(Clone)]
+struct Engine {
+    /// The engine as it was written on the command line
+    name: String,
+    /// How hard the engine searches, `None` when it plays perfectly or with
+    /// Monte Carlo tree search
+    limits: Option<SearchLimits>,
+    /// How many games the engine plays out, `None` unless it uses Monte Carlo
+    /// tree search
+    mcts: Option<MctsLimits>,
+    /// The text of the weights file the engine scores positions with, `None`
+    /// for the default weights
+    weights: Option<String>,
+    /// How the engine picks between the moves it scored
+    config: EngineConfig,
+}
+
+impl Engine {
+    /// Reads an engine like `hard`, `depth=6,nodes=100000`, `playouts=5000`,
+    /// `hard,weights=tuned.txt` or `medium,temperature=20`
+    fn parse(text: &str) -> Result<Self, String> {
+        let mut engine = Engine {
+            name: String::from(text),
+            limits: None,
+            mcts: None,
+            weights: None,
+            config: EngineConfig::DEFAULT,
+        };
+        let mut limits = SearchLimits {
+            depth: usize::MAX,
+            nodes: None,
+            millis: None,
+        };
+        let mut mcts = MctsLimits {
+            playouts: None,
+            millis: None,
+        };
+        let mut level = None;
+
+        for setting in text.split(',') {
+            match setting {
+                "easy" => level = Some(Opponent::EasyCPU),
+                "medium" => level = Some(Opponent::MediumCPU),
+                "hard" => level = Some(Opponent::HardCPU),
+                "perfect" => level = Some(Opponent::PerfectCPU),
+                "mcts" => level = Some(Opponent::MctsCPU),
+                _ => {
+                    let (key, value) = setting.split_once('=').ok_or_else(|| {
+                        format!("'{}' isn't an engine, use a level like hard or limits like depth=6", text)
+                    })?;
+
+                    match key {
+                        "weights" => {
+                            let weights = fs::read_to_string(value)
+                                .map_err(|err| format!("Couldn't read {}: {}", value, err))?;
+                            engine.weights = Some(weights);
+                            continue;
+                        }
+                        "tie-break" => {
+                            engine.config.tie_break = TieBreak::parse(value)?;
+                            continue;
+                        }
+                        "temperature" => {
+                            engine.config.temperature = value
+                                .parse()
+                                .map_err(|_| format!("'{}' isn't a number in {}", value, text))?;
+                            continue;
+                        }
+                        _ => {}
+                    }
+
+                    let value: u64 = value
+                        .parse()
+                        .map_err(|_| format!("'{}' isn't a number in {}", value, text))?;
+                    match key {
+                        "depth" => limits.depth = value as usize,
+                        "nodes" => limits.nodes = Some(value),
+                        "millis" => {
+                            limits.millis = Some(value);
+                            mcts.millis = Some(value);
+                        }
+                        "playouts" => mcts.playouts = Some(value),
+                        _ => {
+                            return Err(format!(
+                                "'{}' isn't a search limit, use depth, nodes, millis or playouts",
+                                key
+                            ))
+                        }
+                    }
+                }
+            }
+        }
+
+        if level == Some(Opponent::MctsCPU) || mcts.playouts.is_some() {
+            engine.mcts = match (mcts.playouts, mcts.millis) {
+                (None, None) => Opponent::MctsCPU.mcts_limits(),
+                _ => Some(mcts),
+            };
+        } else {
+            engine.limits = match level {
+                Some(level) => level.search_limits(),
+                None => Some(limits),
+            };
+        }
+        Ok(engine)
+    }
+
+    /// Whether the engine solves its positions rather than searching or
+    /// playing out games
+    fn is_perfect(&self) -> bool {
+        self.limits.is_none() && self.mcts.is_none()
+    }
+}
+
+/// What the tournament was asked to play
+struct Options {
+    game: String,
+    engines: Vec<Engine>,
+    /// How many games each pair of engines plays
+    games: u32,
+    /// How many random moves start each opening
+    openings: usize,
+    /// The rules as text, `None` for the standard ones
+    rules: Option<String>,
+}
+
+/// Reads the command line arguments, not counting the program name
+fn parse_args(args: &[String]) -> Result<Options, String> {
+    let mut options = Options {
+        game: String::new(),
+        engines: Vec::new(),
+        games: 20,
+        openings: 2,
+        rules: None,
+    };
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .ok_or_else(|| format!("{} needs a value after it", arg))
+        };
+
+        match arg.as_str() {
+            "--games" => {
+                options.games = value()?
+                    .parse()
+                    .map_err(|_| String::from("--games needs a number of games"))?
+            }
+            "--openings" => {
+                options.openings = value()?
+                    .parse()
+                    .map_err(|_| String::from("--openings needs a number of moves"))?
+            }
+            "--rules" => options.rules = Some(value()?.clone()),
+            game if options.game.is_empty() => options.game = String::from(game),
+            engine => options.engines.push(Engine::parse(engine)?),
+        }
+    }
+
+    if options.engines.len() < 2 {
+        return Err(String::from("A tournament needs at least two engines"));
+    }
+
+    Ok(options)
+}
+
+/// The wins, draws and losses of one engine against another
+#[derive(Copy, Clone, Default)]
+struct Score {
+    wins: u32,
+    draws: u32,
+    losses: u32,
+}
+
+impl Score {
+    fn games(&self) -> u32 {
+        self.wins + self.draws + self.losses
+    }
+
+    /// The share of the points won, with a draw worth half a point
+    fn points(&self) -> f64 {
+        (self.wins as f64 + self.draws as f64 / 2.0) / self.games() as f64
+    }
+
+    /// Whether one engine won every game
+    fn is_sweep(&self) -> bool {
+        self.wins == self.games() || self.losses == self.games()
+    }
+
+    /// The Elo difference the score suggests, and the margin of its 95%
+    /// confidence interval
+    ///
+    /// A clean sweep would suggest an endless difference, so half a point is
+    /// added to each side first to keep the estimate bounded
+    fn elo(&self) -> (f64, f64) {
+        let extra = if self.is_sweep() { 0.5 } else { 0.0 };
+        let wins = self.wins as f64 + extra;
+        let draws = self.draws as f64;
+        let losses = self.losses as f64 + extra;
+        let games = wins + draws + losses;
+        let points = (wins + draws / 2.0) / games;
+
+        // The spread of the points won in a single game
+        let variance = (wins * (1.0 - points).powi(2)
+            + draws * (0.5 - points).powi(2)
+            + losses * points.powi(2))
+            / games;
+        let margin = 1.96 * (variance / games).sqrt();
+
+        let low = elo_difference(points - margin);
+        let high = elo_difference(points + margin);
+        (elo_difference(points), (high - low) / 2.0)
+    }
+}
+
+/// The Elo difference at which a player is expected to win `points` of the
+/// points, which is endless for a clean sweep either way
+fn elo_difference(points: f64) -> f64 {
+    match points {
+        p if p <= 0.0 => f64::NEG_INFINITY,
+        p if p >= 1.0 => f64::INFINITY,
+        p => -400.0 * (1.0 / p - 1.0).log10(),
+    }
+}
+
+/// Plays `plies` random moves on `board`, starting over whenever the game ends
+/// before they have all been played
+fn random_opening<G: Game>(board: &G, plies: usize) -> G {
+    let mut rng = thread_rng();
+
+    loop {
+        let mut opening = board.clone();
+        for _ in 0..plies {
+            match opening.legal_moves().choose(&mut rng) {
+                Some(&mv) if !opening.is_terminal() => opening.apply(mv),
+                _ => break,
+            };
+        }
+
+        if !opening.is_terminal() || plies == 0 {
+            return opening;
+        }
+    }
+}
+
+/// Plays out `board` with `engines[0]` playing `first_player`, returning the
+/// index of the engine that won or `None` for a draw
+fn play_game<G: Game>(
+    mut board: G,
+    first_player: G::Player,
+    engines: [&Engine; 2],
+    pick_move: &impl Fn(&G, &Engine) -> G::Move,
+) -> Option<usize> {
+    while !board.is_terminal() {
+        let engine = match board.active_player() == first_player {
+            true => engines[0],
+            false => engines[1],
+        };
+        let mv = pick_move(&board, engine);
+        board.apply(mv);
+    }
+
+    board
+        .winner()
+        .map(|winner| if winner == first_player { 0 } else { 1 })
+}
+
+/// Plays every pair of engines against each other from the empty `board`,
+/// returning the score of each engine against every other one
+fn run<G: Game>(
+    board: &G,
+    options: &Options,
+    pick_move: impl Fn(&G, &Engine) -> G::Move,
+) -> Vec<Vec<Score>> {
+    let engines = &options.engines;
+    let mut scores = vec![vec![Score::default(); engines.len()]; engines.len()];
+    let first_player = board.active_player();
+
+    for a in 0..engines.len() {
+        for b in a + 1..engines.len() {
+            let start = Instant::now();
+            let mut opening = board.clone();
+
+            for game in 0..options.games {
+                // Each opening is played once with either engine moving first
+                let (first, second) = match game % 2 {
+                    0 => {
+                        opening = random_opening(board, options.openings);
+                        (a, b)
+                    }
+                    _ => (b, a),
+                };
+
+                let players = [&engines[first], &engines[second]];
+                match play_game(opening.clone(), first_player, players, &pick_move) {
+                    Some(0) => {
+                        scores[first][second].wins += 1;
+                        scores[second][first].losses += 1;
+                    }
+                    Some(_) => {
+                        scores[first][second].losses += 1;
+                        scores[second][first].wins += 1;
+                    }
+                    None => {
+                        scores[first][second].draws += 1;
+                        scores[second][first].draws += 1;
+                    }
+                }
+            }
+
+            let score = scores[a][b];
+            println!(
+                "{} vs {}: {}-{}-{} in {:.1}s",
+                engines[a].name,
+                engines[b].name,
+                score.wins,
+                score.draws,
+                score.losses,
+                start.elapsed().as_secs_f64()
+            );
+        }
+    }
+
+    scores
+}
+
+/// Writes an Elo difference with its sign, or `inf` when it is endless
+fn format_elo(elo: f64) -> String {
+    match elo.is_finite() {
+        true => format!("{:+.0}", elo),
+        false => format!("{}inf", if elo > 0.0 { "+" } else { "-" }),
+    }
+}
+
+/// Prints each engine's wins, draws and losses against every other one, then
+/// the Elo difference between each pair
+fn print_report(engines: &[Engine], scores: &[Vec<Score>]) {
+    let width = engines
+        .iter()
+        .map(|engine| engine.name.len())
+        .max()
+        .unwrap_or_default()
+        .max(8)
+        + 2;
+
+    println!();
+    println!("Wins-draws-losses of each row against each column");
+    print!("{:width$}", "", width = width);
+    for engine in engines {
+        print!("{:width$}", engine.name, width = width);
+    }
+    println!();
+
+    for (a, engine) in engines.iter().enumerate() {
+        print!("{:width$}", engine.name, width = width);
+        for (b, score) in scores[a].iter().enumerate() {
+            let cell = match a == b {
+                true => String::from("-"),
+                false => format!("{}-{}-{}", score.wins, score.draws, score.losses),
+            };
+            print!("{:width$}", cell, width = width);
+        }
+        println!();
+    }
+
+    println!();
+    for a in 0..engines.len() {
+        for b in a + 1..engines.len() {
+            let score = scores[a][b];
+            if score.games() == 0 {
+                continue;
+            }
+
+            let (elo, margin) = score.elo();
+            let mut notes = vec![];
+            if score.is_sweep() {
+                notes.push("a clean sweep, Elo counts half a point more for each side");
+            }
+            if !margin.is_finite() {
+                notes.push("too few games to bound the margin");
+            }
+
+            println!(
+                "{} vs {}: {:.1}% of the points, Elo {} \u{b1} {}{}",
+                engines[a].name,
+                engines[b].name,
+                score.points() * 100.0,
+                format_elo(elo),
+                format_elo(margin).trim_start_matches('+'),
+                match notes.is_empty() {
+                    true => String::new(),
+                    false => format!(" ({})", notes.join(", ")),
+                }
+            );
+        }
+    }
+}
+
+/// Exits with an error when the weights of any engine can't be read by `parse`
+fn check_weights<W>(engines: &[Engine], parse: impl Fn(&str) -> Result<W, String>) {
+    for engine in engines {
+        if let Some(Err(err)) = engine.weights.as_deref().map(&parse) {
+            eprintln!("{}: {}", engine.name, err);
+            process::exit(1);
+        }
+    }
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let options = match parse_args(&args) {
+        Ok(options) => options,
+        Err(err) => {
+            eprintln!("{}\n\n{}", err, USAGE);
+            process::exit(1);
+        }
+    };
+
+    let scores = match options.game.as_str() {
+        "connect4" => {
+            let rules = match &options.rules {
+                Some(rules) => Connect4Rules::parse(rules),
+                None => Ok(Connect4Rules::STANDARD),
+            };
+            let board = match rules {
+                Ok(rules) => Connect4::with_rules(rules),
+                Err(err) => {
+                    eprintln!("{}", err);
+                    process::exit(1);
+                }
+            };
+
+            check_weights(&options.engines, Connect4Weights::parse);
+            if options.engines.iter().any(Engine::is_perfect)
+                && !cpu_con4::can_play(Opponent::PerfectCPU, board.rules)
+            {
+                eprintln!("The perfect CPU only plays the {} board", Connect4Rules::STANDARD);
+                process::exit(1);
+            }
+
+            println!("Connect 4 on {}, {} games a pairing", board.rules, options.games);
+            run(&board, &options, |board, engine| {
+                let mut board = *board;
+                if let Some(weights) = &engine.weights {
+                    board.weights = Connect4Weights::parse(weights).expect("The weights were checked up front");
+                }
+
+                let side = board.active_player;
+                match (engine.limits, engine.mcts) {
+                    (Some(limits), _) => cpu_con4::make_move(board, side, limits, engine.config),
+                    (None, Some(mcts)) => cpu_con4::make_mcts_move(board, side, mcts, engine.config),
+                    (None, None) => cpu_con4::make_perfect_move(board, side, engine.config),
+                }
+                .expect("The engine moves on its own turn")
+            })
+        }
+        "toot" => {
+            let rules = match &options.rules {
+                Some(rules) => TootAndOttoRules::parse(rules),
+                None => Ok(TootAndOttoRules::STANDARD),
+            };
+            let board = match rules {
+                Ok(rules) => TootAndOtto::with_rules(rules),
+                Err(err) => {
+                    eprintln!("{}", err);
+                    process::exit(1);
+                }
+            };
+
+            check_weights(&options.engines, TootAndOttoWeights::parse);
+            if options.engines.iter().any(Engine::is_perfect)
+                && !cpu_toot::can_play(Opponent::PerfectCPU, board.rules)
+            {
+                eprintln!("The perfect CPU only plays {}", TootAndOttoRules::STANDARD);
+                process::exit(1);
+            }
+
+            println!("TOOT and OTTO on {}, {} games a pairing", board.rules, options.games);
+            run(&board, &options, |board, engine| {
+                let mut board = *board;
+                if let Some(weights) = &engine.weights {
+                    board.weights = TootAndOttoWeights::parse(weights).expect("The weights were checked up front");
+                }
+
+                let side = board.active_player;
+                match (engine.limits, engine.mcts) {
+                    (Some(limits), _) => cpu_toot::make_move(board, side, limits, engine.config),
+                    (None, Some(mcts)) => cpu_toot::make_mcts_move(board, side, mcts, engine.config),
+                    (None, None) => cpu_toot::make_perfect_move(board, side, engine.config),
+                }
+                .expect("The engine moves on its own turn")
+            })
+        }
+        game => {
+            eprintln!("'{}' isn't a game, use connect4 or toot\n\n{}", game, USAGE);
+            process::exit(1);
+        }
+    };
+
+    print_report(&options.engines, &scores);
+}
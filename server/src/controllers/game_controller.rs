@@ -13,7 +13,14 @@ use rocket_contrib::json::Json;
 pub fn update_score(score: Json<ScoreUpdate>) -> Json<String> {
 	match MyMongo::new() {
 		// Establish connection
-		Ok(mut db) => match db.update_score(&score.username, score.game, score.win) {
+		Ok(mut db) => match db.update_score(
+			&score.username,
+			score.game,
+			score.win,
+			score.side,
+			score.hinted,
+			score.undone,
+		) {
 			// Update score
 			Ok(res) => {
 				if res {
@@ -40,6 +47,14 @@ pub fn get_scores(username: &RawStr) -> Json<Game> {
 		to_wins: -1,
 		to_loss: -1,
 		to_ties: -1,
+		xo_first: -1,
+		xo_second: -1,
+		to_first: -1,
+		to_second: -1,
+		xo_hinted: -1,
+		to_hinted: -1,
+		xo_undone: -1,
+		to_undone: -1,
 	};
 	match MyMongo::new() {
 		// Establish connection
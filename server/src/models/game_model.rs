@@ -14,6 +14,14 @@ pub struct Game {
 	pub to_wins: i32,
 	pub to_loss: i32,
 	pub to_ties: i32,
+	pub xo_first: i32,  // connect 4 games played as red
+	pub xo_second: i32, // connect 4 games played as yellow
+	pub to_first: i32,  // toot and otto games played as toot
+	pub to_second: i32, // toot and otto games played as otto
+	pub xo_hinted: i32, // connect 4 games played with hints
+	pub to_hinted: i32, // toot and otto games played with hints
+	pub xo_undone: i32, // connect 4 games with moves taken back
+	pub to_undone: i32, // toot and otto games with moves taken back
 }
 
 // Update score struct
@@ -22,6 +30,12 @@ pub struct ScoreUpdate {
 	pub username: String,
 	pub game: u8, // 0 connect 4, 1 toot
 	pub win: u8,  // 0 loss, 1 win, 2 tie
+	#[serde(default)]
+	pub side: u8, // 0 moved first, 1 moved second
+	#[serde(default)]
+	pub hinted: bool, // hints were shown, so the game isn't ranked
+	#[serde(default)]
+	pub undone: bool, // moves were taken back, so the game isn't ranked
 }
 
 impl MyMongo {
@@ -31,9 +45,37 @@ impl MyMongo {
 		username: &String,
 		game: u8,
 		win: u8,
+		side: u8,
+		hinted: bool,
+		undone: bool,
 	) -> Result<bool, mongodb::error::Error> {
 		let score_db = self.db.collection("scores");
-		let score = match game {
+		// Games played with hints or undos are only counted, never ranked
+		if hinted || undone {
+			let (hinted_key, undone_key) = match game {
+				0 => ("xo_hinted", "xo_undone"),
+				1 => ("to_hinted", "to_undone"),
+				_ => return Ok(false),
+			};
+			// A game can be both hinted and undone, and then counts as both
+			let mut score = doc! {};
+			if hinted {
+				score.insert(hinted_key, 1);
+			}
+			if undone {
+				score.insert(undone_key, 1);
+			}
+			score_db.update_one(
+				doc! {
+					"username": username
+				},
+				doc! {"$inc": score},
+				None,
+			)?;
+			return Ok(true);
+		}
+
+		let mut score = match game {
 			0 => {
 				if win == 1 {
 					doc! {
@@ -66,6 +108,14 @@ impl MyMongo {
 			}
 			_ => return Ok(false),
 		};
+		// Also counts the games played on each side
+		let side = match (game, side) {
+			(0, 0) => "xo_first",
+			(0, _) => "xo_second",
+			(_, 0) => "to_first",
+			(_, _) => "to_second",
+		};
+		score.insert(side, 1);
 		score_db.update_one(
 			doc! {
 				"username": username
@@ -93,6 +143,17 @@ impl MyMongo {
 					to_wins: r.get("to_wins").unwrap().as_i32().unwrap_or(-1),
 					to_loss: r.get("to_loss").unwrap().as_i32().unwrap_or(-1),
 					to_ties: r.get("to_ties").unwrap().as_i32().unwrap_or(-1),
+					// Users from before sides were counted have no side counts
+					xo_first: r.get_i32("xo_first").unwrap_or(0),
+					xo_second: r.get_i32("xo_second").unwrap_or(0),
+					to_first: r.get_i32("to_first").unwrap_or(0),
+					to_second: r.get_i32("to_second").unwrap_or(0),
+					// Nor from before hinted games were counted
+					xo_hinted: r.get_i32("xo_hinted").unwrap_or(0),
+					to_hinted: r.get_i32("to_hinted").unwrap_or(0),
+					// Nor from before games with undos were counted
+					xo_undone: r.get_i32("xo_undone").unwrap_or(0),
+					to_undone: r.get_i32("to_undone").unwrap_or(0),
 				};
 				return Ok(Some(gi));
 			}
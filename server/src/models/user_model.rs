@@ -28,7 +28,15 @@ impl MyMongo {
 			"xo_loss": 0,
 			"to_wins": 0,
 			"to_ties": 0,
-			"to_loss": 0
+			"to_loss": 0,
+			"xo_first": 0,
+			"xo_second": 0,
+			"to_first": 0,
+			"to_second": 0,
+			"xo_hinted": 0,
+			"to_hinted": 0,
+			"xo_undone": 0,
+			"to_undone": 0
 		};
 		score_db.insert_one(score, None)?;
 		Ok(())